@@ -512,6 +512,9 @@ fn collect_sessions(
             history_visibility: None,
             shared_history: false,
             algorithm: RustEventEncryptionAlgorithm::MegolmV1AesSha2,
+            // libolm didn't track when the session was created, nor how much it was used.
+            creation_time: SecondsSinceUnixEpoch(UInt::MIN),
+            use_count: 0,
         };
 
         let session = matrix_sdk_crypto::olm::InboundGroupSession::from_pickle(pickle)?;
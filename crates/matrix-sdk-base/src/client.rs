@@ -14,23 +14,28 @@
 // limitations under the License.
 
 #[cfg(feature = "e2e-encryption")]
-use std::sync::Arc;
+use std::time::Duration;
 use std::{
     collections::{BTreeMap, BTreeSet, HashMap},
     fmt,
     ops::Deref,
+    sync::Arc,
 };
 
 use eyeball::{SharedObservable, Subscriber};
 use eyeball_im::{Vector, VectorDiff};
 use futures_util::Stream;
+use matrix_sdk_common::locks::Mutex as StdMutex;
 #[cfg(feature = "e2e-encryption")]
 use matrix_sdk_crypto::{
     store::DynCryptoStore, types::requests::ToDeviceRequest, CollectStrategy, DecryptionSettings,
     EncryptionSettings, OlmError, OlmMachine, TrustRequirement,
 };
 #[cfg(feature = "e2e-encryption")]
-use ruma::events::room::{history_visibility::HistoryVisibility, member::MembershipState};
+use ruma::events::room::{
+    encryption::RoomEncryptionEventContent, history_visibility::HistoryVisibility,
+    member::MembershipState,
+};
 #[cfg(doc)]
 use ruma::DeviceId;
 use ruma::{
@@ -53,7 +58,7 @@ use tracing::{debug, enabled, info, instrument, warn, Level};
 #[cfg(feature = "e2e-encryption")]
 use crate::RoomMemberships;
 use crate::{
-    deserialized_responses::DisplayName,
+    deserialized_responses::{DisplayName, EventDeserializationError},
     error::{Error, Result},
     event_cache::store::EventCacheStoreLock,
     response_processors::{self as processors, Context},
@@ -115,6 +120,22 @@ pub struct BaseClient {
     #[cfg(feature = "e2e-encryption")]
     pub room_key_recipient_strategy: CollectStrategy,
 
+    /// The maximum duration a room key is used for, when sending encrypted
+    /// messages.
+    ///
+    /// It only applies if it's shorter than the rotation period set in the
+    /// `m.room.encryption` event of the room.
+    #[cfg(feature = "e2e-encryption")]
+    pub room_key_rotation_period: Option<Duration>,
+
+    /// The maximum number of messages a room key is used for, when sending
+    /// encrypted messages.
+    ///
+    /// It only applies if it's lower than the rotation period set in the
+    /// `m.room.encryption` event of the room.
+    #[cfg(feature = "e2e-encryption")]
+    pub room_key_rotation_period_messages: Option<u64>,
+
     /// The settings to use for decrypting events.
     #[cfg(feature = "e2e-encryption")]
     pub decryption_settings: DecryptionSettings,
@@ -122,6 +143,18 @@ pub struct BaseClient {
     /// If the client should handle verification events received when syncing.
     #[cfg(feature = "e2e-encryption")]
     pub handle_verification_events: bool,
+
+    /// Whether the state events that couldn't be deserialized while processing
+    /// a sync response should be kept around, see
+    /// [`BaseClient::last_deserialization_errors`].
+    ///
+    /// Disabled by default.
+    pub collect_deserialization_errors: bool,
+
+    /// The state events that couldn't be deserialized while processing the
+    /// last sync response, if [`Self::collect_deserialization_errors`] is
+    /// enabled.
+    deserialization_errors: Arc<StdMutex<Vec<EventDeserializationError>>>,
 }
 
 #[cfg(not(tarpaulin_include))]
@@ -168,11 +201,17 @@ impl BaseClient {
             #[cfg(feature = "e2e-encryption")]
             room_key_recipient_strategy: Default::default(),
             #[cfg(feature = "e2e-encryption")]
+            room_key_rotation_period: None,
+            #[cfg(feature = "e2e-encryption")]
+            room_key_rotation_period_messages: None,
+            #[cfg(feature = "e2e-encryption")]
             decryption_settings: DecryptionSettings {
                 sender_device_trust_requirement: TrustRequirement::Untrusted,
             },
             #[cfg(feature = "e2e-encryption")]
             handle_verification_events: true,
+            collect_deserialization_errors: false,
+            deserialization_errors: Default::default(),
         }
     }
 
@@ -202,8 +241,12 @@ impl BaseClient {
             ignore_user_list_changes: Default::default(),
             room_info_notable_update_sender: self.room_info_notable_update_sender.clone(),
             room_key_recipient_strategy: self.room_key_recipient_strategy.clone(),
+            room_key_rotation_period: self.room_key_rotation_period,
+            room_key_rotation_period_messages: self.room_key_rotation_period_messages,
             decryption_settings: self.decryption_settings.clone(),
             handle_verification_events,
+            collect_deserialization_errors: self.collect_deserialization_errors,
+            deserialization_errors: Default::default(),
         };
 
         copy.state_store
@@ -236,6 +279,26 @@ impl BaseClient {
         self.state_store.session_meta()
     }
 
+    /// Get the state events that couldn't be deserialized, and have thus been
+    /// ignored, while processing the last sync response.
+    ///
+    /// Timeline events that couldn't be deserialized aren't included, they
+    /// are still part of the timeline as raw events.
+    ///
+    /// This is always empty unless [`Self::collect_deserialization_errors`]
+    /// is enabled.
+    pub fn last_deserialization_errors(&self) -> Vec<EventDeserializationError> {
+        self.deserialization_errors.lock().clone()
+    }
+
+    /// Keep the deserialization errors of the sync response that is being
+    /// processed, replacing the ones of the previous sync response.
+    pub(crate) fn record_deserialization_errors(&self, errors: Vec<EventDeserializationError>) {
+        if self.collect_deserialization_errors {
+            *self.deserialization_errors.lock() = errors;
+        }
+    }
+
     /// Get all the rooms this client knows about.
     pub fn rooms(&self) -> Vec<Room> {
         self.state_store.rooms()
@@ -495,6 +558,10 @@ impl BaseClient {
 
         let mut context = Context::new(StateChanges::new(response.next_batch.clone()));
 
+        if self.collect_deserialization_errors {
+            context = context.with_deserialization_errors();
+        }
+
         #[cfg(feature = "e2e-encryption")]
         let to_device = {
             let processors::e2ee::to_device::Output {
@@ -654,6 +721,10 @@ impl BaseClient {
 
         context.state_changes.ambiguity_maps = ambiguity_cache.cache;
 
+        if let Some(errors) = context.deserialization_errors.take() {
+            self.record_deserialization_errors(errors);
+        }
+
         {
             let _sync_lock = self.sync_lock().lock().await;
 
@@ -894,11 +965,8 @@ impl BaseClient {
 
                 let members = self.state_store.get_user_ids(room_id, filter).await?;
 
-                let settings = EncryptionSettings::new(
-                    room_encryption_event,
-                    history_visibility,
-                    self.room_key_recipient_strategy.clone(),
-                );
+                let settings =
+                    self.room_key_encryption_settings(room_encryption_event, history_visibility);
 
                 Ok(o.share_room_key(room_id, members.iter().map(Deref::deref), settings).await?)
             }
@@ -906,6 +974,32 @@ impl BaseClient {
         }
     }
 
+    /// Get the settings used to share a room key, from the encryption settings
+    /// of the room and the room key settings of this client.
+    #[cfg(feature = "e2e-encryption")]
+    fn room_key_encryption_settings(
+        &self,
+        room_encryption_event: RoomEncryptionEventContent,
+        history_visibility: HistoryVisibility,
+    ) -> EncryptionSettings {
+        let mut settings = EncryptionSettings::new(
+            room_encryption_event,
+            history_visibility,
+            self.room_key_recipient_strategy.clone(),
+        );
+
+        if let Some(rotation_period) = self.room_key_rotation_period {
+            settings = settings.rotation_period(settings.rotation_period.min(rotation_period));
+        }
+
+        if let Some(rotation_period_msgs) = self.room_key_rotation_period_messages {
+            settings = settings
+                .rotation_period_messages(settings.rotation_period_msgs.min(rotation_period_msgs));
+        }
+
+        settings
+    }
+
     /// Get the room with the given room id.
     ///
     /// # Arguments
@@ -1060,18 +1154,25 @@ impl From<&v5::Request> for RequestedRequiredStates {
 
 #[cfg(test)]
 mod tests {
-    use std::collections::HashMap;
+    use std::{
+        collections::{BTreeSet, HashMap},
+        pin::pin,
+        time::Duration,
+    };
 
     use assert_matches2::assert_let;
-    use futures_util::FutureExt as _;
+    use futures_util::{FutureExt as _, StreamExt as _};
     use matrix_sdk_test::{
-        async_test, event_factory::EventFactory, ruma_response_from_json, InvitedRoomBuilder,
-        LeftRoomBuilder, StateTestEvent, StrippedStateTestEvent, SyncResponseBuilder, BOB,
+        async_test, event_factory::EventFactory, ruma_response_from_json,
+        GlobalAccountDataTestEvent, InvitedRoomBuilder, JoinedRoomBuilder, LeftRoomBuilder,
+        PresenceTestEvent, StateTestEvent, StrippedStateTestEvent, SyncResponseBuilder, BOB,
     };
     use ruma::{
         api::client::{self as api, sync::sync_events::v5},
         event_id,
-        events::{room::member::MembershipState, StateEventType},
+        events::{room::member::MembershipState, AnySyncStateEvent, StateEventType},
+        mxc_uri,
+        presence::PresenceState,
         room_id,
         serde::Raw,
         user_id,
@@ -1239,6 +1340,337 @@ mod tests {
         }
     }
 
+    #[async_test]
+    async fn test_sync_token_advances() {
+        let client = logged_in_base_client(None).await;
+        assert!(client.sync_token().await.is_none());
+
+        let mut sync_builder = SyncResponseBuilder::new();
+
+        let response = sync_builder.set_next_batch("token1").build_sync_response();
+        client.receive_sync_response(response).await.unwrap();
+        assert_eq!(client.sync_token().await.as_deref(), Some("token1"));
+
+        let response = sync_builder.set_next_batch("token2").build_sync_response();
+        client.receive_sync_response(response).await.unwrap();
+        assert_eq!(client.sync_token().await.as_deref(), Some("token2"));
+
+        // The token is only overridden once, generated tokens are used afterwards.
+        let response = sync_builder.build_sync_response();
+        assert_ne!(response.next_batch, "token2");
+    }
+
+    #[async_test]
+    async fn test_deserialization_errors_are_collected() {
+        let room_id = room_id!("!room:example.org");
+
+        let mut client = logged_in_base_client(None).await;
+        client.collect_deserialization_errors = true;
+
+        // The `topic` field of the content is missing.
+        let malformed_event: Raw<AnySyncStateEvent> = Raw::from_json(
+            to_raw_value(&json!({
+                "content": {},
+                "event_id": "$topic",
+                "origin_server_ts": 151800140,
+                "sender": "@bob:example.org",
+                "state_key": "",
+                "type": "m.room.topic",
+            }))
+            .unwrap(),
+        );
+
+        let mut sync_builder = SyncResponseBuilder::new();
+        let response = sync_builder
+            .add_joined_room(JoinedRoomBuilder::new(room_id).add_state_event(malformed_event))
+            .build_sync_response();
+        client.receive_sync_response(response).await.unwrap();
+
+        // The event has been ignored, but it has been recorded.
+        assert!(client.get_room(room_id).unwrap().topic().is_none());
+
+        let errors = client.last_deserialization_errors();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].event_type.as_deref(), Some("m.room.topic"));
+        assert!(errors[0].json.contains("$topic"));
+        assert!(!errors[0].error.is_empty());
+
+        // The errors are replaced with the ones of the next sync response.
+        let response = sync_builder.build_sync_response();
+        client.receive_sync_response(response).await.unwrap();
+        assert!(client.last_deserialization_errors().is_empty());
+    }
+
+    #[async_test]
+    async fn test_deserialization_errors_are_not_collected_by_default() {
+        let room_id = room_id!("!room:example.org");
+        let client = logged_in_base_client(None).await;
+
+        let malformed_event: Raw<AnySyncStateEvent> = Raw::from_json(
+            to_raw_value(&json!({
+                "content": {},
+                "event_id": "$topic",
+                "origin_server_ts": 151800140,
+                "sender": "@bob:example.org",
+                "state_key": "",
+                "type": "m.room.topic",
+            }))
+            .unwrap(),
+        );
+
+        let response = SyncResponseBuilder::new()
+            .add_joined_room(JoinedRoomBuilder::new(room_id).add_state_event(malformed_event))
+            .build_sync_response();
+        client.receive_sync_response(response).await.unwrap();
+
+        assert!(client.last_deserialization_errors().is_empty());
+    }
+
+    #[cfg(feature = "e2e-encryption")]
+    #[async_test]
+    async fn test_room_key_rotation_overrides() {
+        use ruma::{
+            assign,
+            events::room::{
+                encryption::RoomEncryptionEventContent, history_visibility::HistoryVisibility,
+            },
+            uint, EventEncryptionAlgorithm,
+        };
+
+        let mut client = logged_in_base_client(None).await;
+        let content = assign!(
+            RoomEncryptionEventContent::new(EventEncryptionAlgorithm::MegolmV1AesSha2),
+            { rotation_period_ms: Some(uint!(3_600_000)), rotation_period_msgs: Some(uint!(50)) }
+        );
+
+        // Without overrides, the rotation periods of the room are used.
+        let settings =
+            client.room_key_encryption_settings(content.clone(), HistoryVisibility::Shared);
+        assert_eq!(settings.rotation_period, Duration::from_secs(3600));
+        assert_eq!(settings.rotation_period_msgs, 50);
+
+        // Shorter rotation periods override the ones of the room…
+        client.room_key_rotation_period = Some(Duration::from_secs(60));
+        client.room_key_rotation_period_messages = Some(10);
+
+        let settings =
+            client.room_key_encryption_settings(content.clone(), HistoryVisibility::Shared);
+        assert_eq!(settings.rotation_period, Duration::from_secs(60));
+        assert_eq!(settings.rotation_period_msgs, 10);
+
+        // … but longer ones don't.
+        client.room_key_rotation_period = Some(Duration::from_secs(7200));
+        client.room_key_rotation_period_messages = Some(100);
+
+        let settings = client.room_key_encryption_settings(content, HistoryVisibility::Shared);
+        assert_eq!(settings.rotation_period, Duration::from_secs(3600));
+        assert_eq!(settings.rotation_period_msgs, 50);
+    }
+
+    #[async_test]
+    async fn test_display_avatar_url() {
+        let user_id = user_id!("@alice:example.org");
+        let bob = user_id!("@bob:example.org");
+        let dm_room_id = room_id!("!dm:example.org");
+        let group_room_id = room_id!("!group:example.org");
+        let bob_avatar = mxc_uri!("mxc://example.org/bob");
+
+        let client = logged_in_base_client(Some(user_id)).await;
+        let f = EventFactory::new();
+
+        let mut sync_builder = SyncResponseBuilder::new();
+        let response = sync_builder
+            .add_joined_room(
+                JoinedRoomBuilder::new(dm_room_id)
+                    .add_state_event(f.member(user_id))
+                    .add_state_event(f.member(bob).avatar_url(bob_avatar)),
+            )
+            .add_joined_room(
+                JoinedRoomBuilder::new(group_room_id)
+                    .add_state_event(f.member(user_id))
+                    .add_state_event(f.member(bob).avatar_url(bob_avatar)),
+            )
+            .build_sync_response();
+        client.receive_sync_response(response).await.unwrap();
+
+        let response = sync_builder
+            .add_global_account_data_event(GlobalAccountDataTestEvent::Custom(json!({
+                "content": {
+                    "@bob:example.org": [dm_room_id],
+                },
+                "type": "m.direct",
+            })))
+            .build_sync_response();
+        client.receive_sync_response(response).await.unwrap();
+
+        // The DM without a room avatar uses the avatar of the other user.
+        let dm_room = client.get_room(dm_room_id).unwrap();
+        assert_eq!(dm_room.display_avatar_url().await.unwrap().as_deref(), Some(bob_avatar));
+
+        // The group room without a room avatar doesn't have an avatar.
+        let group_room = client.get_room(group_room_id).unwrap();
+        assert!(group_room.display_avatar_url().await.unwrap().is_none());
+    }
+
+    #[async_test]
+    async fn test_member_presence() {
+        let user_id = user_id!("@example:localhost");
+        let room_id = room_id!("!test:localhost");
+
+        let client = logged_in_base_client(Some(user_id)).await;
+
+        let mut sync_builder = SyncResponseBuilder::new();
+        let response = sync_builder
+            .add_joined_room(
+                JoinedRoomBuilder::new(room_id)
+                    .add_state_event(EventFactory::new().member(user_id)),
+            )
+            .build_sync_response();
+        client.receive_sync_response(response).await.unwrap();
+
+        let room = client.get_room(room_id).unwrap();
+        let member = room.get_member(user_id).await.unwrap().unwrap();
+        assert!(member.presence().is_none());
+        assert!(member.last_active_ago().is_none());
+
+        let response = sync_builder
+            .add_presence_event(PresenceTestEvent::Custom(json!({
+                "content": {
+                    "currently_active": true,
+                    "last_active_ago": 2500,
+                    "presence": "online",
+                },
+                "sender": user_id,
+                "type": "m.presence",
+            })))
+            .build_sync_response();
+        client.receive_sync_response(response).await.unwrap();
+
+        let member = room.get_member(user_id).await.unwrap().unwrap();
+        assert_eq!(member.presence(), Some(&PresenceState::Online));
+        assert_eq!(member.last_active_ago(), Some(Duration::from_millis(2500)));
+    }
+
+    #[async_test]
+    async fn test_members_stream() {
+        let user_id = user_id!("@alice:example.org");
+        let room_id = room_id!("!test:example.org");
+        let bob = user_id!("@bob:example.org");
+        let carol = user_id!("@carol:example.org");
+
+        let client = logged_in_base_client(Some(user_id)).await;
+        let f = EventFactory::new();
+
+        let mut sync_builder = SyncResponseBuilder::new();
+
+        let response = sync_builder
+            .add_joined_room(
+                JoinedRoomBuilder::new(room_id)
+                    .add_state_event(f.member(user_id))
+                    .add_state_event(f.member(bob)),
+            )
+            .build_sync_response();
+        client.receive_sync_response(response).await.unwrap();
+
+        let room = client.get_room(room_id).unwrap();
+        let mut members_stream = pin!(room.members_stream());
+
+        // Carol joins, and Bob leaves.
+        let response = sync_builder
+            .add_joined_room(
+                JoinedRoomBuilder::new(room_id)
+                    .add_timeline_event(f.member(carol))
+                    .add_timeline_event(f.member(bob).membership(MembershipState::Leave)),
+            )
+            .build_sync_response();
+        client.receive_sync_response(response).await.unwrap();
+
+        let changes = members_stream.next().await.unwrap();
+        assert_eq!(changes.joined, BTreeSet::from([carol.to_owned()]));
+        assert_eq!(changes.left, BTreeSet::from([bob.to_owned()]));
+        assert!(changes.invited.is_empty());
+        assert!(changes.knocked.is_empty());
+    }
+
+    #[async_test]
+    async fn test_members_stream_ignores_profile_changes() {
+        let user_id = user_id!("@alice:example.org");
+        let room_id = room_id!("!test:example.org");
+        let bob = user_id!("@bob:example.org");
+
+        let client = logged_in_base_client(Some(user_id)).await;
+        let f = EventFactory::new();
+
+        let mut sync_builder = SyncResponseBuilder::new();
+
+        let response = sync_builder
+            .add_joined_room(
+                JoinedRoomBuilder::new(room_id)
+                    .add_state_event(f.member(user_id))
+                    .add_state_event(f.member(bob)),
+            )
+            .build_sync_response();
+        client.receive_sync_response(response).await.unwrap();
+
+        let room = client.get_room(room_id).unwrap();
+        let mut members_stream = pin!(room.members_stream());
+
+        // Bob changes his display name, but is still a member of the room.
+        let response = sync_builder
+            .add_joined_room(
+                JoinedRoomBuilder::new(room_id)
+                    .add_timeline_event(f.member(bob).display_name("Bobby")),
+            )
+            .build_sync_response();
+        client.receive_sync_response(response).await.unwrap();
+
+        assert!(members_stream.next().now_or_never().is_none());
+    }
+
+    #[async_test]
+    async fn test_members_stream_full_reload() {
+        let user_id = user_id!("@alice:example.org");
+        let room_id = room_id!("!test:example.org");
+        let bob = user_id!("@bob:example.org");
+        let carol = user_id!("@carol:example.org");
+        let dave = user_id!("@dave:example.org");
+
+        let client = logged_in_base_client(Some(user_id)).await;
+        let f = EventFactory::new().room(room_id);
+
+        let mut sync_builder = SyncResponseBuilder::new();
+
+        let response = sync_builder
+            .add_joined_room(
+                JoinedRoomBuilder::new(room_id)
+                    .add_state_event(f.member(user_id))
+                    .add_state_event(f.member(bob)),
+            )
+            .build_sync_response();
+        client.receive_sync_response(response).await.unwrap();
+
+        let room = client.get_room(room_id).unwrap();
+        let mut members_stream = pin!(room.members_stream());
+
+        // The whole members list is loaded: Bob is still there, Carol left the room
+        // long ago, and Dave joined in the meantime.
+        let request = api::membership::get_member_events::v3::Request::new(room_id.to_owned());
+        let response = api::membership::get_member_events::v3::Response::new(vec![
+            f.member(user_id).into_raw(),
+            f.member(bob).into_raw(),
+            f.member(carol).membership(MembershipState::Leave).into_raw(),
+            f.member(dave).into_raw(),
+        ]);
+        client.receive_all_members(room_id, &request, &response).await.unwrap();
+
+        // Only Dave's membership changed.
+        let changes = members_stream.next().await.unwrap();
+        assert_eq!(changes.joined, BTreeSet::from([dave.to_owned()]));
+        assert!(changes.left.is_empty());
+        assert!(changes.invited.is_empty());
+        assert!(changes.knocked.is_empty());
+    }
+
     #[async_test]
     async fn test_invite_after_leaving() {
         let user_id = user_id!("@alice:example.org");
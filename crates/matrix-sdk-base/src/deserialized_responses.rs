@@ -70,6 +70,34 @@ pub struct AmbiguityChanges {
     pub changes: BTreeMap<OwnedRoomId, BTreeMap<OwnedEventId, AmbiguityChange>>,
 }
 
+/// A state event received from the homeserver that couldn't be deserialized,
+/// and has thus been ignored while processing a sync response.
+///
+/// These are only recorded if
+/// [`BaseClient::collect_deserialization_errors`] is enabled.
+///
+/// [`BaseClient::collect_deserialization_errors`]: crate::BaseClient::collect_deserialization_errors
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct EventDeserializationError {
+    /// The type of the event, if it could be read from the JSON.
+    pub event_type: Option<String>,
+    /// The raw JSON of the event.
+    pub json: String,
+    /// The error message returned by the deserializer.
+    pub error: String,
+}
+
+impl EventDeserializationError {
+    pub(crate) fn new<T>(raw_event: &Raw<T>, error: &serde_json::Error) -> Self {
+        Self {
+            event_type: raw_event.get_field::<String>("type").ok().flatten(),
+            json: raw_event.json().get().to_owned(),
+            error: error.to_string(),
+        }
+    }
+}
+
 static MXID_REGEX: Lazy<Regex> = Lazy::new(|| {
     Regex::new(DisplayName::MXID_PATTERN)
         .expect("We should be able to create a regex from our static MXID pattern")
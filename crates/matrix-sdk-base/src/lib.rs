@@ -57,7 +57,8 @@ pub use once_cell;
 pub use room::{
     apply_redaction, EncryptionState, PredecessorRoom, Room, RoomCreateWithCreatorEventContent,
     RoomDisplayName, RoomHero, RoomInfo, RoomInfoNotableUpdate, RoomInfoNotableUpdateReasons,
-    RoomMember, RoomMembersUpdate, RoomMemberships, RoomState, RoomStateFilter, SuccessorRoom,
+    RoomMember, RoomMembersUpdate, RoomMembershipChanges, RoomMemberships, RoomState,
+    RoomStateFilter, SuccessorRoom,
 };
 pub use store::{
     ComposerDraft, ComposerDraftType, QueueWedgeError, StateChanges, StateStore, StateStoreDataKey,
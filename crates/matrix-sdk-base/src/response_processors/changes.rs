@@ -12,17 +12,24 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::BTreeMap;
+
 use eyeball::SharedObservable;
 use ruma::{
-    events::{ignored_user_list::IgnoredUserListEvent, GlobalAccountDataEventType},
+    events::{
+        ignored_user_list::IgnoredUserListEvent,
+        room::member::{RoomMemberEventContent, SyncRoomMemberEvent},
+        GlobalAccountDataEventType, StateEventType,
+    },
     serde::Raw,
+    OwnedRoomId,
 };
 use tracing::{error, instrument, trace};
 
 use super::Context;
 use crate::{
     store::{BaseStateStore, StateStoreExt as _},
-    Result,
+    Result, RoomMembershipChanges,
 };
 
 /// Save the [`StateChanges`] from the [`Context`] inside the [`BaseStateStore`]
@@ -48,10 +55,12 @@ pub async fn save_and_apply(
 
     let previous_ignored_user_list =
         state_store.get_account_data_event_static().await.ok().flatten();
+    let membership_changes = compute_membership_changes(&context, state_store).await?;
 
     save_changes(&context, state_store, sync_token).await?;
     apply_changes(&context, ignore_user_list_changes, previous_ignored_user_list);
     broadcast_room_info_notable_updates(&context, state_store);
+    broadcast_membership_changes(membership_changes, state_store);
 
     trace!("applied changes");
 
@@ -123,3 +132,72 @@ fn broadcast_room_info_notable_updates(context: &Context, state_store: &BaseStat
         }
     }
 }
+
+/// Compute the membership changes of the members whose `m.room.member` event is
+/// about to be saved, by comparing their new membership with the one in the
+/// store.
+///
+/// Only the rooms whose membership changes are observed are considered.
+async fn compute_membership_changes(
+    context: &Context,
+    state_store: &BaseStateStore,
+) -> Result<BTreeMap<OwnedRoomId, RoomMembershipChanges>> {
+    let mut membership_changes = BTreeMap::new();
+
+    for (room_id, state_events) in &context.state_changes.state {
+        let Some(member_events) = state_events.get(&StateEventType::RoomMember) else {
+            continue;
+        };
+
+        if state_store
+            .room(room_id)
+            .is_none_or(|room| room.room_membership_changes_sender.receiver_count() == 0)
+        {
+            continue;
+        }
+
+        let new_memberships: BTreeMap<_, _> = member_events
+            .values()
+            .filter_map(|raw_event| {
+                let event = raw_event.deserialize_as::<SyncRoomMemberEvent>().ok()?;
+                Some((event.state_key().to_owned(), event.membership().clone()))
+            })
+            .collect();
+
+        let user_ids: Vec<_> = new_memberships.keys().cloned().collect();
+        let previous_memberships: BTreeMap<_, _> = state_store
+            .get_state_events_for_keys_static::<RoomMemberEventContent, _, _>(room_id, &user_ids)
+            .await?
+            .into_iter()
+            .filter_map(|raw_event| {
+                let event = raw_event.deserialize().ok()?;
+                Some((event.user_id().to_owned(), event.membership().clone()))
+            })
+            .collect();
+
+        let mut changes = RoomMembershipChanges::default();
+
+        for (user_id, membership) in new_memberships {
+            let previous_membership = previous_memberships.get(&user_id);
+            changes.record(user_id, previous_membership, &membership);
+        }
+
+        if !changes.is_empty() {
+            membership_changes.insert(room_id.clone(), changes);
+        }
+    }
+
+    Ok(membership_changes)
+}
+
+fn broadcast_membership_changes(
+    membership_changes: BTreeMap<OwnedRoomId, RoomMembershipChanges>,
+    state_store: &BaseStateStore,
+) {
+    for (room_id, changes) in membership_changes {
+        if let Some(room) = state_store.room(&room_id) {
+            // There might be no subscribers anymore, that's fine.
+            let _ = room.room_membership_changes_sender.send(changes);
+        }
+    }
+}
@@ -29,9 +29,11 @@ pub mod verification;
 
 use std::collections::BTreeMap;
 
-use ruma::OwnedRoomId;
+use ruma::{serde::Raw, OwnedRoomId};
 
-use crate::{RoomInfoNotableUpdateReasons, StateChanges};
+use crate::{
+    deserialized_responses::EventDeserializationError, RoomInfoNotableUpdateReasons, StateChanges,
+};
 
 type RoomInfoNotableUpdates = BTreeMap<OwnedRoomId, RoomInfoNotableUpdateReasons>;
 
@@ -40,10 +42,36 @@ type RoomInfoNotableUpdates = BTreeMap<OwnedRoomId, RoomInfoNotableUpdateReasons
 pub(crate) struct Context {
     pub(super) state_changes: StateChanges,
     pub(super) room_info_notable_updates: RoomInfoNotableUpdates,
+    /// The state events that couldn't be deserialized, and have been ignored,
+    /// or `None` if they aren't collected.
+    pub(crate) deserialization_errors: Option<Vec<EventDeserializationError>>,
 }
 
 impl Context {
     pub fn new(state_changes: StateChanges) -> Self {
-        Self { state_changes, room_info_notable_updates: Default::default() }
+        Self {
+            state_changes,
+            room_info_notable_updates: Default::default(),
+            deserialization_errors: None,
+        }
+    }
+
+    /// Collect the state events that couldn't be deserialized, see
+    /// [`Self::push_deserialization_error`].
+    pub fn with_deserialization_errors(mut self) -> Self {
+        self.deserialization_errors = Some(Vec::new());
+        self
+    }
+
+    /// Record a state event that couldn't be deserialized, if they're
+    /// collected.
+    pub(crate) fn push_deserialization_error<T>(
+        &mut self,
+        raw_event: &Raw<T>,
+        error: &serde_json::Error,
+    ) {
+        if let Some(errors) = &mut self.deserialization_errors {
+            errors.push(EventDeserializationError::new(raw_event, error));
+        }
     }
 }
@@ -82,15 +82,17 @@ pub async fn update_any_room(
     // incomplete or staled already. We must only read state events from
     // `required_state`.
     let (raw_state_events, state_events) =
-        state_events::sync::collect(&room_response.required_state);
+        state_events::sync::collect(context, &room_response.required_state);
 
     let state_store = notification.state_store;
 
     // Find or create the room in the store
     let is_new_room = !state_store.room_exists(room_id);
 
-    let invite_state_events =
-        room_response.invite_state.as_ref().map(|events| state_events::stripped::collect(events));
+    let invite_state_events = room_response
+        .invite_state
+        .as_ref()
+        .map(|events| state_events::stripped::collect(context, events));
 
     #[allow(unused_mut)] // Required for some feature flag combinations
     let (mut room, mut room_info, maybe_room_update_kind) = membership(
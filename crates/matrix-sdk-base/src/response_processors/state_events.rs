@@ -49,9 +49,10 @@ pub mod sync {
 
     /// Collect [`AnySyncStateEvent`] to [`AnySyncStateEvent`].
     pub fn collect(
+        context: &mut Context,
         raw_events: &[Raw<AnySyncStateEvent>],
     ) -> (Vec<Raw<AnySyncStateEvent>>, Vec<AnySyncStateEvent>) {
-        super::collect(raw_events)
+        super::collect(context, raw_events)
     }
 
     /// Collect [`AnySyncTimelineEvent`] to [`AnySyncStateEvent`].
@@ -59,15 +60,19 @@ pub mod sync {
     /// A [`AnySyncTimelineEvent`] can represent either message-like events or
     /// state events. The message-like events are filtered out.
     pub fn collect_from_timeline(
+        context: &mut Context,
         raw_events: &[Raw<AnySyncTimelineEvent>],
     ) -> (Vec<Raw<AnySyncStateEvent>>, Vec<AnySyncStateEvent>) {
-        super::collect(raw_events.iter().filter_map(|raw_event| {
-            // Only state events have a `state_key` field.
-            match raw_event.get_field::<&str>("state_key") {
-                Ok(Some(_)) => Some(raw_event.cast_ref()),
-                _ => None,
-            }
-        }))
+        super::collect(
+            context,
+            raw_events.iter().filter_map(|raw_event| {
+                // Only state events have a `state_key` field.
+                match raw_event.get_field::<&str>("state_key") {
+                    Ok(Some(_)) => Some(raw_event.cast_ref()),
+                    _ => None,
+                }
+            }),
+        )
     }
 
     /// Dispatch the sync state events.
@@ -223,9 +228,10 @@ pub mod stripped {
 
     /// Collect [`AnyStrippedStateEvent`] to [`AnyStrippedStateEvent`].
     pub fn collect(
+        context: &mut Context,
         raw_events: &[Raw<AnyStrippedStateEvent>],
     ) -> (Vec<Raw<AnyStrippedStateEvent>>, Vec<AnyStrippedStateEvent>) {
-        super::collect(raw_events)
+        super::collect(context, raw_events)
     }
 
     /// Dispatch the stripped state events.
@@ -290,7 +296,7 @@ pub mod stripped {
     }
 }
 
-fn collect<'a, I, T>(raw_events: I) -> (Vec<Raw<T>>, Vec<T>)
+fn collect<'a, I, T>(context: &mut Context, raw_events: I) -> (Vec<Raw<T>>, Vec<T>)
 where
     I: IntoIterator<Item = &'a Raw<T>>,
     T: Deserialize<'a> + 'a,
@@ -301,6 +307,7 @@ where
             Ok(event) => Some((raw_event.clone(), event)),
             Err(e) => {
                 warn!("Couldn't deserialize stripped state event: {e}");
+                context.push_deserialization_error(raw_event, &e);
                 None
             }
         })
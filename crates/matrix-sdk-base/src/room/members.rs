@@ -16,9 +16,11 @@ use std::{
     collections::{BTreeMap, BTreeSet, HashMap},
     mem,
     sync::Arc,
+    time::Duration,
 };
 
 use bitflags::bitflags;
+use futures_util::{stream, Stream};
 use ruma::{
     events::{
         ignored_user_list::IgnoredUserListEventContent,
@@ -29,9 +31,11 @@ use ruma::{
         },
         MessageLikeEventType, StateEventType,
     },
+    presence::PresenceState,
     MxcUri, OwnedUserId, UserId,
 };
-use tracing::debug;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{debug, warn};
 
 use super::Room;
 use crate::{
@@ -116,6 +120,36 @@ impl Room {
         Ok(members)
     }
 
+    /// Get a `Stream` of the membership changes of this room's members.
+    ///
+    /// Each item contains the members whose membership changed in a sync
+    /// response, or when the whole members list is reloaded, grouped by their
+    /// new membership. The changes are computed by comparing the new
+    /// membership of a member with the one in the store; profile changes
+    /// aren't reported.
+    ///
+    /// If the stream lags too far behind, the changes it missed are lost.
+    pub fn members_stream(&self) -> impl Stream<Item = RoomMembershipChanges> {
+        let receiver = self.room_membership_changes_sender.subscribe();
+        let room_id = self.room_id().to_owned();
+
+        stream::unfold(receiver, move |mut receiver| {
+            let room_id = room_id.clone();
+
+            async move {
+                loop {
+                    match receiver.recv().await {
+                        Ok(changes) => return Some((changes, receiver)),
+                        Err(RecvError::Lagged(lag)) => {
+                            warn!(?room_id, lag, "Lagged behind the room membership changes");
+                        }
+                        Err(RecvError::Closed) => return None,
+                    }
+                }
+            }
+        })
+    }
+
     /// Returns the number of members who have joined or been invited to the
     /// room.
     pub fn active_members_count(&self) -> u64 {
@@ -203,7 +237,6 @@ pub struct RoomMember {
     // Stored in addition to the latest member event overall to get displayname
     // and avatar from, which should be ignored on events sent by others.
     pub(crate) profile: Arc<Option<MinimalRoomMemberEvent>>,
-    #[allow(dead_code)]
     pub(crate) presence: Arc<Option<PresenceEvent>>,
     pub(crate) power_levels: Arc<Option<SyncOrStrippedState<RoomPowerLevelsEventContent>>>,
     pub(crate) max_power_level: i64,
@@ -384,6 +417,20 @@ impl RoomMember {
         }
     }
 
+    /// Get the presence state of the member, if known.
+    pub fn presence(&self) -> Option<&PresenceState> {
+        self.presence.as_ref().as_ref().map(|event| &event.content.presence)
+    }
+
+    /// Get how long ago the member was last active, if known.
+    ///
+    /// This is relative to when the presence event of the member was received
+    /// from the homeserver.
+    pub fn last_active_ago(&self) -> Option<Duration> {
+        let last_active_ago = self.presence.as_ref().as_ref()?.content.last_active_ago?;
+        Some(Duration::from_millis(last_active_ago.into()))
+    }
+
     /// Is the name that the member uses ambiguous in the room.
     ///
     /// A name is considered to be ambiguous if at least one other member shares
@@ -392,6 +439,19 @@ impl RoomMember {
         self.display_name_ambiguous
     }
 
+    /// Get the name of the member, disambiguated with the user id if needed.
+    ///
+    /// This returns [`Self::name`], followed by the user id between
+    /// parentheses if the name is ambiguous in the room (see
+    /// [`Self::name_ambiguous`]), e.g. `Alice (@alice:example.org)`.
+    pub fn disambiguated_name(&self) -> String {
+        if self.name_ambiguous() {
+            format!("{} ({})", self.name(), self.user_id())
+        } else {
+            self.name().to_owned()
+        }
+    }
+
     /// Get the membership state of this member.
     pub fn membership(&self) -> &MembershipState {
         self.event.membership()
@@ -421,6 +481,64 @@ pub enum RoomMembersUpdate {
     Partial(BTreeSet<OwnedUserId>),
 }
 
+/// The membership changes of a room's members, as reported by
+/// [`Room::members_stream`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RoomMembershipChanges {
+    /// The members who joined the room.
+    pub joined: BTreeSet<OwnedUserId>,
+    /// The members who were invited to the room.
+    pub invited: BTreeSet<OwnedUserId>,
+    /// The members who requested to join the room.
+    pub knocked: BTreeSet<OwnedUserId>,
+    /// The members who left, or were kicked or banned from, the room.
+    pub left: BTreeSet<OwnedUserId>,
+}
+
+impl RoomMembershipChanges {
+    /// Whether no membership changed.
+    pub fn is_empty(&self) -> bool {
+        self.joined.is_empty()
+            && self.invited.is_empty()
+            && self.knocked.is_empty()
+            && self.left.is_empty()
+    }
+
+    /// Record the change of the membership of the given user, if their
+    /// membership changed from `previous` to `new`.
+    ///
+    /// A user without a previous membership is considered as having left the
+    /// room, and a ban as a leave.
+    pub(crate) fn record(
+        &mut self,
+        user_id: OwnedUserId,
+        previous: Option<&MembershipState>,
+        new: &MembershipState,
+    ) {
+        fn normalize(membership: &MembershipState) -> MembershipState {
+            match membership {
+                MembershipState::Ban => MembershipState::Leave,
+                membership => membership.clone(),
+            }
+        }
+
+        let previous = previous.map_or(MembershipState::Leave, normalize);
+        let new = normalize(new);
+
+        if previous == new {
+            return;
+        }
+
+        match new {
+            MembershipState::Join => self.joined.insert(user_id),
+            MembershipState::Invite => self.invited.insert(user_id),
+            MembershipState::Knock => self.knocked.insert(user_id),
+            MembershipState::Leave => self.left.insert(user_id),
+            _ => false,
+        };
+    }
+}
+
 bitflags! {
     /// Room membership filter as a bitset.
     ///
@@ -21,6 +21,7 @@ mod encryption;
 mod knock;
 mod latest_event;
 mod members;
+mod power_levels;
 mod room_info;
 mod state;
 mod tags;
@@ -41,7 +42,7 @@ use eyeball::{AsyncLock, SharedObservable};
 use futures_util::{Stream, StreamExt};
 #[cfg(feature = "e2e-encryption")]
 use matrix_sdk_common::ring_buffer::RingBuffer;
-pub use members::{RoomMember, RoomMembersUpdate, RoomMemberships};
+pub use members::{RoomMember, RoomMembersUpdate, RoomMembershipChanges, RoomMemberships};
 pub(crate) use room_info::SyncInfo;
 pub use room_info::{
     apply_redaction, BaseRoomInfo, RoomInfo, RoomInfoNotableUpdate, RoomInfoNotableUpdateReasons,
@@ -113,6 +114,10 @@ pub struct Room {
 
     /// A sender that will notify receivers when room member updates happen.
     pub room_member_updates_sender: broadcast::Sender<RoomMembersUpdate>,
+
+    /// A sender that will notify receivers when the membership of room members
+    /// changes.
+    pub(crate) room_membership_changes_sender: broadcast::Sender<RoomMembershipChanges>,
 }
 
 impl Room {
@@ -134,6 +139,7 @@ impl Room {
         room_info_notable_update_sender: broadcast::Sender<RoomInfoNotableUpdate>,
     ) -> Self {
         let (room_member_updates_sender, _) = broadcast::channel(10);
+        let (room_membership_changes_sender, _) = broadcast::channel(10);
         Self {
             own_user_id: own_user_id.into(),
             room_id: room_info.room_id.clone(),
@@ -146,6 +152,7 @@ impl Room {
             room_info_notable_update_sender,
             seen_knock_request_ids_map: SharedObservable::new_async(None),
             room_member_updates_sender,
+            room_membership_changes_sender,
         }
     }
 
@@ -244,6 +251,35 @@ impl Room {
         self.inner.read().avatar_info().map(ToOwned::to_owned)
     }
 
+    /// Get the avatar URL to display for this room.
+    ///
+    /// This is the avatar of the room if it has one. Otherwise, if the room is
+    /// a direct message with a single other user, this is the avatar of that
+    /// user in the room.
+    pub async fn display_avatar_url(&self) -> StoreResult<Option<OwnedMxcUri>> {
+        if let Some(avatar_url) = self.avatar_url() {
+            return Ok(Some(avatar_url));
+        }
+
+        let direct_targets = self.direct_targets();
+
+        if direct_targets.len() != 1 {
+            return Ok(None);
+        }
+
+        let Some(user_id) = direct_targets
+            .iter()
+            .next()
+            .and_then(|target| <&UserId>::try_from(target.as_str()).ok())
+        else {
+            return Ok(None);
+        };
+
+        let member = self.get_member(user_id).await?;
+
+        Ok(member.and_then(|member| member.avatar_url().map(ToOwned::to_owned)))
+    }
+
     /// Get the canonical alias of this room.
     pub fn canonical_alias(&self) -> Option<OwnedRoomAliasId> {
         self.inner.read().canonical_alias().map(ToOwned::to_owned)
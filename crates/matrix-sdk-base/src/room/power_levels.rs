@@ -0,0 +1,184 @@
+// Copyright 2025 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use ruma::events::room::power_levels::{PowerLevelAction, RoomPowerLevels};
+
+use super::Room;
+use crate::Error;
+
+impl Room {
+    /// Get the power level of the current user in this room.
+    ///
+    /// It is computed from the `m.room.power_levels` event in the state store.
+    /// If the power levels are unknown, the room creator has a power level of
+    /// 100, and every other user has a power level of 0.
+    pub async fn own_power_level(&self) -> Result<i64, Error> {
+        Ok(match self.power_levels_if_known().await? {
+            Some(power_levels) => power_levels.for_user(self.own_user_id()).into(),
+            None if self.is_own_user_creator() => 100,
+            None => 0,
+        })
+    }
+
+    /// Whether the current user can do the given action in this room.
+    ///
+    /// Like [`Self::own_power_level`], it is computed from the power levels in
+    /// the state store. If the power levels are unknown, only the room creator
+    /// can do anything.
+    ///
+    /// The power levels are loaded again on each call, to check several
+    /// actions at once, load them with [`Self::power_levels`] and use
+    /// [`RoomPowerLevels::user_can_do`].
+    pub async fn can(&self, action: PowerLevelAction) -> Result<bool, Error> {
+        Ok(match self.power_levels_if_known().await? {
+            Some(power_levels) => power_levels.user_can_do(self.own_user_id(), action),
+            None => self.is_own_user_creator(),
+        })
+    }
+
+    /// Get the power levels of this room, or `None` if the state store doesn't
+    /// contain them.
+    async fn power_levels_if_known(&self) -> Result<Option<RoomPowerLevels>, Error> {
+        match self.power_levels().await {
+            Ok(power_levels) => Ok(Some(power_levels)),
+            Err(Error::InsufficientData) => Ok(None),
+            Err(error) => Err(error),
+        }
+    }
+
+    fn is_own_user_creator(&self) -> bool {
+        self.inner.read().creator() == Some(self.own_user_id())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{ops::Not, sync::Arc};
+
+    use matrix_sdk_test::async_test;
+    use ruma::{
+        events::{
+            room::power_levels::PowerLevelAction, AnySyncStateEvent, MessageLikeEventType,
+            StateEventType,
+        },
+        room_id,
+        serde::Raw,
+        user_id,
+    };
+    use serde_json::json;
+
+    use super::super::{Room, RoomState};
+    use crate::store::{MemoryStore, StateChanges, StateStore};
+
+    fn make_room_test_helper(room_type: RoomState) -> (Arc<MemoryStore>, Room) {
+        let store = Arc::new(MemoryStore::new());
+        let user_id = user_id!("@me:example.org");
+        let room_id = room_id!("!test:localhost");
+        let (sender, _receiver) = tokio::sync::broadcast::channel(1);
+
+        (store.clone(), Room::new(user_id, store, room_id, room_type, sender))
+    }
+
+    async fn receive_state_event(store: &MemoryStore, room: &Room, event: serde_json::Value) {
+        let raw_event: Raw<AnySyncStateEvent> = Raw::new(&event).unwrap().cast();
+        let event = raw_event.deserialize().unwrap();
+
+        room.inner.update_if(|info| info.handle_state_event(&event));
+
+        let mut changes = StateChanges::default();
+        changes.add_state_event(room.room_id(), event, raw_event);
+        store.save_changes(&changes).await.unwrap();
+    }
+
+    #[async_test]
+    async fn test_own_power_level_without_power_levels() {
+        let (store, room) = make_room_test_helper(RoomState::Joined);
+
+        assert_eq!(room.own_power_level().await.unwrap(), 0);
+        assert!(room.can(PowerLevelAction::Invite).await.unwrap().not());
+
+        // The creator of the room has all the rights.
+        receive_state_event(
+            &store,
+            &room,
+            json!({
+                "content": {
+                    "creator": "@me:example.org",
+                    "room_version": "10",
+                },
+                "event_id": "$create",
+                "origin_server_ts": 1,
+                "sender": "@me:example.org",
+                "state_key": "",
+                "type": "m.room.create",
+            }),
+        )
+        .await;
+
+        assert_eq!(room.own_power_level().await.unwrap(), 100);
+        assert!(room.can(PowerLevelAction::Invite).await.unwrap());
+    }
+
+    #[async_test]
+    async fn test_own_power_level_and_actions() {
+        let (store, room) = make_room_test_helper(RoomState::Joined);
+
+        receive_state_event(
+            &store,
+            &room,
+            json!({
+                "content": {
+                    "ban": 50,
+                    "events": {
+                        "m.room.name": 50,
+                        "m.room.power_levels": 100,
+                        "m.room.message": 25,
+                    },
+                    "events_default": 0,
+                    "invite": 0,
+                    "kick": 75,
+                    "redact": 100,
+                    "state_default": 50,
+                    "users": {
+                        "@me:example.org": 50,
+                    },
+                    "users_default": 0,
+                },
+                "event_id": "$power_levels",
+                "origin_server_ts": 1,
+                "sender": "@admin:example.org",
+                "state_key": "",
+                "type": "m.room.power_levels",
+            }),
+        )
+        .await;
+
+        assert_eq!(room.own_power_level().await.unwrap(), 50);
+
+        assert!(room.can(PowerLevelAction::Ban).await.unwrap());
+        assert!(room.can(PowerLevelAction::Invite).await.unwrap());
+        assert!(room.can(PowerLevelAction::Kick).await.unwrap().not());
+        assert!(room.can(PowerLevelAction::RedactOther).await.unwrap().not());
+        assert!(room
+            .can(PowerLevelAction::SendMessage(MessageLikeEventType::RoomMessage))
+            .await
+            .unwrap());
+        assert!(room.can(PowerLevelAction::SendState(StateEventType::RoomName)).await.unwrap());
+        assert!(room
+            .can(PowerLevelAction::SendState(StateEventType::RoomPowerLevels))
+            .await
+            .unwrap()
+            .not());
+    }
+}
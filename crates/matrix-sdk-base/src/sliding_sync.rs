@@ -121,6 +121,10 @@ impl BaseClient {
 
         let mut context = processors::Context::default();
 
+        if self.collect_deserialization_errors {
+            context = context.with_deserialization_errors();
+        }
+
         let state_store = self.state_store.clone();
         let mut ambiguity_cache = AmbiguityCache::new(state_store.inner.clone());
 
@@ -209,6 +213,10 @@ impl BaseClient {
 
         context.state_changes.ambiguity_maps = ambiguity_cache.cache;
 
+        if let Some(errors) = context.deserialization_errors.take() {
+            self.record_deserialization_errors(errors);
+        }
+
         // Save the changes and apply them.
         processors::changes::save_and_apply(
             context,
@@ -389,6 +397,40 @@ mod tests {
         assert_eq!(room.unread_notification_counts(), count.into());
     }
 
+    #[async_test]
+    async fn test_deserialization_errors_are_collected() {
+        let mut client = logged_in_base_client(None).await;
+        client.collect_deserialization_errors = true;
+        let room_id = room_id!("!r:e.uk");
+
+        // The `topic` field of the content is missing.
+        let mut room = http::response::Room::new();
+        room.required_state.push(
+            serde_json::from_value(json!({
+                "content": {},
+                "event_id": "$topic",
+                "origin_server_ts": 151800140,
+                "sender": "@bob:example.org",
+                "state_key": "",
+                "type": "m.room.topic",
+            }))
+            .unwrap(),
+        );
+        let response = response_with_room(room_id, room);
+        client
+            .process_sliding_sync(&response, &RequestedRequiredStates::default())
+            .await
+            .expect("Failed to process sync");
+
+        // The event has been ignored, but it has been recorded.
+        assert!(client.get_room(room_id).unwrap().topic().is_none());
+
+        let errors = client.last_deserialization_errors();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].event_type.as_deref(), Some("m.room.topic"));
+        assert!(errors[0].json.contains("$topic"));
+    }
+
     #[async_test]
     async fn test_can_process_empty_sliding_sync_response() {
         let client = logged_in_base_client(None).await;
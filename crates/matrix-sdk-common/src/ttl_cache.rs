@@ -36,9 +36,13 @@ impl<V: Clone> TtlItem<V> {
 }
 
 /// A TTL cache where items get removed deterministically in the `get()` call.
+///
+/// The cache can optionally be bounded, in which case the oldest items get
+/// removed when inserting new items into a full cache.
 #[derive(Debug)]
 pub struct TtlCache<K: Eq + Hash, V: Clone> {
     lifetime: Duration,
+    max_items: Option<usize>,
     items: HashMap<K, TtlItem<V>>,
 }
 
@@ -49,7 +53,15 @@ where
 {
     /// Create a new, empty, [`TtlCache`].
     pub fn new() -> Self {
-        Self { items: Default::default(), lifetime: DEFAULT_LIFETIME }
+        Self { items: Default::default(), lifetime: DEFAULT_LIFETIME, max_items: None }
+    }
+
+    /// Create a new, empty, [`TtlCache`] holding at most `max_items` items.
+    ///
+    /// When inserting into a full cache, expired items are removed first, then
+    /// the oldest items.
+    pub fn with_max_items(max_items: usize) -> Self {
+        Self { max_items: Some(max_items), ..Self::new() }
     }
 
     /// Does the cache contain an non-expired item with the matching key.
@@ -80,6 +92,39 @@ where
 
             cache.insert(key, item);
         }
+
+        self.evict_overflow();
+    }
+
+    /// Remove items until the cache doesn't hold more than `max_items` items,
+    /// starting with the expired ones, then the oldest ones.
+    fn evict_overflow(&mut self) {
+        let Some(max_items) = self.max_items else {
+            return;
+        };
+
+        if self.items.len() <= max_items {
+            return;
+        }
+
+        self.items.retain(|_, value| !value.expired());
+
+        while self.items.len() > max_items {
+            let Some(oldest) = self.items.values().map(|item| item.insertion_time).min() else {
+                break;
+            };
+
+            // Remove a single item, since several items can share the same insertion time.
+            let mut removed = false;
+            self.items.retain(|_, item| {
+                if !removed && item.insertion_time == oldest {
+                    removed = true;
+                    false
+                } else {
+                    true
+                }
+            });
+        }
     }
 
     /// Remove the item that matches the given key.
@@ -146,4 +191,29 @@ mod tests {
         assert!(!cache.contains("A"));
         assert!(cache.get("A").is_none(), "The item should have been removed from the cache");
     }
+
+    #[test]
+    fn test_ttl_cache_max_items() {
+        let mut cache = TtlCache::with_max_items(2);
+
+        cache.insert("A", 1);
+        std::thread::sleep(std::time::Duration::from_millis(1));
+        cache.insert("B", 2);
+        assert!(cache.contains("A"));
+        assert!(cache.contains("B"));
+
+        // The oldest item is evicted to make room for the new one.
+        std::thread::sleep(std::time::Duration::from_millis(1));
+        cache.insert("C", 3);
+        assert!(!cache.contains("A"));
+        assert!(cache.contains("B"));
+        assert!(cache.contains("C"));
+
+        // Expired items are evicted first.
+        cache.expire("C");
+        cache.insert("D", 4);
+        assert!(cache.contains("B"));
+        assert!(!cache.contains("C"));
+        assert!(cache.contains("D"));
+    }
 }
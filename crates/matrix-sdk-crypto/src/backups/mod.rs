@@ -25,7 +25,10 @@
 
 use std::{
     collections::{BTreeMap, BTreeSet},
-    sync::Arc,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
 };
 
 use ruma::{
@@ -60,6 +63,7 @@ pub struct BackupMachine {
     store: Store,
     backup_key: Arc<RwLock<Option<MegolmV1BackupKey>>>,
     pending_backup: Arc<RwLock<Option<PendingBackup>>>,
+    batch_size: Arc<AtomicUsize>,
 }
 
 type SenderKey = String;
@@ -132,16 +136,38 @@ impl SignatureState {
 }
 
 impl BackupMachine {
-    const BACKUP_BATCH_SIZE: usize = 100;
+    /// The default maximum number of room keys uploaded in a single backup
+    /// request.
+    pub const DEFAULT_BATCH_SIZE: usize = 100;
 
     pub(crate) fn new(store: Store, backup_key: Option<MegolmV1BackupKey>) -> Self {
         Self {
-            store,
             backup_key: RwLock::new(backup_key).into(),
             pending_backup: RwLock::new(None).into(),
+            batch_size: AtomicUsize::new(Self::DEFAULT_BATCH_SIZE).into(),
+            store,
         }
     }
 
+    /// Set the maximum number of room keys uploaded in a single backup
+    /// request created by [`BackupMachine::backup`].
+    ///
+    /// Smaller batches are less likely to time out on slow connections, while
+    /// bigger ones need fewer requests. Defaults to
+    /// [`BackupMachine::DEFAULT_BATCH_SIZE`]; a batch size of 0 is treated as
+    /// 1.
+    ///
+    /// This doesn't affect a backup request that has already been created.
+    pub fn set_batch_size(&self, batch_size: usize) {
+        self.batch_size.store(batch_size.max(1), Ordering::SeqCst);
+    }
+
+    /// Get the maximum number of room keys uploaded in a single backup
+    /// request.
+    pub fn batch_size(&self) -> usize {
+        self.batch_size.load(Ordering::SeqCst)
+    }
+
     /// Are we able to back up room keys to the server?
     pub async fn enabled(&self) -> bool {
         self.backup_key.read().await.as_ref().is_some_and(|b| b.backup_version().is_some())
@@ -339,6 +365,21 @@ impl BackupMachine {
         }
     }
 
+    /// Verify some backup info that we downloaded from the server, only
+    /// trusting a signature from our own cross-signing identity.
+    ///
+    /// This is a stricter version of [`BackupMachine::verify_backup`]:
+    /// signatures from our devices are ignored, and `true` is only returned if
+    /// the backup info has been signed by our master key, and our own identity
+    /// is verified.
+    pub async fn verify_backup_requiring_cross_signing(
+        &self,
+        backup_info: RoomKeyBackupInfo,
+    ) -> Result<bool, CryptoStoreError> {
+        let verification = self.verify_backup(backup_info, false).await?;
+        Ok(verification.user_identity_signature.trusted())
+    }
+
     /// Sign a [`RoomKeyBackupInfo`] using the device's identity key and, if
     /// available, the cross-signing master key.
     ///
@@ -401,7 +442,21 @@ impl BackupMachine {
     /// Get the number of backed up room keys and the total number of room keys.
     pub async fn room_key_counts(&self) -> Result<RoomKeyCounts, CryptoStoreError> {
         let backup_version = self.backup_key.read().await.as_ref().and_then(|k| k.backup_version());
-        self.store.inbound_group_session_counts(backup_version.as_deref()).await
+        self.store.crypto_store().inbound_group_session_counts(backup_version.as_deref()).await
+    }
+
+    /// Get the room key counts without querying the store.
+    ///
+    /// The counts computed by the last call to
+    /// [`BackupMachine::room_key_counts`] are kept up to date as room keys are
+    /// saved and backed up, which makes this cheap enough to be polled. Call
+    /// [`BackupMachine::room_key_counts`] to recount the room keys.
+    ///
+    /// Returns `None` if the room keys haven't been counted yet for the
+    /// current backup version, or if the counts had to be invalidated.
+    pub async fn cached_room_key_counts(&self) -> Option<RoomKeyCounts> {
+        let backup_version = self.backup_key.read().await.as_ref().and_then(|k| k.backup_version());
+        self.store.crypto_store().cached_inbound_group_session_counts(backup_version.as_deref())
     }
 
     /// Disable and reset our backup state.
@@ -415,7 +470,7 @@ impl BackupMachine {
         self.backup_key.write().await.take();
         self.pending_backup.write().await.take();
 
-        self.store.reset_backup_state().await?;
+        self.store.crypto_store().reset_backup_state().await?;
 
         debug!("Done disabling backup");
 
@@ -488,6 +543,7 @@ impl BackupMachine {
                 trace!(request_id = ?r.request_id, keys = ?r.sessions, "Marking room keys as backed up");
 
                 self.store
+                    .crypto_store()
                     .mark_inbound_group_sessions_as_backed_up(
                         &r.request.version,
                         &room_and_session_ids,
@@ -530,7 +586,7 @@ impl BackupMachine {
         };
 
         let sessions =
-            self.store.inbound_group_sessions_for_backup(&version, Self::BACKUP_BATCH_SIZE).await?;
+            self.store.inbound_group_sessions_for_backup(&version, self.batch_size()).await?;
 
         if sessions.is_empty() {
             trace!(?backup_key, "No room keys need to be backed up");
@@ -633,15 +689,83 @@ impl BackupMachine {
             .import_room_keys(decrypted_room_keys, backup_version.as_deref(), progress_listener)
             .await
     }
+
+    /// Decrypt and import room keys downloaded from a backup.
+    ///
+    /// Room keys that can't be decrypted with the given key, e.g. because they
+    /// were uploaded to the backup with another key, aren't silently skipped:
+    /// they're reported in [`RoomKeyImportResult::failed`], grouped by room,
+    /// and they're included in [`RoomKeyImportResult::total_count`].
+    ///
+    /// # Arguments
+    ///
+    /// * `decryption_key` - The private key of the backup.
+    ///
+    /// * `backup_version` - The version of the backup the keys were downloaded
+    ///   from.
+    ///
+    /// * `room_keys` - The encrypted room keys, as downloaded from the backup.
+    ///
+    /// * `progress_listener` - A closure called with the number of imported
+    ///   room keys and the total number of decrypted room keys.
+    pub async fn decrypt_and_import_room_keys(
+        &self,
+        decryption_key: &BackupDecryptionKey,
+        backup_version: &str,
+        room_keys: BTreeMap<OwnedRoomId, RoomKeyBackup>,
+        progress_listener: impl Fn(usize, usize),
+    ) -> Result<RoomKeyImportResult, CryptoStoreError> {
+        let mut decrypted_room_keys = Vec::new();
+        let mut failed: BTreeMap<OwnedRoomId, BTreeSet<SessionId>> = BTreeMap::new();
+
+        for (room_id, room_keys) in room_keys {
+            for (session_id, room_key) in room_keys.sessions {
+                let room_key = match room_key.deserialize() {
+                    Ok(room_key) => decryption_key
+                        .decrypt_session_data(room_key.session_data)
+                        .map_err(|e| e.to_string()),
+                    Err(e) => Err(e.to_string()),
+                };
+
+                match room_key {
+                    Ok(room_key) => {
+                        decrypted_room_keys.push(ExportedRoomKey::from_backed_up_room_key(
+                            room_id.to_owned(),
+                            session_id,
+                            room_key,
+                        ));
+                    }
+                    Err(error) => {
+                        warn!(?room_id, %session_id, %error, "Couldn't decrypt a backed up room key");
+                        failed.entry(room_id.to_owned()).or_default().insert(session_id);
+                    }
+                }
+            }
+        }
+
+        let mut result = self
+            .store
+            .import_room_keys(decrypted_room_keys, Some(backup_version), progress_listener)
+            .await?;
+
+        result.total_count += failed.values().map(BTreeSet::len).sum::<usize>();
+        result.failed = failed;
+
+        Ok(result)
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::collections::BTreeMap;
+    use std::collections::{BTreeMap, BTreeSet};
 
     use assert_matches2::assert_let;
+    use futures_util::future::join;
     use matrix_sdk_test::async_test;
-    use ruma::{device_id, room_id, user_id, CanonicalJsonValue, DeviceId, RoomId, UserId};
+    use ruma::{
+        api::client::backup::RoomKeyBackup, device_id, room_id, serde::Raw, user_id,
+        CanonicalJsonValue, DeviceId, RoomId, UserId,
+    };
     use serde_json::json;
 
     use super::BackupMachine;
@@ -679,6 +803,14 @@ mod tests {
         device_id!("JLAFKJWSCS")
     }
 
+    fn bob_id() -> &'static UserId {
+        user_id!("@bob:example.org")
+    }
+
+    fn bob_device_id() -> &'static DeviceId {
+        device_id!("BOBDEVICE")
+    }
+
     fn room_id() -> &'static RoomId {
         room_id!("!test:localhost")
     }
@@ -757,6 +889,205 @@ mod tests {
         backup_flow(machine).await
     }
 
+    #[async_test]
+    async fn test_backup_batch_size() -> Result<(), OlmError> {
+        let machine = OlmMachine::new(alice_id(), alice_device_id()).await;
+        let backup_machine = machine.backup_machine();
+        assert_eq!(backup_machine.batch_size(), BackupMachine::DEFAULT_BATCH_SIZE);
+
+        for i in 0..5 {
+            let room_id = RoomId::parse(format!("!room{i}:localhost")).unwrap();
+            machine.create_outbound_group_session_with_defaults_test_helper(&room_id).await?;
+        }
+
+        let backup_key = BackupDecryptionKey::new().unwrap().megolm_v1_public_key();
+        backup_key.set_version("1".to_owned());
+        backup_machine.enable_backup_v1(backup_key).await?;
+
+        backup_machine.set_batch_size(2);
+
+        // 5 room keys in batches of 2 need 3 requests.
+        let mut batches = Vec::new();
+        while let Some((request_id, request)) = backup_machine.backup().await? {
+            batches.push(request.rooms.values().map(|room| room.sessions.len()).sum::<usize>());
+            backup_machine.mark_request_as_sent(&request_id).await?;
+        }
+
+        assert_eq!(batches, vec![2, 2, 1]);
+
+        Ok(())
+    }
+
+    #[async_test]
+    async fn test_decrypt_and_import_room_keys_reports_failures() -> Result<(), OlmError> {
+        let machine = OlmMachine::new(alice_id(), alice_device_id()).await;
+
+        let decryption_key = BackupDecryptionKey::new().unwrap();
+        let other_decryption_key = BackupDecryptionKey::new().unwrap();
+
+        let session = machine.create_inbound_session_test_helper(room_id()).await?;
+        let other_session = machine.create_inbound_session_test_helper(room_id2()).await?;
+
+        // The first room key is encrypted with the right backup key, the second one
+        // with another key.
+        let room_key = decryption_key.megolm_v1_public_key().encrypt(session.clone()).await;
+        let other_room_key =
+            other_decryption_key.megolm_v1_public_key().encrypt(other_session.clone()).await;
+
+        let room_keys = BTreeMap::from([
+            (
+                room_id().to_owned(),
+                RoomKeyBackup::new(BTreeMap::from([(
+                    session.session_id().to_owned(),
+                    Raw::new(&room_key).unwrap(),
+                )])),
+            ),
+            (
+                room_id2().to_owned(),
+                RoomKeyBackup::new(BTreeMap::from([(
+                    other_session.session_id().to_owned(),
+                    Raw::new(&other_room_key).unwrap(),
+                )])),
+            ),
+        ]);
+
+        let other_machine = OlmMachine::new(alice_id(), alice_device_id()).await;
+        let result = other_machine
+            .backup_machine()
+            .decrypt_and_import_room_keys(&decryption_key, "1", room_keys, |_, _| {})
+            .await?;
+
+        assert_eq!(result.imported_count, 1);
+        assert_eq!(result.total_count, 2);
+        assert!(result.keys.contains_key(room_id()));
+        assert_eq!(
+            result.failed,
+            BTreeMap::from([(
+                room_id2().to_owned(),
+                BTreeSet::from([other_session.session_id().to_owned()])
+            )])
+        );
+
+        Ok(())
+    }
+
+    #[async_test]
+    async fn test_cached_room_key_counts() -> Result<(), OlmError> {
+        let machine = OlmMachine::new(alice_id(), alice_device_id()).await;
+        let backup_machine = machine.backup_machine();
+
+        let backup_key = BackupDecryptionKey::new().unwrap().megolm_v1_public_key();
+        backup_key.set_version("1".to_owned());
+        backup_machine.enable_backup_v1(backup_key).await?;
+
+        // Nothing is cached until the room keys have been counted once.
+        assert!(backup_machine.cached_room_key_counts().await.is_none());
+        assert_eq!(backup_machine.room_key_counts().await?.total, 0);
+
+        // New room keys are accounted for.
+        machine.create_outbound_group_session_with_defaults_test_helper(room_id()).await?;
+        machine.create_outbound_group_session_with_defaults_test_helper(room_id2()).await?;
+
+        let counts = backup_machine.cached_room_key_counts().await.unwrap();
+        assert_eq!(counts.total, 2);
+        assert_eq!(counts.backed_up, 0);
+
+        // So are the room keys being backed up.
+        let (request_id, _) = backup_machine.backup().await?.unwrap();
+        backup_machine.mark_request_as_sent(&request_id).await?;
+
+        let counts = backup_machine.cached_room_key_counts().await.unwrap();
+        assert_eq!(counts.total, 2);
+        assert_eq!(counts.backed_up, 2);
+
+        // And the backup being disabled.
+        backup_machine.disable_backup().await?;
+
+        let counts = backup_machine.cached_room_key_counts().await.unwrap();
+        assert_eq!(counts.total, 2);
+        assert_eq!(counts.backed_up, 0);
+
+        Ok(())
+    }
+
+    #[async_test]
+    async fn test_cached_room_key_counts_with_room_keys_saved_again() -> Result<(), OlmError> {
+        let machine = OlmMachine::new(alice_id(), alice_device_id()).await;
+        let backup_machine = machine.backup_machine();
+
+        let backup_key = BackupDecryptionKey::new().unwrap().megolm_v1_public_key();
+        backup_key.set_version("1".to_owned());
+        backup_machine.enable_backup_v1(backup_key).await?;
+        backup_machine.room_key_counts().await?;
+
+        machine.create_outbound_group_session_with_defaults_test_helper(room_id()).await?;
+        let session = machine.store().get_inbound_group_sessions().await?.pop().unwrap();
+
+        // Saving a room key again, e.g. because its sender data was updated, doesn't
+        // count it twice.
+        machine.store().save_inbound_group_sessions(&[session.clone()]).await?;
+
+        let counts = backup_machine.cached_room_key_counts().await.unwrap();
+        assert_eq!(counts.total, 1);
+        assert_eq!(counts.backed_up, 0);
+
+        let (request_id, _) = backup_machine.backup().await?.unwrap();
+        backup_machine.mark_request_as_sent(&request_id).await?;
+
+        let counts = backup_machine.cached_room_key_counts().await.unwrap();
+        assert_eq!(counts.total, 1);
+        assert_eq!(counts.backed_up, 1);
+
+        // Marking a room key which is already backed up doesn't count it twice
+        // either: since we can't know in which backup version it was backed up, the
+        // counts are invalidated…
+        machine
+            .store()
+            .crypto_store()
+            .mark_inbound_group_sessions_as_backed_up("1", &[(room_id(), session.session_id())])
+            .await?;
+        assert!(backup_machine.cached_room_key_counts().await.is_none());
+
+        // … and recounted.
+        let counts = backup_machine.room_key_counts().await?;
+        assert_eq!(counts.total, 1);
+        assert_eq!(counts.backed_up, 1);
+
+        Ok(())
+    }
+
+    #[async_test]
+    async fn test_cached_room_key_counts_with_concurrent_imports() -> Result<(), OlmError> {
+        // Create some room keys to import.
+        let bob = OlmMachine::new(bob_id(), bob_device_id()).await;
+        for room_id in [room_id(), room_id2()] {
+            bob.create_outbound_group_session_with_defaults_test_helper(room_id).await?;
+        }
+        let exported_keys = bob.store().export_room_keys(|_| true).await?;
+
+        let machine = OlmMachine::new(alice_id(), alice_device_id()).await;
+        let backup_machine = machine.backup_machine();
+        backup_machine.room_key_counts().await?;
+
+        // Count the room keys while importing them.
+        for exported_key in exported_keys {
+            let (counts, import_result) = join(
+                backup_machine.room_key_counts(),
+                machine.store().import_room_keys(vec![exported_key], None, |_, _| ()),
+            )
+            .await;
+            counts?;
+            import_result?;
+        }
+
+        // Each room key is counted exactly once.
+        let counts = backup_machine.cached_room_key_counts().await.unwrap();
+        assert_eq!(counts.total, 2);
+        assert_eq!(machine.store().inbound_group_session_counts(None).await?.total, 2);
+
+        Ok(())
+    }
+
     #[async_test]
     async fn test_verify_auth_data() -> Result<(), OlmError> {
         let machine = OlmMachine::new(alice_id(), alice_device_id()).await;
@@ -904,6 +1235,30 @@ mod tests {
         assert!(result.trusted());
     }
 
+    #[async_test]
+    async fn test_verify_backup_requiring_cross_signing() {
+        let machine = OlmMachine::new(alice_id(), alice_device_id()).await;
+        let backup_machine = machine.backup_machine();
+
+        let decryption_key = BackupDecryptionKey::new().unwrap();
+
+        // Without a cross-signing identity, the backup info is only signed by our
+        // device, which isn't enough for the stricter check.
+        let mut backup_info = decryption_key.to_backup_info();
+        backup_machine.sign_backup(&mut backup_info).await.unwrap();
+
+        assert!(backup_machine.verify_backup(backup_info.clone(), false).await.unwrap().trusted());
+        assert!(!backup_machine.verify_backup_requiring_cross_signing(backup_info).await.unwrap());
+
+        // Once signed by our master key, the backup info passes the stricter check.
+        machine.bootstrap_cross_signing(true).await.unwrap();
+
+        let mut backup_info = decryption_key.to_backup_info();
+        backup_machine.sign_backup(&mut backup_info).await.unwrap();
+
+        assert!(backup_machine.verify_backup_requiring_cross_signing(backup_info).await.unwrap());
+    }
+
     #[async_test]
     async fn test_fix_backup_key_mismatch() {
         let store = MemoryStore::new();
@@ -147,7 +147,7 @@ pub fn encrypt_room_key_export(
     Ok([HEADER.to_owned(), ciphertext, FOOTER.to_owned()].join("\n"))
 }
 
-fn encrypt_helper(plaintext: &[u8], passphrase: &str, rounds: u32) -> String {
+pub(super) fn encrypt_helper(plaintext: &[u8], passphrase: &str, rounds: u32) -> String {
     let mut salt = [0u8; SALT_SIZE];
     let mut rng = thread_rng();
 
@@ -171,7 +171,7 @@ fn encrypt_helper(plaintext: &[u8], passphrase: &str, rounds: u32) -> String {
     base64_encode(payload)
 }
 
-fn decrypt_helper(ciphertext: &str, passphrase: &str) -> Result<String, KeyExportError> {
+pub(super) fn decrypt_helper(ciphertext: &str, passphrase: &str) -> Result<String, KeyExportError> {
     let decoded = base64_decode(ciphertext)?;
 
     let mut decoded = Cursor::new(decoded);
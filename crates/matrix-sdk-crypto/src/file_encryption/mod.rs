@@ -1,7 +1,10 @@
 mod attachments;
 mod key_export;
+mod store_export;
 
 pub use attachments::{
     AttachmentDecryptor, AttachmentEncryptor, DecryptorError, MediaEncryptionInfo,
 };
 pub use key_export::{decrypt_room_key_export, encrypt_room_key_export, KeyExportError};
+pub(crate) use store_export::StoreExport;
+pub use store_export::{EncryptedStoreExport, StoreExportError};
@@ -0,0 +1,131 @@
+// Copyright 2025 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Error as SerdeError;
+use thiserror::Error;
+use zeroize::Zeroize;
+
+use super::key_export::{decrypt_helper, encrypt_helper, KeyExportError};
+use crate::{
+    error::SessionUnpickleError,
+    olm::{
+        PickledAccount, PickledCrossSigningIdentity, PickledInboundGroupSession, PickledSession,
+        SigningError,
+    },
+    store::types::BackupDecryptionKey,
+    CryptoStoreError,
+};
+
+const HEADER: &str = "-----BEGIN MATRIX CRYPTO STORE EXPORT-----";
+const FOOTER: &str = "-----END MATRIX CRYPTO STORE EXPORT-----";
+
+/// Error representing a failure while exporting or importing a crypto store.
+#[derive(Error, Debug)]
+pub enum StoreExportError {
+    /// The export couldn't be decrypted, or isn't a valid store export.
+    #[error(transparent)]
+    Decryption(#[from] KeyExportError),
+    /// The store content couldn't be serialized.
+    #[error(transparent)]
+    Serialization(#[from] SerdeError),
+    /// An error happened while reading from or writing to the crypto store.
+    #[error(transparent)]
+    Store(#[from] CryptoStoreError),
+    /// The Olm account, or one of the room keys, couldn't be unpickled.
+    #[error(transparent)]
+    Pickle(#[from] vodozemac::PickleError),
+    /// One of the Olm sessions couldn't be unpickled.
+    #[error(transparent)]
+    SessionPickle(#[from] SessionUnpickleError),
+    /// The private cross-signing keys couldn't be unpickled.
+    #[error(transparent)]
+    CrossSigningPickle(#[from] SigningError),
+    /// The store to import into already contains an Olm account.
+    #[error("the crypto store already contains an account")]
+    AccountAlreadyExists,
+}
+
+/// The decrypted content of an [`EncryptedStoreExport`].
+#[derive(Serialize, Deserialize)]
+pub(crate) struct StoreExport {
+    pub account: PickledAccount,
+    pub private_identity: PickledCrossSigningIdentity,
+    pub sessions: Vec<PickledSession>,
+    pub inbound_group_sessions: Vec<PickledInboundGroupSession>,
+    #[serde(default)]
+    pub backup_decryption_key: Option<BackupDecryptionKey>,
+    #[serde(default)]
+    pub backup_version: Option<String>,
+}
+
+/// A passphrase-encrypted export of a whole crypto store, as created by
+/// [`OlmMachine::export_store`].
+///
+/// The export is an ASCII-armored string, which can be stored or transferred
+/// as is, and restored with [`OlmMachine::import_store`].
+///
+/// [`OlmMachine::export_store`]: crate::OlmMachine::export_store
+/// [`OlmMachine::import_store`]: crate::OlmMachine::import_store
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct EncryptedStoreExport(String);
+
+impl EncryptedStoreExport {
+    /// Encrypt the given store content using the given passphrase.
+    ///
+    /// See [`crate::encrypt_room_key_export`] for the meaning of `rounds`.
+    pub(crate) fn encrypt(
+        export: &StoreExport,
+        passphrase: &str,
+        rounds: u32,
+    ) -> Result<Self, SerdeError> {
+        let mut plaintext = serde_json::to_string(export)?.into_bytes();
+        let ciphertext = encrypt_helper(&plaintext, passphrase, rounds);
+
+        plaintext.zeroize();
+
+        Ok(Self([HEADER.to_owned(), ciphertext, FOOTER.to_owned()].join("\n")))
+    }
+
+    /// Decrypt the store content using the given passphrase.
+    pub(crate) fn decrypt(&self, passphrase: &str) -> Result<StoreExport, KeyExportError> {
+        let export = self.0.trim();
+
+        let payload = export
+            .strip_prefix(HEADER)
+            .and_then(|export| export.strip_suffix(FOOTER))
+            .ok_or(KeyExportError::InvalidHeaders)?;
+        let payload: String = payload.lines().collect();
+
+        let mut decrypted = decrypt_helper(&payload, passphrase)?;
+
+        let ret = serde_json::from_str(&decrypted);
+
+        decrypted.zeroize();
+
+        Ok(ret?)
+    }
+
+    /// Get the ASCII-armored export.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<String> for EncryptedStoreExport {
+    fn from(export: String) -> Self {
+        Self(export)
+    }
+}
@@ -38,7 +38,7 @@ use crate::{
     },
     store::{
         caches::{SequenceNumber, StoreCache, StoreCacheGuard},
-        types::{Changes, DeviceChanges, IdentityChanges, UserKeyQueryResult},
+        types::{Changes, DeviceChanges, DeviceKeyChanged, IdentityChanges, UserKeyQueryResult},
         KeyQueryManager, Result as StoreResult, Store,
     },
     types::{
@@ -236,6 +236,18 @@ impl IdentityManager {
                         error = ?e,
                         "Rejecting device update",
                     );
+
+                    // The Ed25519 key of a device is pinned the first time we see it, let the
+                    // listeners know that the homeserver returned a different one.
+                    if device.ed25519_key() != device_keys.ed25519_key() {
+                        store.crypto_store().notify_device_key_changed(DeviceKeyChanged {
+                            old_ed25519_key: device.ed25519_key(),
+                            new_ed25519_key: device_keys.ed25519_key(),
+                            user_id: device_keys.user_id,
+                            device_id: device_keys.device_id,
+                        });
+                    }
+
                     Ok(DeviceChange::None)
                 }
                 Ok(true) => Ok(DeviceChange::Updated(device)),
@@ -1530,6 +1542,7 @@ pub(crate) mod tests {
     };
     use serde_json::json;
     use stream_assert::{assert_closed, assert_pending, assert_ready};
+    use vodozemac::Ed25519SecretKey;
 
     use super::testing::{
         device_id, key_query, manager_test_helper, other_key_query, other_user_id, user_id,
@@ -1930,6 +1943,63 @@ pub(crate) mod tests {
         assert!(!update.new.is_empty(), "The device update should contain some devices");
     }
 
+    #[async_test]
+    async fn test_device_key_changes_stream() {
+        let manager = manager_test_helper(user_id(), device_id()).await;
+        let other_user = other_user_id();
+        let other_device_id = device_id!("SKISMLNIMH");
+
+        let stream = manager.store.device_key_changes_stream();
+        pin_mut!(stream);
+
+        manager
+            .receive_keys_query_response(&TransactionId::new(), &other_key_query())
+            .await
+            .unwrap();
+        assert_pending!(stream);
+
+        let original_key = manager
+            .store
+            .get_device_data(other_user, other_device_id)
+            .await
+            .unwrap()
+            .unwrap()
+            .ed25519_key();
+
+        // The homeserver now returns a different Ed25519 key for the same device.
+        let new_key = Ed25519SecretKey::new().public_key();
+        let response = ruma_response_from_json(&json!({
+            "device_keys": {
+                "@example2:localhost": {
+                    "SKISMLNIMH": {
+                        "algorithms": ["m.olm.v1.curve25519-aes-sha2", "m.megolm.v1.aes-sha2"],
+                        "device_id": "SKISMLNIMH",
+                        "keys": {
+                            "curve25519:SKISMLNIMH": "qO9xFazIcW8dE0oqHGMojGgJwbBpMOhGnIfJy2pzvmI",
+                            "ed25519:SKISMLNIMH": new_key.to_base64(),
+                        },
+                        "signatures": {},
+                        "user_id": "@example2:localhost",
+                    }
+                }
+            },
+            "failures": {},
+        }));
+        manager.receive_keys_query_response(&TransactionId::new(), &response).await.unwrap();
+
+        // The change is flagged.
+        let change = assert_ready!(stream);
+        assert_eq!(change.user_id, other_user);
+        assert_eq!(change.device_id, other_device_id);
+        assert_eq!(change.old_ed25519_key, original_key);
+        assert_eq!(change.new_ed25519_key, Some(new_key));
+
+        // And the device keeps the key that was first seen.
+        let device =
+            manager.store.get_device_data(other_user, other_device_id).await.unwrap().unwrap();
+        assert_eq!(device.ed25519_key(), original_key);
+    }
+
     #[async_test]
     async fn test_identities_stream() {
         let manager = manager_test_helper(user_id(), device_id()).await;
@@ -65,6 +65,11 @@ pub struct RoomKeyImportResult {
     /// It's a map from room id to a map of the sender key to a set of session
     /// ids.
     pub keys: BTreeMap<OwnedRoomId, BTreeMap<String, BTreeSet<String>>>,
+    /// The map of keys that couldn't be decrypted, when importing keys from a
+    /// backup with [`backups::BackupMachine::decrypt_and_import_room_keys`].
+    ///
+    /// It's a map from room id to a set of session ids.
+    pub failed: BTreeMap<OwnedRoomId, BTreeSet<String>>,
 }
 
 impl RoomKeyImportResult {
@@ -73,7 +78,7 @@ impl RoomKeyImportResult {
         total_count: usize,
         keys: BTreeMap<OwnedRoomId, BTreeMap<String, BTreeSet<String>>>,
     ) -> Self {
-        Self { imported_count, total_count, keys }
+        Self { imported_count, total_count, keys, failed: BTreeMap::new() }
     }
 }
 
@@ -83,7 +88,7 @@ pub use error::{
 };
 pub use file_encryption::{
     decrypt_room_key_export, encrypt_room_key_export, AttachmentDecryptor, AttachmentEncryptor,
-    DecryptorError, KeyExportError, MediaEncryptionInfo,
+    DecryptorError, EncryptedStoreExport, KeyExportError, MediaEncryptionInfo, StoreExportError,
 };
 pub use gossiping::{GossipRequest, GossippedSecret};
 pub use identities::{
@@ -66,12 +66,13 @@ use crate::{
     backups::{BackupMachine, MegolmV1BackupKey},
     dehydrated_devices::{DehydratedDevices, DehydrationError},
     error::{EventError, MegolmError, MegolmResult, OlmError, OlmResult, SetRoomSettingsError},
+    file_encryption::{EncryptedStoreExport, StoreExport, StoreExportError},
     gossiping::GossipMachine,
     identities::{user::UserIdentity, Device, IdentityManager, UserDevices},
     olm::{
         Account, CrossSigningStatus, EncryptionSettings, IdentityKeys, InboundGroupSession,
         KnownSenderData, OlmDecryptionInfo, PrivateCrossSigningIdentity, SenderData,
-        SenderDataFinder, SessionType, StaticAccountData,
+        SenderDataFinder, Session, SessionType, StaticAccountData,
     },
     session_manager::{GroupSessionManager, SessionManager},
     store::{
@@ -1903,6 +1904,18 @@ impl OlmMachine {
                     &decryption_settings.sender_device_trust_requirement,
                 )?;
 
+                // The use count is only used to prioritize the room keys to back up, failing
+                // to update it shouldn't prevent the event from being decrypted.
+                if let Err(error) = self
+                    .inner
+                    .store
+                    .crypto_store()
+                    .record_inbound_group_session_use(room_id, session.session_id())
+                    .await
+                {
+                    warn!(?error, "Failed to record the use of the room key");
+                }
+
                 Ok((decrypted_event, encryption_info))
             }
             Err(error) => Err(
@@ -2530,6 +2543,134 @@ impl OlmMachine {
         self.store().import_cross_signing_keys(export).await
     }
 
+    /// Export the secrets of this device, encrypted with the given passphrase,
+    /// so they can be restored into another crypto store.
+    ///
+    /// Unlike a room key export, this contains the Olm account (and thus the
+    /// identity keys of this device), the private cross-signing keys and the
+    /// backup decryption key, on top of the room keys. This allows migrating
+    /// an account from one store implementation to another.
+    ///
+    /// This isn't a full copy of the store:
+    ///
+    /// * Olm sessions are only exported for the devices of tracked users that
+    ///   are still known to the store. Sessions with other devices are lost,
+    ///   and will be recreated when needed.
+    /// * The devices and user identities of other users aren't exported, they
+    ///   will be fetched again from the homeserver after the import.
+    /// * The hashes of the Olm messages already received aren't exported, so
+    ///   replayed to-device messages can't be detected across the migration.
+    ///
+    /// # Arguments
+    ///
+    /// * `passphrase` - The passphrase that will be used to encrypt the export.
+    ///
+    /// * `rounds` - The number of rounds that should be used for the key
+    ///   derivation, see [`crate::encrypt_room_key_export`].
+    pub async fn export_store(
+        &self,
+        passphrase: &str,
+        rounds: u32,
+    ) -> Result<EncryptedStoreExport, StoreExportError> {
+        let account = self.store().cache().await?.account().await?.pickle();
+        let private_identity = self.inner.user_identity.lock().await.pickle().await;
+
+        let mut sessions = Vec::new();
+        for user_id in self.tracked_users().await? {
+            for device in self.store().get_device_data_for_user(&user_id).await?.into_values() {
+                let Some(curve_key) = device.curve25519_key() else { continue };
+
+                if let Some(device_sessions) =
+                    self.store().get_sessions(&curve_key.to_base64()).await?
+                {
+                    for session in device_sessions.lock().await.iter() {
+                        sessions.push(session.pickle().await);
+                    }
+                }
+            }
+        }
+
+        let mut inbound_group_sessions = Vec::new();
+        for session in self.store().get_inbound_group_sessions().await? {
+            inbound_group_sessions.push(session.pickle().await);
+        }
+
+        let backup_keys = self.store().load_backup_keys().await?;
+
+        let export = StoreExport {
+            account,
+            private_identity,
+            sessions,
+            inbound_group_sessions,
+            backup_decryption_key: backup_keys.decryption_key,
+            backup_version: backup_keys.backup_version,
+        };
+
+        Ok(EncryptedStoreExport::encrypt(&export, passphrase, rounds)?)
+    }
+
+    /// Restore an export created by [`OlmMachine::export_store`] into the
+    /// given crypto store, and create an [`OlmMachine`] using it.
+    ///
+    /// The store must not contain an account yet, otherwise
+    /// [`StoreExportError::AccountAlreadyExists`] is returned.
+    ///
+    /// # Arguments
+    ///
+    /// * `store` - The crypto store to import the export into.
+    ///
+    /// * `export` - The encrypted export.
+    ///
+    /// * `passphrase` - The passphrase that was used to encrypt the export.
+    pub async fn import_store(
+        store: impl IntoCryptoStore,
+        export: &EncryptedStoreExport,
+        passphrase: &str,
+    ) -> Result<Self, StoreExportError> {
+        let store = store.into_crypto_store();
+
+        if store.load_account().await?.is_some() {
+            return Err(StoreExportError::AccountAlreadyExists);
+        }
+
+        let export = export.decrypt(passphrase)?;
+
+        let account = Account::from_pickle(export.account)?;
+        let user_id = account.user_id().to_owned();
+        let device_id = account.device_id().to_owned();
+        let our_device_keys = account.device_keys();
+
+        let sessions = export
+            .sessions
+            .into_iter()
+            .map(|pickle| Session::from_pickle(our_device_keys.clone(), pickle))
+            .collect::<Result<_, _>>()?;
+        let inbound_group_sessions = export
+            .inbound_group_sessions
+            .into_iter()
+            .map(InboundGroupSession::from_pickle)
+            .collect::<Result<_, _>>()?;
+        let private_identity = PrivateCrossSigningIdentity::from_pickle(export.private_identity)?;
+
+        // As in `with_store`, our own device is trusted since we own its private keys.
+        let device = DeviceData::from_account(&account);
+        device.set_trust_state(LocalTrust::Verified);
+
+        let changes = Changes {
+            private_identity: Some(private_identity),
+            sessions,
+            inbound_group_sessions,
+            devices: DeviceChanges { new: vec![device], ..Default::default() },
+            backup_decryption_key: export.backup_decryption_key,
+            backup_version: export.backup_version,
+            ..Default::default()
+        };
+        store.save_changes(changes).await?;
+        store.save_pending_changes(PendingChanges { account: Some(account) }).await?;
+
+        Ok(Self::with_store(&user_id, &device_id, store, None).await?)
+    }
+
     async fn sign_with_master_key(
         &self,
         message: &str,
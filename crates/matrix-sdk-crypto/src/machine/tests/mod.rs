@@ -78,8 +78,8 @@ use crate::{
     },
     utilities::json_convert,
     verification::tests::bob_id,
-    Account, DecryptionSettings, DeviceData, EncryptionSettings, LocalTrust, MegolmError, OlmError,
-    RoomEventDecryptionResult, TrustRequirement,
+    Account, DecryptionSettings, DeviceData, EncryptionSettings, KeyExportError, LocalTrust,
+    MegolmError, OlmError, RoomEventDecryptionResult, StoreExportError, TrustRequirement,
 };
 
 mod decryption_verification_state;
@@ -702,6 +702,16 @@ async fn test_megolm_encryption() {
     if let Some(igs) = room_keys_received_stream.next().now_or_never() {
         panic!("Session stream unexpectedly returned update: {igs:?}");
     }
+
+    // But the use of the room key is recorded.
+    bob.store().crypto_store().flush_room_key_uses().await.unwrap();
+    let stored_session = bob
+        .store()
+        .get_inbound_group_session(room_id, group_session.session_id())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(stored_session.use_count(), 1);
 }
 
 #[async_test]
@@ -1712,3 +1722,109 @@ async fn test_verified_latch_migration() {
         assert!(!tu.dirty);
     });
 }
+
+#[async_test]
+async fn test_export_and_import_store() {
+    let (alice, bob) = get_machine_pair_with_session(alice_id(), user_id(), false).await;
+    alice.update_tracked_users([bob.user_id()]).await.unwrap();
+    alice.bootstrap_cross_signing(false).await.unwrap();
+
+    let room_id = room_id!("!test:example.org");
+    alice.create_outbound_group_session_with_defaults_test_helper(room_id).await.unwrap();
+
+    let backup_decryption_key = BackupDecryptionKey::new().unwrap();
+    alice
+        .store()
+        .save_changes(Changes {
+            backup_decryption_key: Some(backup_decryption_key.clone()),
+            backup_version: Some("1".to_owned()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    let export = alice.export_store("1234", 1).await.unwrap();
+
+    // The export can't be decrypted with another passphrase.
+    assert_matches!(
+        OlmMachine::import_store(MemoryStore::new(), &export, "4321").await,
+        Err(StoreExportError::Decryption(KeyExportError::InvalidMac))
+    );
+
+    let imported = OlmMachine::import_store(MemoryStore::new(), &export, "1234").await.unwrap();
+
+    // The identity of the device has been restored.
+    assert_eq!(imported.user_id(), alice.user_id());
+    assert_eq!(imported.device_id(), alice.device_id());
+    assert_eq!(imported.identity_keys().ed25519, alice.identity_keys().ed25519);
+    assert_eq!(imported.identity_keys().curve25519, alice.identity_keys().curve25519);
+    assert!(imported.cross_signing_status().await.is_complete());
+
+    // So have the Olm session with Bob and the room key.
+    let bob_sender_key = bob.identity_keys().curve25519.to_base64();
+    let sessions = imported.store().get_sessions(&bob_sender_key).await.unwrap().unwrap();
+    assert_eq!(sessions.lock().await.len(), 1);
+    assert_eq!(imported.store().get_inbound_group_sessions().await.unwrap().len(), 1);
+
+    // And the backup decryption key.
+    let backup_keys = imported.store().load_backup_keys().await.unwrap();
+    assert_eq!(backup_keys.decryption_key.unwrap().to_base64(), backup_decryption_key.to_base64());
+    assert_eq!(backup_keys.backup_version.as_deref(), Some("1"));
+
+    // Importing into a store that already has an account fails.
+    let store = Arc::new(MemoryStore::new());
+    OlmMachine::with_store(alice_id(), alice_device_id(), store.clone(), None).await.unwrap();
+    assert_matches!(
+        OlmMachine::import_store(store, &export, "1234").await,
+        Err(StoreExportError::AccountAlreadyExists)
+    );
+}
+
+#[async_test]
+async fn test_export_store_without_tracked_devices() {
+    // Alice has an Olm session with Bob, but doesn't track Bob's devices.
+    let (alice, bob) = get_machine_pair_with_session(alice_id(), user_id(), false).await;
+    assert!(!alice.tracked_users().await.unwrap().contains(bob.user_id()));
+
+    let export = alice.export_store("1234", 1).await.unwrap();
+    let imported = OlmMachine::import_store(MemoryStore::new(), &export, "1234").await.unwrap();
+
+    // The account is restored, but the session with Bob's untracked device isn't
+    // part of the export.
+    assert_eq!(imported.identity_keys().curve25519, alice.identity_keys().curve25519);
+    let bob_sender_key = bob.identity_keys().curve25519.to_base64();
+    assert!(imported.store().get_sessions(&bob_sender_key).await.unwrap().is_none());
+}
+
+#[async_test]
+async fn test_room_key_rotation_after_message_count() {
+    let (alice, bob) =
+        get_machine_pair_with_setup_sessions_test_helper(alice_id(), user_id(), false).await;
+    let room_id = room_id!("!test:example.org");
+    let settings = EncryptionSettings::default().rotation_period_messages(2);
+
+    let outbound_session_id = || {
+        alice
+            .inner
+            .group_session_manager
+            .get_outbound_group_session(room_id)
+            .expect("there should be an outbound session")
+            .session_id()
+            .to_owned()
+    };
+
+    let mut session_ids = Vec::new();
+
+    // Send three messages, sharing the room key before each of them as the SDK
+    // does.
+    for body in ["1", "2", "3"] {
+        alice.share_room_key(room_id, iter::once(bob.user_id()), settings.clone()).await.unwrap();
+        alice.encrypt_room_event(room_id, RoomMessageEventContent::text_plain(body)).await.unwrap();
+        session_ids.push(outbound_session_id());
+    }
+
+    // The first two messages used the same session, which was rotated for the third
+    // one.
+    assert_eq!(session_ids[0], session_ids[1]);
+    assert_ne!(session_ids[1], session_ids[2]);
+}
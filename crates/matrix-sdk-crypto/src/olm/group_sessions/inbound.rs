@@ -17,14 +17,14 @@ use std::{
     fmt,
     ops::Deref,
     sync::{
-        atomic::{AtomicBool, Ordering::SeqCst},
+        atomic::{AtomicBool, AtomicU64, Ordering::SeqCst},
         Arc,
     },
 };
 
 use ruma::{
     events::room::history_visibility::HistoryVisibility, serde::JsonObject, DeviceKeyAlgorithm,
-    OwnedRoomId, RoomId,
+    OwnedRoomId, RoomId, SecondsSinceUnixEpoch, UInt,
 };
 use serde::{Deserialize, Serialize};
 use tokio::sync::Mutex;
@@ -217,6 +217,14 @@ pub struct InboundGroupSession {
     ///
     /// [MSC3061]: https://github.com/matrix-org/matrix-spec-proposals/pull/3061
     shared_history: bool,
+
+    /// The Unix timestamp when this [`InboundGroupSession`] was created
+    /// locally.
+    creation_time: SecondsSinceUnixEpoch,
+
+    /// The number of events this [`InboundGroupSession`] has decrypted, as
+    /// recorded in the store.
+    use_count: Arc<AtomicU64>,
 }
 
 impl InboundGroupSession {
@@ -291,6 +299,8 @@ impl InboundGroupSession {
             algorithm: encryption_algorithm.into(),
             backed_up: AtomicBool::new(false).into(),
             shared_history,
+            creation_time: SecondsSinceUnixEpoch::now(),
+            use_count: Default::default(),
         })
     }
 
@@ -356,6 +366,8 @@ impl InboundGroupSession {
             history_visibility: self.history_visibility.as_ref().clone(),
             algorithm: (*self.algorithm).to_owned(),
             shared_history: self.shared_history,
+            creation_time: self.creation_time,
+            use_count: self.use_count(),
         }
     }
 
@@ -388,6 +400,34 @@ impl InboundGroupSession {
         self.backed_up.store(true, SeqCst)
     }
 
+    /// The Unix timestamp when this session was created locally.
+    ///
+    /// Sessions which were created before the creation time was tracked
+    /// report the Unix epoch.
+    pub fn creation_time(&self) -> SecondsSinceUnixEpoch {
+        self.creation_time
+    }
+
+    /// The number of events this session has decrypted.
+    ///
+    /// Sessions which were created before their use was tracked only count the
+    /// events decrypted since then.
+    pub fn use_count(&self) -> u64 {
+        self.use_count.load(SeqCst)
+    }
+
+    /// Record that this session has decrypted the given number of events.
+    pub(crate) fn add_uses(&self, uses: u64) {
+        self.use_count.fetch_add(uses, SeqCst);
+    }
+
+    /// Make sure the use count of this session isn't lower than the given one,
+    /// e.g. the one of the stored copy of this session, which may have been
+    /// updated since this copy was loaded.
+    pub(crate) fn keep_use_count(&self, use_count: u64) {
+        self.use_count.fetch_max(use_count, SeqCst);
+    }
+
     /// Get the map of signing keys this session was received from.
     pub fn signing_keys(&self) -> &SigningKeys<DeviceKeyAlgorithm> {
         &self.creator_info.signing_keys
@@ -435,6 +475,8 @@ impl InboundGroupSession {
             history_visibility,
             algorithm,
             shared_history,
+            creation_time,
+            use_count,
         } = pickle;
 
         let session: InnerSession = pickle.into();
@@ -456,6 +498,8 @@ impl InboundGroupSession {
             algorithm: algorithm.into(),
             imported,
             shared_history,
+            creation_time,
+            use_count: AtomicU64::from(use_count).into(),
         })
     }
 
@@ -666,12 +710,24 @@ pub struct PickledInboundGroupSession {
     /// [MSC3061]: https://github.com/matrix-org/matrix-spec-proposals/pull/3061
     #[serde(default)]
     pub shared_history: bool,
+    /// The Unix timestamp when the session was created locally.
+    #[serde(default = "default_creation_time")]
+    pub creation_time: SecondsSinceUnixEpoch,
+    /// The number of events the session has decrypted.
+    #[serde(default)]
+    pub use_count: u64,
 }
 
 fn default_algorithm() -> EventEncryptionAlgorithm {
     EventEncryptionAlgorithm::MegolmV1AesSha2
 }
 
+/// Sessions pickled before their creation time was tracked are considered to
+/// be the oldest ones.
+fn default_creation_time() -> SecondsSinceUnixEpoch {
+    SecondsSinceUnixEpoch(UInt::MIN)
+}
+
 impl TryFrom<&HistoricRoomKey> for InboundGroupSession {
     type Error = SessionCreationError;
 
@@ -706,6 +762,8 @@ impl TryFrom<&HistoricRoomKey> for InboundGroupSession {
             algorithm: algorithm.to_owned().into(),
             backed_up: AtomicBool::from(false).into(),
             shared_history: true,
+            creation_time: SecondsSinceUnixEpoch::now(),
+            use_count: Default::default(),
         })
     }
 }
@@ -746,6 +804,8 @@ impl TryFrom<&ExportedRoomKey> for InboundGroupSession {
             algorithm: algorithm.to_owned().into(),
             backed_up: AtomicBool::from(false).into(),
             shared_history: *shared_history,
+            creation_time: SecondsSinceUnixEpoch::now(),
+            use_count: Default::default(),
         })
     }
 }
@@ -777,6 +837,8 @@ impl From<&ForwardedMegolmV1AesSha2Content> for InboundGroupSession {
             algorithm: EventEncryptionAlgorithm::MegolmV1AesSha2.into(),
             backed_up: AtomicBool::from(false).into(),
             shared_history: false,
+            creation_time: SecondsSinceUnixEpoch::now(),
+            use_count: Default::default(),
         }
     }
 }
@@ -804,6 +866,8 @@ impl From<&ForwardedMegolmV2AesSha2Content> for InboundGroupSession {
             algorithm: EventEncryptionAlgorithm::MegolmV1AesSha2.into(),
             backed_up: AtomicBool::from(false).into(),
             shared_history: false,
+            creation_time: SecondsSinceUnixEpoch::now(),
+            use_count: Default::default(),
         }
     }
 }
@@ -830,7 +894,7 @@ mod tests {
     use matrix_sdk_test::async_test;
     use ruma::{
         device_id, events::room::history_visibility::HistoryVisibility, owned_room_id, room_id,
-        user_id, DeviceId, UserId,
+        user_id, DeviceId, SecondsSinceUnixEpoch, UInt, UserId,
     };
     use serde_json::json;
     use similar_asserts::assert_eq;
@@ -866,6 +930,7 @@ mod tests {
             ".pickle.signing_key" => "[signing_key]",
             ".sender_key" => "[sender_key]",
             ".signing_key.ed25519" => "[ed25519_key]",
+            ".creation_time" => "[creation_time]",
         });
     }
 
@@ -925,6 +990,10 @@ mod tests {
         );
         assert!(legacy_session);
         assert!(!owner_check_failed);
+
+        // And the session is considered to be the oldest one, and unused.
+        assert_eq!(unpickled.creation_time(), SecondsSinceUnixEpoch(UInt::MIN));
+        assert_eq!(unpickled.use_count(), 0);
     }
 
     #[async_test]
@@ -988,7 +1057,9 @@ mod tests {
                 "backed_up":false,
                 "shared_history":false,
                 "history_visibility":"shared",
-                "algorithm":"m.megolm.v1.aes-sha2"
+                "algorithm":"m.megolm.v1.aes-sha2",
+                "creation_time": igs.creation_time(),
+                "use_count": 0
             })
         );
     }
@@ -139,6 +139,24 @@ impl EncryptionSettings {
             sharing_strategy,
         }
     }
+
+    /// Set how long a session should be used before rotating it.
+    ///
+    /// Unless the `_disable-minimum-rotation-period-ms` feature is enabled,
+    /// sessions are used for at least one hour, whatever this value is.
+    pub fn rotation_period(mut self, rotation_period: Duration) -> Self {
+        self.rotation_period = rotation_period;
+        self
+    }
+
+    /// Set how many messages can be encrypted with a session before rotating
+    /// it.
+    ///
+    /// The value is clamped between 1 and 10,000 messages.
+    pub fn rotation_period_messages(mut self, rotation_period_msgs: u64) -> Self {
+        self.rotation_period_msgs = rotation_period_msgs;
+        self
+    }
 }
 
 /// Outbound group session.
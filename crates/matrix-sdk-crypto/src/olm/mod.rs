@@ -35,7 +35,9 @@ pub use group_sessions::{
     SenderDataType, SessionCreationError, SessionExportError, SessionKey, ShareInfo,
 };
 pub use session::{PickledSession, Session};
-pub use signing::{CrossSigningStatus, PickledCrossSigningIdentity, PrivateCrossSigningIdentity};
+pub use signing::{
+    CrossSigningStatus, PickledCrossSigningIdentity, PrivateCrossSigningIdentity, SigningError,
+};
 pub(crate) use utility::{SignedJsonObject, VerifyJson};
 pub use vodozemac::{olm::IdentityKeys, Curve25519PublicKey};
 
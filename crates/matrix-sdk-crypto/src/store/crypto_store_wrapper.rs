@@ -1,16 +1,22 @@
-use std::{future, ops::Deref, sync::Arc};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    future, mem,
+    ops::Deref,
+    sync::Arc,
+};
 
 use futures_core::Stream;
 use futures_util::StreamExt;
-use matrix_sdk_common::store_locks::CrossProcessStoreLock;
-use ruma::{DeviceId, OwnedDeviceId, OwnedUserId, UserId};
+use matrix_sdk_common::{locks::Mutex as StdMutex, store_locks::CrossProcessStoreLock};
+use ruma::{DeviceId, OwnedDeviceId, OwnedRoomId, OwnedUserId, RoomId, UserId};
 use tokio::sync::{broadcast, Mutex};
 use tokio_stream::wrappers::{errors::BroadcastStreamRecvError, BroadcastStream};
 use tracing::{debug, trace, warn};
 
 use super::{
-    caches::SessionStore, types::RoomKeyBundleInfo, DeviceChanges, IdentityChanges,
-    LockableCryptoStore,
+    caches::SessionStore,
+    types::{DeviceKeyChanged, RoomKeyBundleInfo, RoomKeyCounts},
+    DeviceChanges, IdentityChanges, LockableCryptoStore,
 };
 use crate::{
     olm::InboundGroupSession,
@@ -19,6 +25,10 @@ use crate::{
     CryptoStoreError, GossippedSecret, OwnUserIdentityData, Session, UserIdentityData,
 };
 
+/// The number of recorded uses of the room keys after which they're written to
+/// the store.
+const ROOM_KEY_USES_FLUSH_THRESHOLD: u64 = 100;
+
 /// A wrapper for crypto store implementations that adds update notifiers.
 ///
 /// This is shared between [`StoreInner`] and
@@ -53,6 +63,89 @@ pub(crate) struct CryptoStoreWrapper {
     /// The sender side of a broadcast channel which sends out information about
     /// historic room key bundles we have received.
     historic_room_key_bundles_broadcaster: broadcast::Sender<RoomKeyBundleInfo>,
+
+    /// The sender side of a broadcast channel which sends out information about
+    /// devices whose Ed25519 key changed, and whose update has been rejected.
+    device_key_changes_broadcaster: broadcast::Sender<DeviceKeyChanged>,
+
+    /// The room key counts, kept up to date as room keys are saved and backed
+    /// up.
+    room_key_counts: StdMutex<RoomKeyCountsCache>,
+
+    /// The number of events each room key has decrypted since its use count
+    /// was last written to the store.
+    pending_room_key_uses: StdMutex<BTreeMap<(OwnedRoomId, String), u64>>,
+
+    /// Serializes the writes of room keys, so that the use counts written by
+    /// [`CryptoStoreWrapper::flush_room_key_uses`] and the other writes don't
+    /// overwrite each other.
+    room_keys_write_lock: Mutex<()>,
+}
+
+/// The room key counts computed by
+/// [`CryptoStoreWrapper::inbound_group_session_counts`], kept up to date by the
+/// writes of room keys.
+#[derive(Debug, Default)]
+struct RoomKeyCountsCache {
+    /// The cached counts, along with the backup version used to count the
+    /// backed up room keys.
+    counts: Option<(Option<String>, RoomKeyCounts)>,
+
+    /// Incremented each time a write of room keys starts, to detect writes
+    /// racing with a recount.
+    generation: u64,
+
+    /// The number of writes of room keys in progress.
+    pending_writes: usize,
+}
+
+/// A write of room keys in progress, which must be accounted for in the
+/// [`RoomKeyCountsCache`] once it's done.
+///
+/// If it's dropped without being finished, because the write failed or was
+/// cancelled, the cached counts are invalidated.
+struct RoomKeyCountsUpdate<'a> {
+    cache: &'a StdMutex<RoomKeyCountsCache>,
+    finished: bool,
+}
+
+impl<'a> RoomKeyCountsUpdate<'a> {
+    fn start(cache: &'a StdMutex<RoomKeyCountsCache>) -> Self {
+        let mut guard = cache.lock();
+        guard.generation += 1;
+        guard.pending_writes += 1;
+
+        Self { cache, finished: false }
+    }
+
+    /// Whether there are cached counts to update.
+    ///
+    /// Once the write has started, the counts can't be cached by a concurrent
+    /// recount until it's done, so this doesn't change before
+    /// [`Self::finish`] is called.
+    fn has_counts(&self) -> bool {
+        self.cache.lock().counts.is_some()
+    }
+
+    /// Update the cached counts, if any, now that the write succeeded.
+    fn finish(mut self, update: impl FnOnce(Option<&str>, &mut RoomKeyCounts)) {
+        if let Some((backup_version, counts)) = &mut self.cache.lock().counts {
+            update(backup_version.as_deref(), counts);
+        }
+
+        self.finished = true;
+    }
+}
+
+impl Drop for RoomKeyCountsUpdate<'_> {
+    fn drop(&mut self) {
+        let mut guard = self.cache.lock();
+        guard.pending_writes -= 1;
+
+        if !self.finished {
+            guard.counts = None;
+        }
+    }
 }
 
 impl CryptoStoreWrapper {
@@ -64,6 +157,7 @@ impl CryptoStoreWrapper {
         // devices, that's why we increase the capacity here.
         let identities_broadcaster = broadcast::Sender::new(20);
         let historic_room_key_bundles_broadcaster = broadcast::Sender::new(10);
+        let device_key_changes_broadcaster = broadcast::Sender::new(10);
 
         Self {
             user_id: user_id.to_owned(),
@@ -75,6 +169,10 @@ impl CryptoStoreWrapper {
             secrets_broadcaster,
             identities_broadcaster,
             historic_room_key_bundles_broadcaster,
+            device_key_changes_broadcaster,
+            room_key_counts: Default::default(),
+            pending_room_key_uses: Default::default(),
+            room_keys_write_lock: Default::default(),
         }
     }
 
@@ -135,8 +233,24 @@ impl CryptoStoreWrapper {
             }
         }
 
+        let room_keys_write = if changes.inbound_group_sessions.is_empty() {
+            None
+        } else {
+            let guard = self.room_keys_write_lock.lock().await;
+            let update = RoomKeyCountsUpdate::start(&self.room_key_counts);
+            let new_room_keys =
+                self.prepare_room_keys_for_saving(&changes.inbound_group_sessions, None).await?;
+            Some((guard, update, new_room_keys))
+        };
+
         self.store.save_changes(changes).await?;
 
+        if let Some((_guard, update, new_room_keys)) = room_keys_write {
+            if let Some(new_room_keys) = new_room_keys {
+                update.finish(|_, counts| counts.total += new_room_keys);
+            }
+        }
+
         // If we updated our own public identity, log it for debugging purposes
         if tracing::level_enabled!(tracing::Level::DEBUG) {
             for updated_identity in
@@ -302,8 +416,25 @@ impl CryptoStoreWrapper {
         backed_up_to_version: Option<&str>,
     ) -> store::Result<()> {
         let room_key_updates: Vec<_> = sessions.iter().map(RoomKeyInfo::from).collect();
+
+        let _room_keys_write_guard = self.room_keys_write_lock.lock().await;
+        let room_key_counts_update = RoomKeyCountsUpdate::start(&self.room_key_counts);
+        let new_room_keys =
+            self.prepare_room_keys_for_saving(&sessions, backed_up_to_version).await?;
+
         self.store.save_inbound_group_sessions(sessions, backed_up_to_version).await?;
 
+        if let Some(new_room_keys) = new_room_keys {
+            room_key_counts_update.finish(|counted_backup_version, counts| {
+                counts.total += new_room_keys;
+
+                if backed_up_to_version.is_some() && backed_up_to_version == counted_backup_version
+                {
+                    counts.backed_up += new_room_keys;
+                }
+            });
+        }
+
         if !room_key_updates.is_empty() {
             // Ignore the result. It can only fail if there are no listeners.
             let _ = self.room_keys_received_sender.send(room_key_updates);
@@ -311,6 +442,244 @@ impl CryptoStoreWrapper {
         Ok(())
     }
 
+    /// Get the number of room keys, and the number of room keys backed up in
+    /// the given backup version, from the store.
+    ///
+    /// The result is cached, see
+    /// [`CryptoStoreWrapper::cached_inbound_group_session_counts`].
+    pub async fn inbound_group_session_counts(
+        &self,
+        backup_version: Option<&str>,
+    ) -> store::Result<RoomKeyCounts> {
+        let generation = {
+            let cache = self.room_key_counts.lock();
+            (cache.pending_writes == 0).then_some(cache.generation)
+        };
+
+        let counts = self.store.inbound_group_session_counts(backup_version).await?;
+
+        // Only cache the counts if no room keys have been written in the meantime,
+        // since we can't know whether they've been counted or not.
+        let mut cache = self.room_key_counts.lock();
+        if generation.is_some_and(|generation| generation == cache.generation) {
+            cache.counts = Some((backup_version.map(ToOwned::to_owned), counts.clone()));
+        }
+
+        Ok(counts)
+    }
+
+    /// Get the room key counts computed by the last call to
+    /// [`CryptoStoreWrapper::inbound_group_session_counts`], for the same
+    /// backup version, and kept up to date since then.
+    ///
+    /// Returns `None` if the room keys haven't been counted yet for this backup
+    /// version, or if the counts had to be invalidated.
+    pub fn cached_inbound_group_session_counts(
+        &self,
+        backup_version: Option<&str>,
+    ) -> Option<RoomKeyCounts> {
+        let cache = self.room_key_counts.lock();
+        let (counted_backup_version, counts) = cache.counts.as_ref()?;
+
+        if counted_backup_version.as_deref() == backup_version {
+            Some(counts.clone())
+        } else if backup_version.is_none() {
+            // Nothing is backed up without a backup, the total is still valid though.
+            Some(RoomKeyCounts { total: counts.total, backed_up: 0 })
+        } else {
+            None
+        }
+    }
+
+    /// Mark the given inbound group sessions as backed up in the given backup
+    /// version.
+    pub async fn mark_inbound_group_sessions_as_backed_up(
+        &self,
+        backup_version: &str,
+        room_and_session_ids: &[(&RoomId, &str)],
+    ) -> store::Result<()> {
+        let _room_keys_write_guard = self.room_keys_write_lock.lock().await;
+        let room_key_counts_update = RoomKeyCountsUpdate::start(&self.room_key_counts);
+
+        // Only the room keys which weren't backed up yet change the counts. We can't
+        // know in which backup version a room key was backed up, so the counts are
+        // invalidated if some of them were.
+        let mut newly_backed_up = Some(0);
+
+        if room_key_counts_update.has_counts() {
+            for &(room_id, session_id) in room_and_session_ids {
+                match self.store.get_inbound_group_session(room_id, session_id).await? {
+                    Some(session) if session.backed_up() => {
+                        newly_backed_up = None;
+                        break;
+                    }
+                    Some(_) => newly_backed_up = newly_backed_up.map(|count| count + 1),
+                    None => {}
+                }
+            }
+        }
+
+        self.store
+            .mark_inbound_group_sessions_as_backed_up(backup_version, room_and_session_ids)
+            .await?;
+
+        if let Some(newly_backed_up) = newly_backed_up {
+            room_key_counts_update.finish(|counted_backup_version, counts| {
+                if counted_backup_version == Some(backup_version) {
+                    counts.backed_up += newly_backed_up;
+                }
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Prepare the given room keys to be saved, by comparing them to their
+    /// stored copies.
+    ///
+    /// The use counts of the stored copies are kept, since they may have been
+    /// written since the given room keys were loaded.
+    ///
+    /// Returns the number of room keys which aren't in the store yet, to update
+    /// the cached room key counts once they're saved. Room keys which are
+    /// already in the store don't change the counts, unless their backup state
+    /// changes. We can't know in which backup version a stored room key was
+    /// backed up, so `None` is returned in this case, to invalidate the cached
+    /// counts.
+    async fn prepare_room_keys_for_saving(
+        &self,
+        sessions: &[InboundGroupSession],
+        backed_up_to_version: Option<&str>,
+    ) -> store::Result<Option<usize>> {
+        let mut new_room_keys = BTreeSet::new();
+        let mut backup_state_changed = false;
+
+        for session in sessions {
+            match self
+                .store
+                .get_inbound_group_session(session.room_id(), session.session_id())
+                .await?
+            {
+                None => {
+                    new_room_keys.insert((session.room_id(), session.session_id()));
+                }
+                Some(stored) => {
+                    session.keep_use_count(stored.use_count());
+
+                    if backed_up_to_version.is_some() || stored.backed_up() != session.backed_up() {
+                        backup_state_changed = true;
+                    }
+                }
+            }
+        }
+
+        Ok((!backup_state_changed).then_some(new_room_keys.len()))
+    }
+
+    /// Reset the backup state of all the stored inbound group sessions.
+    pub async fn reset_backup_state(&self) -> store::Result<()> {
+        let _room_keys_write_guard = self.room_keys_write_lock.lock().await;
+        let room_key_counts_update = RoomKeyCountsUpdate::start(&self.room_key_counts);
+
+        self.store.reset_backup_state().await?;
+
+        room_key_counts_update.finish(|_, counts| counts.backed_up = 0);
+
+        Ok(())
+    }
+
+    /// Record that the given inbound group session has decrypted one more
+    /// event.
+    ///
+    /// The uses are kept in memory, and written to the store once there are
+    /// enough of them, see [`CryptoStoreWrapper::flush_room_key_uses`]. The
+    /// uses which aren't written yet when the store is dropped are lost, which
+    /// is fine since the use counts are only used to prioritize the room keys
+    /// to back up.
+    pub(crate) async fn record_inbound_group_session_use(
+        &self,
+        room_id: &RoomId,
+        session_id: &str,
+    ) -> store::Result<()> {
+        let pending_uses = {
+            let mut pending_room_key_uses = self.pending_room_key_uses.lock();
+            *pending_room_key_uses
+                .entry((room_id.to_owned(), session_id.to_owned()))
+                .or_default() += 1;
+            pending_room_key_uses.values().sum::<u64>()
+        };
+
+        if pending_uses >= ROOM_KEY_USES_FLUSH_THRESHOLD {
+            self.flush_room_key_uses().await?;
+        }
+
+        Ok(())
+    }
+
+    /// Get the number of events the given inbound group session has decrypted
+    /// since its use count was last written to the store.
+    pub(crate) fn pending_room_key_uses(&self, session: &InboundGroupSession) -> u64 {
+        self.pending_room_key_uses
+            .lock()
+            .get(&(session.room_id().to_owned(), session.session_id().to_owned()))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Write the recorded uses of the room keys to the store, in a single
+    /// batch.
+    ///
+    /// The room keys are reloaded from the store before their use counts are
+    /// updated, and no other room keys are written in the meantime, so that a
+    /// better copy of a room key which was saved since it was loaded isn't
+    /// overwritten.
+    pub(crate) async fn flush_room_key_uses(&self) -> store::Result<()> {
+        let _room_keys_write_guard = self.room_keys_write_lock.lock().await;
+
+        let room_key_uses = mem::take(&mut *self.pending_room_key_uses.lock());
+        if room_key_uses.is_empty() {
+            return Ok(());
+        }
+
+        let mut sessions = Vec::with_capacity(room_key_uses.len());
+
+        for ((room_id, session_id), &uses) in &room_key_uses {
+            match self.store.get_inbound_group_session(room_id, session_id).await {
+                Ok(Some(session)) => {
+                    session.add_uses(uses);
+                    sessions.push(session);
+                }
+                // The room key isn't in the store anymore, there's nothing to update.
+                Ok(None) => {}
+                Err(error) => {
+                    self.restore_room_key_uses(room_key_uses);
+                    return Err(error);
+                }
+            }
+        }
+
+        // These aren't new room keys, so the room key counts don't change, and no room
+        // key update is sent out.
+        let changes = Changes { inbound_group_sessions: sessions, ..Default::default() };
+
+        if let Err(error) = self.store.save_changes(changes).await {
+            self.restore_room_key_uses(room_key_uses);
+            return Err(error);
+        }
+
+        Ok(())
+    }
+
+    /// Put back the uses of the room keys which couldn't be written to the
+    /// store, so that they're written by the next flush.
+    fn restore_room_key_uses(&self, room_key_uses: BTreeMap<(OwnedRoomId, String), u64>) {
+        let mut pending_room_key_uses = self.pending_room_key_uses.lock();
+
+        for (room_key, uses) in room_key_uses {
+            *pending_room_key_uses.entry(room_key).or_default() += uses;
+        }
+    }
+
     /// Receive notifications of room keys being received as a [`Stream`].
     ///
     /// Each time a room key is updated in any way, an update will be sent to
@@ -354,6 +723,20 @@ impl CryptoStoreWrapper {
         Self::filter_errors_out_of_stream(stream, "bundle_stream")
     }
 
+    /// Receive notifications of devices whose Ed25519 key changed as a
+    /// [`Stream`].
+    pub fn device_key_changes_stream(&self) -> impl Stream<Item = DeviceKeyChanged> {
+        let stream = BroadcastStream::new(self.device_key_changes_broadcaster.subscribe());
+        Self::filter_errors_out_of_stream(stream, "device_key_changes_stream")
+    }
+
+    /// Notify the listeners of [`Self::device_key_changes_stream`] that the
+    /// Ed25519 key of a device changed.
+    pub(crate) fn notify_device_key_changed(&self, change: DeviceKeyChanged) {
+        // Ignore the result. It can only fail if there are no listeners.
+        let _ = self.device_key_changes_broadcaster.send(change);
+    }
+
     /// Returns a stream of newly created or updated cryptographic identities.
     ///
     /// This is just a helper method which allows us to build higher level
@@ -412,10 +795,33 @@ impl Deref for CryptoStoreWrapper {
 #[cfg(test)]
 mod test {
     use matrix_sdk_test::async_test;
-    use ruma::user_id;
+    use ruma::{device_id, user_id};
 
     use super::*;
-    use crate::machine::test_helpers::get_machine_pair_with_setup_sessions_test_helper;
+    use crate::{
+        machine::test_helpers::get_machine_pair_with_setup_sessions_test_helper, store::MemoryStore,
+    };
+
+    #[async_test]
+    async fn test_room_key_counts_not_cached_during_writes() {
+        let store = CryptoStoreWrapper::new(
+            user_id!("@alice:example.com"),
+            device_id!("ALICEDEVICE"),
+            MemoryStore::new(),
+        );
+
+        // The room keys are counted while room keys are being written, so the counts
+        // aren't cached since the new room keys might have been counted already.
+        let update = RoomKeyCountsUpdate::start(&store.room_key_counts);
+        store.inbound_group_session_counts(None).await.unwrap();
+        update.finish(|_, counts| counts.total += 1);
+
+        assert!(store.cached_inbound_group_session_counts(None).is_none());
+
+        // Once the writes are done, the counts are cached.
+        store.inbound_group_session_counts(None).await.unwrap();
+        assert_eq!(store.cached_inbound_group_session_counts(None).unwrap().total, 0);
+    }
 
     #[async_test]
     async fn test_cache_cleared_after_device_update() {
@@ -39,6 +39,7 @@
 //! [`CryptoStore`]: trait.Cryptostore.html
 
 use std::{
+    cmp::Reverse,
     collections::{BTreeMap, BTreeSet, HashMap, HashSet},
     fmt::Debug,
     ops::Deref,
@@ -64,8 +65,9 @@ use types::RoomKeyBundleInfo;
 use vodozemac::{megolm::SessionOrdering, Curve25519PublicKey};
 
 use self::types::{
-    Changes, CrossSigningKeyExport, DeviceChanges, DeviceUpdates, IdentityChanges, IdentityUpdates,
-    PendingChanges, RoomKeyInfo, RoomKeyWithheldInfo, UserKeyQueryResult,
+    Changes, CrossSigningKeyExport, DeviceChanges, DeviceKeyChanged, DeviceUpdates,
+    IdentityChanges, IdentityUpdates, OrderBy, PendingChanges, RoomKeyInfo, RoomKeyWithheldInfo,
+    UserKeyQueryResult,
 };
 #[cfg(doc)]
 use crate::{backups::BackupMachine, identities::OwnUserIdentity};
@@ -528,6 +530,11 @@ pub enum SecretsBundleExportError {
 }
 
 impl Store {
+    /// The minimum number of room keys which need to be backed up that
+    /// [`Store::inbound_group_sessions_for_backup_ordered()`] loads to pick the
+    /// first ones in the requested order.
+    pub const ORDERED_BACKUP_BATCH_SIZE: usize = 1000;
+
     /// Create a new Store.
     pub(crate) fn new(
         account: StaticAccountData,
@@ -1360,6 +1367,19 @@ impl Store {
         self.inner.store.historic_room_key_stream()
     }
 
+    /// Receive notifications of devices whose Ed25519 key changed in a
+    /// `/keys/query` response.
+    ///
+    /// The Ed25519 key of a device is pinned the first time the device is
+    /// seen, and such updates are rejected. This stream allows to warn the user
+    /// about them.
+    ///
+    /// If the reader of the stream lags too far behind, a warning will be
+    /// logged and items will be dropped.
+    pub fn device_key_changes_stream(&self) -> impl Stream<Item = DeviceKeyChanged> {
+        self.inner.store.device_key_changes_stream()
+    }
+
     /// Import the given room keys into the store.
     ///
     /// # Arguments
@@ -1494,6 +1514,57 @@ impl Store {
         self.inner.store.clone()
     }
 
+    /// Get a batch of the room keys which haven't been backed up to the
+    /// backup with the given version yet, in the given order.
+    ///
+    /// Unlike [`CryptoStore::inbound_group_sessions_for_backup()`], which
+    /// returns the room keys in an arbitrary order, this lets clients back up
+    /// the most relevant room keys first.
+    ///
+    /// To keep the number of room keys loaded at once bounded, they're picked
+    /// among a batch of at least [`Store::ORDERED_BACKUP_BATCH_SIZE`] room keys
+    /// returned by [`CryptoStore::inbound_group_sessions_for_backup()`]. The
+    /// order is thus only exact if fewer room keys need to be backed up.
+    ///
+    /// # Arguments
+    ///
+    /// * `backup_version` - The version of the backup.
+    ///
+    /// * `limit` - The maximum number of room keys to return.
+    ///
+    /// * `order` - The order in which the room keys are returned. Room keys
+    ///   which were received before their creation time was tracked are
+    ///   considered to be the oldest ones, and only the uses since this SDK
+    ///   version are counted.
+    pub async fn inbound_group_sessions_for_backup_ordered(
+        &self,
+        backup_version: &str,
+        limit: usize,
+        order: OrderBy,
+    ) -> Result<Vec<InboundGroupSession>> {
+        let mut sessions = self
+            .inner
+            .store
+            .inbound_group_sessions_for_backup(
+                backup_version,
+                limit.max(Self::ORDERED_BACKUP_BATCH_SIZE),
+            )
+            .await?;
+
+        match order {
+            OrderBy::Oldest => sessions.sort_by_key(|session| session.creation_time()),
+            OrderBy::Newest => sessions.sort_by_key(|session| Reverse(session.creation_time())),
+            OrderBy::MostUsed => sessions.sort_by_cached_key(|session| {
+                // Count the uses which haven't been written to the store yet too.
+                Reverse(session.use_count() + self.inner.store.pending_room_key_uses(session))
+            }),
+        }
+
+        sessions.truncate(limit);
+
+        Ok(sessions)
+    }
+
     /// Export the keys that match the given predicate.
     ///
     /// # Arguments
@@ -1706,13 +1777,13 @@ mod tests {
     use futures_util::StreamExt;
     use insta::{_macro_support::Content, assert_json_snapshot, internals::ContentPath};
     use matrix_sdk_test::async_test;
-    use ruma::{device_id, room_id, user_id, RoomId};
-    use vodozemac::megolm::SessionKey;
+    use ruma::{device_id, room_id, user_id, RoomId, SecondsSinceUnixEpoch};
+    use vodozemac::megolm::{GroupSession, SessionKey};
 
     use crate::{
         machine::test_helpers::get_machine_pair,
         olm::{InboundGroupSession, SenderData},
-        store::types::DehydratedDeviceKey,
+        store::types::{DehydratedDeviceKey, OrderBy},
         types::EventEncryptionAlgorithm,
         OlmMachine,
     };
@@ -1955,6 +2026,119 @@ mod tests {
         });
     }
 
+    #[async_test]
+    async fn test_inbound_group_sessions_for_backup_ordered() {
+        let alice = OlmMachine::new(user_id!("@a:s.co"), device_id!("ALICE")).await;
+        let room_id = room_id!("!room1:localhost");
+
+        // Given three room keys created at different times…
+        let oldest = create_inbound_group_session_created_at(&alice, room_id, 1_000).await;
+        let middle = create_inbound_group_session_created_at(&alice, room_id, 2_000).await;
+        let newest = create_inbound_group_session_created_at(&alice, room_id, 3_000).await;
+        alice
+            .store()
+            .save_inbound_group_sessions(&[middle.clone(), newest.clone(), oldest.clone()])
+            .await
+            .unwrap();
+
+        // … where the oldest one decrypted two events, and the middle one a single one.
+        let crypto_store = alice.store().crypto_store();
+        for session in [&oldest, &oldest, &middle] {
+            crypto_store
+                .record_inbound_group_session_use(room_id, session.session_id())
+                .await
+                .unwrap();
+        }
+
+        let session_ids = |sessions: Vec<InboundGroupSession>| {
+            sessions.iter().map(|session| session.session_id().to_owned()).collect::<Vec<_>>()
+        };
+
+        // When I ask for the room keys to back up, then they come in the requested
+        // order, up to the limit.
+        let sessions = alice
+            .store()
+            .inbound_group_sessions_for_backup_ordered("1", 2, OrderBy::Oldest)
+            .await
+            .unwrap();
+        assert_eq!(session_ids(sessions), [oldest.session_id(), middle.session_id()]);
+
+        let sessions = alice
+            .store()
+            .inbound_group_sessions_for_backup_ordered("1", 2, OrderBy::Newest)
+            .await
+            .unwrap();
+        assert_eq!(session_ids(sessions), [newest.session_id(), middle.session_id()]);
+
+        let sessions = alice
+            .store()
+            .inbound_group_sessions_for_backup_ordered("1", 2, OrderBy::MostUsed)
+            .await
+            .unwrap();
+        assert_eq!(session_ids(sessions), [oldest.session_id(), middle.session_id()]);
+
+        // The uses are written to the store when they're flushed…
+        crypto_store.flush_room_key_uses().await.unwrap();
+
+        let stored_session = alice
+            .store()
+            .get_inbound_group_session(room_id, oldest.session_id())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored_session.use_count(), 2);
+
+        // … and they aren't lost when a copy of the room key which was loaded before
+        // is saved again.
+        alice.store().save_inbound_group_sessions(&[oldest.clone()]).await.unwrap();
+
+        let stored_session = alice
+            .store()
+            .get_inbound_group_session(room_id, oldest.session_id())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored_session.use_count(), 2);
+
+        let sessions = alice
+            .store()
+            .inbound_group_sessions_for_backup_ordered("1", 2, OrderBy::MostUsed)
+            .await
+            .unwrap();
+        assert_eq!(session_ids(sessions), [oldest.session_id(), middle.session_id()]);
+
+        // The room keys which are already backed up aren't returned.
+        alice
+            .store()
+            .mark_inbound_group_sessions_as_backed_up("1", &[(room_id, oldest.session_id())])
+            .await
+            .unwrap();
+
+        let sessions = alice
+            .store()
+            .inbound_group_sessions_for_backup_ordered("1", 3, OrderBy::Oldest)
+            .await
+            .unwrap();
+        assert_eq!(session_ids(sessions), [middle.session_id(), newest.session_id()]);
+    }
+
+    /// Create an inbound Megolm session for the given room, with a new session
+    /// key and the given creation time.
+    async fn create_inbound_group_session_created_at(
+        olm_machine: &OlmMachine,
+        room_id: &RoomId,
+        creation_time: u32,
+    ) -> InboundGroupSession {
+        let session_key = GroupSession::new(Default::default()).session_key();
+        let session =
+            create_inbound_group_session_with_visibility(olm_machine, room_id, &session_key, false);
+
+        let mut pickle = session.pickle().await;
+        pickle.creation_time = SecondsSinceUnixEpoch(creation_time.into());
+
+        InboundGroupSession::from_pickle(pickle).unwrap()
+    }
+
     /// Create an inbound Megolm session for the given room.
     ///
     /// `olm_machine` is used to set the `sender_key` and `signing_key`
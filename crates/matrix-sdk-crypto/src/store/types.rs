@@ -24,7 +24,7 @@ use std::{
 
 use ruma::{OwnedDeviceId, OwnedRoomId, OwnedUserId};
 use serde::{Deserialize, Serialize};
-use vodozemac::{base64_encode, Curve25519PublicKey};
+use vodozemac::{base64_encode, Curve25519PublicKey, Ed25519PublicKey};
 use zeroize::{Zeroize, ZeroizeOnDrop};
 
 use super::{DehydrationError, GossipRequest};
@@ -362,6 +362,19 @@ pub struct RoomKeyCounts {
     pub backed_up: usize,
 }
 
+/// The order in which
+/// [`Store::inbound_group_sessions_for_backup_ordered()`](super::Store::inbound_group_sessions_for_backup_ordered)
+/// returns the room keys which need to be backed up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderBy {
+    /// The room keys which were created first come first.
+    Oldest,
+    /// The room keys which were created last come first.
+    Newest,
+    /// The room keys which decrypted the most events come first.
+    MostUsed,
+}
+
 /// Stored versions of the backup keys.
 #[derive(Default, Clone, Debug)]
 pub struct BackupKeys {
@@ -479,6 +492,28 @@ pub struct RoomKeyWithheldInfo {
     pub withheld_event: RoomKeyWithheldEvent,
 }
 
+/// Information about a device whose Ed25519 key changed in a `/keys/query`
+/// response.
+///
+/// The Ed25519 key of a device is pinned the first time the device is seen,
+/// so updates that change it are rejected and the device keeps its original
+/// keys. Such a change usually means that the homeserver is misbehaving, or
+/// that the device ID has been reused by another device.
+#[derive(Clone, Debug)]
+pub struct DeviceKeyChanged {
+    /// The user ID of the owner of the device.
+    pub user_id: OwnedUserId,
+
+    /// The ID of the device.
+    pub device_id: OwnedDeviceId,
+
+    /// The Ed25519 key that was first seen for the device.
+    pub old_ed25519_key: Option<Ed25519PublicKey>,
+
+    /// The Ed25519 key that the homeserver returned for the device.
+    pub new_ed25519_key: Option<Ed25519PublicKey>,
+}
+
 /// Information about a received historic room key bundle.
 ///
 /// This struct contains information needed to uniquely identify a room key
@@ -376,7 +376,14 @@ impl RoomListService {
     }
 
     async fn list_for(&self, sliding_sync_list_name: &str) -> Result<RoomList, Error> {
-        RoomList::new(&self.client, &self.sliding_sync, sliding_sync_list_name, self.state()).await
+        RoomList::new(
+            &self.client,
+            &self.sliding_sync,
+            sliding_sync_list_name,
+            self.state(),
+            self.state_machine.all_rooms_growing_batch_size(),
+        )
+        .await
     }
 
     /// Get a [`RoomList`] for all rooms.
@@ -12,7 +12,13 @@
 // See the License for that specific language governing permissions and
 // limitations under the License.
 
-use std::{future::ready, sync::Arc};
+use std::{
+    future::ready,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc, Mutex,
+    },
+};
 
 use async_cell::sync::AsyncCell;
 use async_rx::StreamExt as _;
@@ -23,7 +29,8 @@ use eyeball_im_util::vector::VectorObserverExt;
 use futures_util::{pin_mut, stream, Stream, StreamExt as _};
 use matrix_sdk::{
     executor::{spawn, JoinHandle},
-    Client, SlidingSync, SlidingSyncList,
+    sliding_sync::Range,
+    Client, SlidingSync, SlidingSyncList, SlidingSyncMode,
 };
 use matrix_sdk_base::RoomInfoNotableUpdate;
 use tokio::{
@@ -33,7 +40,7 @@ use tokio::{
 use tracing::{error, trace};
 
 use super::{
-    filters::BoxedFilterFn,
+    filters::{BoxedFilterFn, Filter},
     sorters::{new_sorter_lexicographic, new_sorter_name, new_sorter_recency},
     Error, Room, State,
 };
@@ -46,6 +53,8 @@ pub struct RoomList {
     sliding_sync_list: SlidingSyncList,
     loading_state: SharedObservable<RoomListLoadingState>,
     loading_state_task: JoinHandle<()>,
+    viewport: Mutex<Option<Range>>,
+    growing_batch_size: Arc<AtomicU32>,
 }
 
 impl Drop for RoomList {
@@ -60,6 +69,7 @@ impl RoomList {
         sliding_sync: &Arc<SlidingSync>,
         sliding_sync_list_name: &str,
         room_list_service_state: Subscriber<State>,
+        growing_batch_size: Arc<AtomicU32>,
     ) -> Result<Self, Error> {
         let sliding_sync_list = sliding_sync
             .on_list(sliding_sync_list_name, |list| ready(list.clone()))
@@ -108,6 +118,8 @@ impl RoomList {
                     loading_state.set(RoomListLoadingState::Loaded { maximum_number_of_rooms });
                 }
             }),
+            viewport: Mutex::new(None),
+            growing_batch_size,
         })
     }
 
@@ -119,10 +131,143 @@ impl RoomList {
     }
 
     /// Get a stream of rooms.
-    fn entries(&self) -> (Vector<Room>, impl Stream<Item = Vec<VectorDiff<Room>>> + '_) {
+    ///
+    /// The returned rooms are all the rooms known by the [`Client`], not only
+    /// the ones in the range of the underlying sliding sync list, and they are
+    /// neither filtered nor sorted. New rooms are added as they are fetched,
+    /// e.g. when the viewport grows, see [`Self::set_viewport`] and
+    /// [`Self::load_more`].
+    pub fn entries(&self) -> (Vector<Room>, impl Stream<Item = Vec<VectorDiff<Room>>> + '_) {
         self.client.rooms_stream()
     }
 
+    /// Get a stream of rooms filtered by `filter`.
+    ///
+    /// The filter is applied again every time a room is updated, so that the
+    /// returned rooms stay in sync with the filter, e.g. a room that becomes
+    /// unread will enter the list if `filter` is
+    /// [`new_filter_unread`](super::filters::new_filter_unread). Contrary to
+    /// [`Self::entries_with_dynamic_adapters`], the rooms are not sorted and
+    /// the filter can't be changed.
+    pub fn filtered_entries(
+        &self,
+        filter: impl Filter + Send + Sync + 'static,
+    ) -> (Vector<Room>, impl Stream<Item = Vec<VectorDiff<Room>>> + '_) {
+        let filter: BoxedFilterFn = Box::new(filter);
+
+        let (raw_values, raw_stream) = self.entries();
+
+        // Combine normal stream events with other updates from rooms, so that the
+        // filter is re-applied when a room is updated.
+        let merged_stream = merge_stream_and_receiver(
+            raw_values.clone(),
+            raw_stream,
+            self.client.room_info_notable_update_receiver(),
+        );
+
+        (raw_values, merged_stream).filter(filter)
+    }
+
+    /// Get a stream of the [`UnreadCounts`] aggregated over all the rooms.
+    ///
+    /// The current aggregate is yielded first, then a new aggregate is
+    /// yielded every time it changes, i.e. when the unread notifications
+    /// count of a room changes or when a room is added or removed.
+    pub fn total_unread_counts(&self) -> impl Stream<Item = UnreadCounts> + '_ {
+        let (raw_values, raw_stream) = self.entries();
+
+        // Combine normal stream events with other updates from rooms, so that the
+        // aggregate is computed again when a room is updated.
+        let merged_stream = merge_stream_and_receiver(
+            raw_values.clone(),
+            raw_stream,
+            self.client.room_info_notable_update_receiver(),
+        );
+
+        stream! {
+            pin_mut!(merged_stream);
+
+            let mut rooms = raw_values;
+            let mut unread_counts = UnreadCounts::from_rooms(&rooms);
+
+            yield unread_counts;
+
+            while let Some(diffs) = merged_stream.next().await {
+                for diff in diffs {
+                    diff.apply(&mut rooms);
+                }
+
+                let new_unread_counts = UnreadCounts::from_rooms(&rooms);
+
+                if new_unread_counts != unread_counts {
+                    unread_counts = new_unread_counts;
+
+                    yield unread_counts;
+                }
+            }
+        }
+    }
+
+    /// Set the viewport, i.e. the range of rooms the underlying sliding sync
+    /// list must sync.
+    ///
+    /// The sliding sync list is moved to a [`SlidingSyncMode::Selective`]
+    /// sync-mode with `range` as its only range. Be aware that the
+    /// [`RoomListService`](super::RoomListService) state machine may change the
+    /// sync-mode of the list again when it transitions to another [`State`].
+    pub fn set_viewport(&self, range: Range) {
+        self.sliding_sync_list
+            .set_sync_mode(SlidingSyncMode::new_selective().add_range(range.clone()));
+
+        *self.viewport.lock().unwrap() = Some(range);
+    }
+
+    /// Load more rooms.
+    ///
+    /// If a viewport has been set with [`Self::set_viewport`], its end is
+    /// extended by [`VIEWPORT_PAGE_SIZE`] rooms, without going past the maximum
+    /// number of rooms, if known.
+    ///
+    /// Otherwise, the batch size of the [`SlidingSyncMode::Growing`] sync-mode
+    /// of the underlying sliding sync list is increased by
+    /// [`VIEWPORT_PAGE_SIZE`] rooms, so that more rooms are fetched by each
+    /// request. The range of the list keeps growing from the rooms which have
+    /// already been fetched, see [`SlidingSyncList::set_growing_batch_size`].
+    /// The [`RoomListService`](super::RoomListService) state machine keeps the
+    /// increased batch size when it switches the list back to the growing
+    /// sync-mode, e.g. after [`State::Recovering`].
+    pub fn load_more(&self) {
+        let range = {
+            let viewport = self.viewport.lock().unwrap();
+
+            let Some(range) = viewport.as_ref() else {
+                let batch_size = self
+                    .growing_batch_size
+                    .fetch_add(VIEWPORT_PAGE_SIZE, Ordering::SeqCst)
+                    .saturating_add(VIEWPORT_PAGE_SIZE);
+
+                // If the list isn't in the growing sync-mode yet, the state machine will use
+                // the increased batch size when it switches to it.
+                self.sliding_sync_list.set_growing_batch_size(batch_size);
+
+                return;
+            };
+
+            let (start, end) = (*range.start(), range.end().saturating_add(VIEWPORT_PAGE_SIZE));
+
+            let end = match self.sliding_sync_list.maximum_number_of_rooms() {
+                Some(maximum_number_of_rooms) => {
+                    end.min(maximum_number_of_rooms.saturating_sub(1)).max(start)
+                }
+                None => end,
+            };
+
+            start..=end
+        };
+
+        self.set_viewport(range);
+    }
+
     /// Get a configurable stream of rooms.
     ///
     /// It's possible to provide a filter that will filter out room list
@@ -240,6 +385,43 @@ fn merge_stream_and_receiver(
     }
 }
 
+/// The unread counts aggregated over all the rooms of a [`RoomList`].
+///
+/// See [`RoomList::total_unread_counts`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct UnreadCounts {
+    /// The total number of unread notifications.
+    pub notifications: u64,
+
+    /// The total number of unread notifications with the highlight flag set.
+    pub highlights: u64,
+
+    /// The number of rooms with at least one unread notification.
+    pub rooms_with_unread: u64,
+}
+
+impl UnreadCounts {
+    fn from_rooms(rooms: &Vector<Room>) -> Self {
+        rooms.iter().map(|room| room.unread_notification_counts()).fold(
+            Self::default(),
+            |mut unread_counts, room_counts| {
+                unread_counts.notifications += room_counts.notification_count;
+                unread_counts.highlights += room_counts.highlight_count;
+
+                if room_counts.notification_count > 0 {
+                    unread_counts.rooms_with_unread += 1;
+                }
+
+                unread_counts
+            },
+        )
+    }
+}
+
+/// The number of rooms [`RoomList::load_more`] adds to the viewport, or to the
+/// batch size of the growing sync-mode.
+pub const VIEWPORT_PAGE_SIZE: u32 = 20;
+
 /// The loading state of a [`RoomList`].
 ///
 /// When a [`RoomList`] is displayed to the user, it can be in various states.
@@ -14,7 +14,13 @@
 
 //! States and actions for the `RoomList` state machine.
 
-use std::{future::ready, sync::Mutex};
+use std::{
+    future::ready,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc, Mutex,
+    },
+};
 
 use eyeball::{SharedObservable, Subscriber};
 use matrix_sdk::{sliding_sync::Range, SlidingSync, SlidingSyncMode};
@@ -78,6 +84,15 @@ pub struct StateMachine {
     ///
     /// To be used in coordination with `Self::last_state_update_time`.
     state_lifespan: Duration,
+
+    /// The `batch_size` of the growing sync-mode of the `ALL_ROOMS_LIST_NAME`
+    /// list.
+    ///
+    /// It's shared with the [`super::RoomList`]s, so that
+    /// [`super::RoomList::load_more`] can increase it, and the state machine
+    /// keeps using the increased value when it switches back to the growing
+    /// sync-mode.
+    all_rooms_growing_batch_size: Arc<AtomicU32>,
 }
 
 impl StateMachine {
@@ -86,9 +101,18 @@ impl StateMachine {
             state: SharedObservable::new(State::Init),
             last_state_update_time: Mutex::new(Instant::now()),
             state_lifespan: DEFAULT_STATE_LIFESPAN,
+            all_rooms_growing_batch_size: Arc::new(AtomicU32::new(
+                ALL_ROOMS_DEFAULT_GROWING_BATCH_SIZE,
+            )),
         }
     }
 
+    /// Get the shared `batch_size` of the growing sync-mode of the
+    /// `ALL_ROOMS_LIST_NAME` list.
+    pub(super) fn all_rooms_growing_batch_size(&self) -> Arc<AtomicU32> {
+        self.all_rooms_growing_batch_size.clone()
+    }
+
     /// Get the current state.
     pub(super) fn get(&self) -> State {
         self.state.get()
@@ -118,7 +142,11 @@ impl StateMachine {
             Init => SettingUp,
 
             SettingUp | Recovering => {
-                set_all_rooms_to_growing_sync_mode(sliding_sync).await?;
+                set_all_rooms_to_growing_sync_mode(
+                    sliding_sync,
+                    self.all_rooms_growing_batch_size.load(Ordering::SeqCst),
+                )
+                .await?;
                 Running
             }
 
@@ -160,10 +188,13 @@ impl StateMachine {
     }
 }
 
-async fn set_all_rooms_to_growing_sync_mode(sliding_sync: &SlidingSync) -> Result<(), Error> {
+async fn set_all_rooms_to_growing_sync_mode(
+    sliding_sync: &SlidingSync,
+    batch_size: u32,
+) -> Result<(), Error> {
     sliding_sync
         .on_list(ALL_ROOMS_LIST_NAME, |list| {
-            list.set_sync_mode(SlidingSyncMode::new_growing(ALL_ROOMS_DEFAULT_GROWING_BATCH_SIZE));
+            list.set_sync_mode(SlidingSyncMode::new_growing(batch_size));
 
             ready(())
         })
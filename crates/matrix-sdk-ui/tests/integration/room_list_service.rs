@@ -18,8 +18,12 @@ use matrix_sdk_test::{
 };
 use matrix_sdk_ui::{
     room_list_service::{
-        filters::{new_filter_fuzzy_match_room_name, new_filter_non_left, new_filter_none},
-        Error, RoomListLoadingState, State, SyncIndicator, ALL_ROOMS_LIST_NAME as ALL_ROOMS,
+        filters::{
+            new_filter_fuzzy_match_room_name, new_filter_non_left, new_filter_none,
+            new_filter_unread,
+        },
+        Error, RoomListLoadingState, State, SyncIndicator, UnreadCounts,
+        ALL_ROOMS_LIST_NAME as ALL_ROOMS,
     },
     timeline::{RoomExt as _, TimelineItemKind, VirtualTimelineItem},
     RoomListService,
@@ -1330,6 +1334,510 @@ async fn test_loading_states() -> Result<(), Error> {
     Ok(())
 }
 
+#[async_test]
+async fn test_viewport() -> Result<(), Error> {
+    let (_client, server, room_list) = new_room_list_service().await?;
+
+    let sync = room_list.sync();
+    pin_mut!(sync);
+
+    let all_rooms = room_list.all_rooms().await?;
+
+    sync_then_assert_request_and_fake_response! {
+        [server, room_list, sync]
+        states = Init => SettingUp,
+        assert request >= {
+            "lists": {
+                ALL_ROOMS: {
+                    "ranges": [[0, 19]],
+                },
+            },
+        },
+        respond with = {
+            "pos": "0",
+            "lists": {
+                ALL_ROOMS: {
+                    "count": 30,
+                },
+            },
+            "rooms": {},
+        },
+    };
+
+    sync_then_assert_request_and_fake_response! {
+        [server, room_list, sync]
+        states = SettingUp => Running,
+        assert request >= {
+            "lists": {
+                ALL_ROOMS: {
+                    "ranges": [[0, 29]],
+                },
+            },
+        },
+        respond with = {
+            "pos": "1",
+            "lists": {
+                ALL_ROOMS: {
+                    "count": 30,
+                },
+            },
+            "rooms": {},
+        },
+    };
+
+    let (entries, _) = all_rooms.entries();
+    assert!(entries.is_empty());
+
+    // Restrict the viewport to the first 2 rooms.
+    all_rooms.set_viewport(0..=1);
+
+    sync_then_assert_request_and_fake_response! {
+        [server, room_list, sync]
+        states = Running => Running,
+        assert request >= {
+            "lists": {
+                ALL_ROOMS: {
+                    "ranges": [[0, 1]],
+                },
+            },
+        },
+        respond with = {
+            "pos": "2",
+            "lists": {
+                ALL_ROOMS: {
+                    "count": 30,
+                },
+            },
+            "rooms": {
+                "!r0:bar.org": {
+                    "initial": true,
+                    "timeline": [],
+                },
+                "!r1:bar.org": {
+                    "initial": true,
+                    "timeline": [],
+                },
+            },
+        },
+    };
+
+    let (entries, _) = all_rooms.entries();
+    assert_eq!(entries.len(), 2);
+
+    // Load more rooms: the viewport grows by one page.
+    all_rooms.load_more();
+
+    sync_then_assert_request_and_fake_response! {
+        [server, room_list, sync]
+        states = Running => Running,
+        assert request >= {
+            "lists": {
+                ALL_ROOMS: {
+                    "ranges": [[0, 21]],
+                },
+            },
+        },
+        respond with = {
+            "pos": "3",
+            "lists": {
+                ALL_ROOMS: {
+                    "count": 30,
+                },
+            },
+            "rooms": {
+                "!r2:bar.org": {
+                    "initial": true,
+                    "timeline": [],
+                },
+                "!r3:bar.org": {
+                    "initial": true,
+                    "timeline": [],
+                },
+            },
+        },
+    };
+
+    let (entries, _) = all_rooms.entries();
+    assert_eq!(entries.len(), 4);
+
+    // Loading more rooms never goes beyond the maximum number of rooms.
+    all_rooms.load_more();
+
+    sync_then_assert_request_and_fake_response! {
+        [server, room_list, sync]
+        states = Running => Running,
+        assert request >= {
+            "lists": {
+                ALL_ROOMS: {
+                    "ranges": [[0, 29]],
+                },
+            },
+        },
+        respond with = {
+            "pos": "4",
+            "lists": {
+                ALL_ROOMS: {
+                    "count": 30,
+                },
+            },
+            "rooms": {},
+        },
+    };
+
+    Ok(())
+}
+
+#[async_test]
+async fn test_load_more_without_viewport() -> Result<(), Error> {
+    let (_client, server, room_list) = new_room_list_service().await?;
+
+    let sync = room_list.sync();
+    pin_mut!(sync);
+
+    let all_rooms = room_list.all_rooms().await?;
+
+    sync_then_assert_request_and_fake_response! {
+        [server, room_list, sync]
+        states = Init => SettingUp,
+        assert request >= {
+            "lists": {
+                ALL_ROOMS: {
+                    "ranges": [[0, 19]],
+                },
+            },
+        },
+        respond with = {
+            "pos": "0",
+            "lists": {
+                ALL_ROOMS: {
+                    "count": 300,
+                },
+            },
+            "rooms": {},
+        },
+    };
+
+    // Load more rooms without having set a viewport: the batch size of the growing
+    // sync-mode is increased by one page.
+    all_rooms.load_more();
+
+    sync_then_assert_request_and_fake_response! {
+        [server, room_list, sync]
+        states = SettingUp => Running,
+        assert request >= {
+            "lists": {
+                ALL_ROOMS: {
+                    "ranges": [[0, 119]],
+                },
+            },
+        },
+        respond with = {
+            "pos": "1",
+            "lists": {
+                ALL_ROOMS: {
+                    "count": 300,
+                },
+            },
+            "rooms": {},
+        },
+    };
+
+    sync_then_assert_request_and_fake_response! {
+        [server, room_list, sync]
+        sync matches Some(Err(_)),
+        states = Running => Error { .. },
+        assert request >= {
+            "lists": {
+                ALL_ROOMS: {
+                    // The list is still growing, with the increased batch size.
+                    "ranges": [[0, 239]],
+                },
+            },
+        },
+        respond with = (code 400) {
+            "error": "foo",
+            "errcode": "M_UNKNOWN",
+        },
+    };
+
+    assert!(sync.next().await.is_none());
+
+    // Start a new sync, which goes through the `Recovering` state.
+    let sync = room_list.sync();
+    pin_mut!(sync);
+
+    sync_then_assert_request_and_fake_response! {
+        [server, room_list, sync]
+        states = Error { .. } => Recovering,
+        assert request >= {
+            "lists": {
+                ALL_ROOMS: {
+                    "ranges": [[0, 19]],
+                },
+            },
+        },
+        respond with = {
+            "pos": "2",
+            "lists": {
+                ALL_ROOMS: {
+                    "count": 300,
+                },
+            },
+            "rooms": {},
+        },
+    };
+
+    sync_then_assert_request_and_fake_response! {
+        [server, room_list, sync]
+        states = Recovering => Running,
+        assert request >= {
+            "lists": {
+                ALL_ROOMS: {
+                    // The increased batch size has been kept.
+                    "ranges": [[0, 119]],
+                },
+            },
+        },
+        respond with = {
+            "pos": "3",
+            "lists": {
+                ALL_ROOMS: {
+                    "count": 300,
+                },
+            },
+            "rooms": {},
+        },
+    };
+
+    Ok(())
+}
+
+#[async_test]
+async fn test_load_more_without_viewport_keeps_growing() -> Result<(), Error> {
+    let (_client, server, room_list) = new_room_list_service().await?;
+
+    let sync = room_list.sync();
+    pin_mut!(sync);
+
+    let all_rooms = room_list.all_rooms().await?;
+
+    sync_then_assert_request_and_fake_response! {
+        [server, room_list, sync]
+        states = Init => SettingUp,
+        assert request >= {
+            "lists": {
+                ALL_ROOMS: {
+                    "ranges": [[0, 19]],
+                },
+            },
+        },
+        respond with = {
+            "pos": "0",
+            "lists": {
+                ALL_ROOMS: {
+                    "count": 500,
+                },
+            },
+            "rooms": {},
+        },
+    };
+
+    sync_then_assert_request_and_fake_response! {
+        [server, room_list, sync]
+        states = SettingUp => Running,
+        assert request >= {
+            "lists": {
+                ALL_ROOMS: {
+                    "ranges": [[0, 99]],
+                },
+            },
+        },
+        respond with = {
+            "pos": "1",
+            "lists": {
+                ALL_ROOMS: {
+                    "count": 500,
+                },
+            },
+            "rooms": {},
+        },
+    };
+
+    // Load more rooms while the list is growing: the range isn't reset, it grows
+    // from the 100 fetched rooms with the increased batch size.
+    all_rooms.load_more();
+
+    sync_then_assert_request_and_fake_response! {
+        [server, room_list, sync]
+        states = Running => Running,
+        assert request >= {
+            "lists": {
+                ALL_ROOMS: {
+                    "ranges": [[0, 219]],
+                },
+            },
+        },
+        respond with = {
+            "pos": "2",
+            "lists": {
+                ALL_ROOMS: {
+                    "count": 500,
+                },
+            },
+            "rooms": {},
+        },
+    };
+
+    Ok(())
+}
+
+#[async_test]
+async fn test_filtered_entries() -> Result<(), Error> {
+    let (_client, server, room_list) = new_room_list_service().await?;
+
+    let sync = room_list.sync();
+    pin_mut!(sync);
+
+    let all_rooms = room_list.all_rooms().await?;
+
+    let (mut unread_rooms, unread_rooms_stream) = all_rooms.filtered_entries(new_filter_unread());
+    pin_mut!(unread_rooms_stream);
+
+    assert!(unread_rooms.is_empty());
+
+    sync_then_assert_request_and_fake_response! {
+        [server, room_list, sync]
+        states = Init => SettingUp,
+        assert request >= {
+            "lists": {
+                ALL_ROOMS: {
+                    "ranges": [[0, 19]],
+                },
+            },
+        },
+        respond with = {
+            "pos": "0",
+            "lists": {
+                ALL_ROOMS: {
+                    "count": 2,
+                },
+            },
+            "rooms": {
+                "!r0:bar.org": {
+                    "initial": true,
+                    "timeline": [],
+                },
+                "!r1:bar.org": {
+                    "initial": true,
+                    "timeline": [],
+                },
+            },
+        },
+    };
+
+    // The rooms are not unread, they are filtered out.
+    while let Some(Some(diffs)) = unread_rooms_stream.next().now_or_never() {
+        for diff in diffs {
+            diff.apply(&mut unread_rooms);
+        }
+    }
+
+    assert!(unread_rooms.is_empty());
+
+    // Mark one room as unread.
+    sync_then_assert_request_and_fake_response! {
+        [server, room_list, sync]
+        states = SettingUp => Running,
+        assert request >= {
+            "lists": {
+                ALL_ROOMS: {
+                    "ranges": [[0, 1]],
+                },
+            },
+        },
+        respond with = {
+            "pos": "1",
+            "lists": {
+                ALL_ROOMS: {
+                    "count": 2,
+                },
+            },
+            "rooms": {},
+            "extensions": {
+                "account_data": {
+                    "rooms": {
+                        "!r1:bar.org": [
+                            {
+                                "type": "m.marked_unread",
+                                "content": {
+                                    "unread": true,
+                                },
+                            },
+                        ],
+                    },
+                },
+            },
+        },
+    };
+
+    // The filter has been applied again: the unread room enters the list.
+    while let Some(Some(diffs)) = unread_rooms_stream.next().now_or_never() {
+        for diff in diffs {
+            diff.apply(&mut unread_rooms);
+        }
+    }
+
+    assert_eq!(unread_rooms.len(), 1);
+    assert_eq!(unread_rooms[0].room_id(), room_id!("!r1:bar.org"));
+
+    // Mark the room as read again.
+    sync_then_assert_request_and_fake_response! {
+        [server, room_list, sync]
+        states = Running => Running,
+        assert request >= {
+            "lists": {
+                ALL_ROOMS: {
+                    "ranges": [[0, 1]],
+                },
+            },
+        },
+        respond with = {
+            "pos": "2",
+            "lists": {
+                ALL_ROOMS: {
+                    "count": 2,
+                },
+            },
+            "rooms": {},
+            "extensions": {
+                "account_data": {
+                    "rooms": {
+                        "!r1:bar.org": [
+                            {
+                                "type": "m.marked_unread",
+                                "content": {
+                                    "unread": false,
+                                },
+                            },
+                        ],
+                    },
+                },
+            },
+        },
+    };
+
+    // The room leaves the list.
+    while let Some(Some(diffs)) = unread_rooms_stream.next().now_or_never() {
+        for diff in diffs {
+            diff.apply(&mut unread_rooms);
+        }
+    }
+
+    assert!(unread_rooms.is_empty());
+
+    Ok(())
+}
+
 #[async_test]
 async fn test_dynamic_entries_stream() -> Result<(), Error> {
     let (_client, server, room_list) = new_room_list_service().await?;
@@ -2436,6 +2944,113 @@ async fn test_room_unread_notifications() -> Result<(), Error> {
     Ok(())
 }
 
+#[async_test]
+async fn test_total_unread_counts() -> Result<(), Error> {
+    let (_, server, room_list) = new_room_list_service().await?;
+
+    let sync = room_list.sync();
+    pin_mut!(sync);
+
+    let all_rooms = room_list.all_rooms().await?;
+
+    let total_unread_counts = all_rooms.total_unread_counts();
+    pin_mut!(total_unread_counts);
+
+    // No room yet.
+    assert_next_matches!(
+        total_unread_counts,
+        UnreadCounts { notifications: 0, highlights: 0, rooms_with_unread: 0 }
+    );
+
+    sync_then_assert_request_and_fake_response! {
+        [server, room_list, sync]
+        assert request >= {
+            "lists": {
+                ALL_ROOMS: {
+                    "ranges": [[0, 19]],
+                },
+            },
+        },
+        respond with = {
+            "pos": "0",
+            "lists": {
+                ALL_ROOMS: {
+                    "count": 3,
+                },
+            },
+            "rooms": {
+                "!r0:bar.org": {
+                    "initial": true,
+                    "timeline": [],
+                    "notification_count": 2,
+                    "highlight_count": 1,
+                },
+                "!r1:bar.org": {
+                    "initial": true,
+                    "timeline": [],
+                    "notification_count": 5,
+                    "highlight_count": 0,
+                },
+                "!r2:bar.org": {
+                    "initial": true,
+                    "timeline": [],
+                },
+            },
+        },
+    };
+
+    let mut last_unread_counts = None;
+
+    while let Some(Some(unread_counts)) = total_unread_counts.next().now_or_never() {
+        last_unread_counts = Some(unread_counts);
+    }
+
+    assert_eq!(
+        last_unread_counts,
+        Some(UnreadCounts { notifications: 7, highlights: 1, rooms_with_unread: 2 })
+    );
+
+    sync_then_assert_request_and_fake_response! {
+        [server, room_list, sync]
+        assert request >= {
+            "lists": {
+                ALL_ROOMS: {
+                    "ranges": [[0, 2]],
+                },
+            },
+        },
+        respond with = {
+            "pos": "1",
+            "lists": {},
+            "rooms": {
+                "!r0:bar.org": {
+                    "timeline": [],
+                    "notification_count": 0,
+                    "highlight_count": 0,
+                },
+                "!r2:bar.org": {
+                    "timeline": [],
+                    "notification_count": 3,
+                    "highlight_count": 3,
+                },
+            },
+        },
+    };
+
+    let mut last_unread_counts = None;
+
+    while let Some(Some(unread_counts)) = total_unread_counts.next().now_or_never() {
+        last_unread_counts = Some(unread_counts);
+    }
+
+    assert_eq!(
+        last_unread_counts,
+        Some(UnreadCounts { notifications: 8, highlights: 3, rooms_with_unread: 2 })
+    );
+
+    Ok(())
+}
+
 #[async_test]
 async fn test_room_timeline() -> Result<(), Error> {
     let (_, server, room_list) = new_room_list_service().await?;
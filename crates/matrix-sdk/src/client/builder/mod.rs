@@ -17,6 +17,8 @@ mod homeserver_config;
 
 #[cfg(feature = "sqlite")]
 use std::path::Path;
+#[cfg(feature = "e2e-encryption")]
+use std::time::Duration;
 use std::{fmt, sync::Arc};
 
 use homeserver_config::*;
@@ -48,7 +50,7 @@ use crate::{
     },
     config::RequestConfig,
     error::RumaApiError,
-    http_client::HttpClient,
+    http_client::{HttpClient, RequestMiddleware},
     send_queue::SendQueueData,
     sliding_sync::VersionBuilder as SlidingSyncVersionBuilder,
     HttpError, IdParseError,
@@ -100,15 +102,21 @@ pub struct ClientBuilder {
     http_cfg: Option<HttpConfig>,
     store_config: BuilderStoreConfig,
     request_config: RequestConfig,
+    request_middlewares: Vec<Arc<dyn RequestMiddleware>>,
     respect_login_well_known: bool,
     server_versions: Option<Box<[MatrixVersion]>>,
     handle_refresh_tokens: bool,
+    collect_deserialization_errors: bool,
     base_client: Option<BaseClient>,
     #[cfg(feature = "e2e-encryption")]
     encryption_settings: EncryptionSettings,
     #[cfg(feature = "e2e-encryption")]
     room_key_recipient_strategy: CollectStrategy,
     #[cfg(feature = "e2e-encryption")]
+    room_key_rotation_period: Option<Duration>,
+    #[cfg(feature = "e2e-encryption")]
+    room_key_rotation_period_messages: Option<u64>,
+    #[cfg(feature = "e2e-encryption")]
     decryption_settings: DecryptionSettings,
     #[cfg(feature = "e2e-encryption")]
     enable_share_history_on_invite: bool,
@@ -127,15 +135,21 @@ impl ClientBuilder {
                 Self::DEFAULT_CROSS_PROCESS_STORE_LOCKS_HOLDER_NAME.to_owned(),
             )),
             request_config: Default::default(),
+            request_middlewares: Vec::new(),
             respect_login_well_known: true,
             server_versions: None,
             handle_refresh_tokens: false,
+            collect_deserialization_errors: false,
             base_client: None,
             #[cfg(feature = "e2e-encryption")]
             encryption_settings: Default::default(),
             #[cfg(feature = "e2e-encryption")]
             room_key_recipient_strategy: Default::default(),
             #[cfg(feature = "e2e-encryption")]
+            room_key_rotation_period: None,
+            #[cfg(feature = "e2e-encryption")]
+            room_key_rotation_period_messages: None,
+            #[cfg(feature = "e2e-encryption")]
             decryption_settings: DecryptionSettings {
                 sender_device_trust_requirement: TrustRequirement::Untrusted,
             },
@@ -339,6 +353,9 @@ impl ClientBuilder {
     }
 
     /// Set a custom HTTP user agent for the client.
+    ///
+    /// It is sent with every request, and defaults to `matrix-rust-sdk/`
+    /// followed by the version of the SDK.
     #[cfg(not(target_family = "wasm"))]
     pub fn user_agent(mut self, user_agent: impl AsRef<str>) -> Self {
         self.http_settings().user_agent = Some(user_agent.as_ref().to_owned());
@@ -382,6 +399,15 @@ impl ClientBuilder {
         self
     }
 
+    /// Add a middleware that will be called for every HTTP request sent by the
+    /// client, and every response it receives.
+    ///
+    /// Middlewares are called in the order in which they were added.
+    pub fn add_request_middleware(mut self, middleware: impl RequestMiddleware + 'static) -> Self {
+        self.request_middlewares.push(Arc::new(middleware));
+        self
+    }
+
     /// Specify the Matrix versions supported by the homeserver manually, rather
     /// than `build()` doing it using a `get_supported_versions` request.
     ///
@@ -411,8 +437,9 @@ impl ClientBuilder {
     ///   is encountered, it means that the user needs to be logged in again.
     ///
     /// * The access token and refresh token need to be watched for changes,
-    ///   using the authentication API's `session_tokens_stream()` for example,
-    ///   to be able to [restore the session] later.
+    ///   using the save session callback of [`Client::set_session_callbacks()`]
+    ///   or [`Client::subscribe_to_session_changes()`] for example, to be able
+    ///   to [restore the session] later.
     ///
     /// [refreshing access tokens]: https://spec.matrix.org/v1.3/client-server-api/#refreshing-access-tokens
     /// [`UnknownToken`]: ruma::api::client::error::ErrorKind::UnknownToken
@@ -422,6 +449,17 @@ impl ClientBuilder {
         self
     }
 
+    /// Keep the state events that couldn't be deserialized while processing a
+    /// sync response, instead of only logging them.
+    ///
+    /// This is useful to diagnose schema mismatches between the homeserver and
+    /// the SDK. The events that were ignored in the last sync response can be
+    /// retrieved with [`Client::last_deserialization_errors()`].
+    pub fn collect_deserialization_errors(mut self) -> Self {
+        self.collect_deserialization_errors = true;
+        self
+    }
+
     /// Public for test only
     #[doc(hidden)]
     pub fn base_client(mut self, base_client: BaseClient) -> Self {
@@ -445,6 +483,31 @@ impl ClientBuilder {
         self
     }
 
+    /// Set the maximum duration a room key is used for, when sending encrypted
+    /// messages.
+    ///
+    /// It only applies if it's shorter than the rotation period set in the
+    /// `m.room.encryption` event of the room. Unless the
+    /// `_disable-minimum-rotation-period-ms` feature of `matrix-sdk-crypto` is
+    /// enabled, room keys are used for at least one hour, whatever this value
+    /// is.
+    #[cfg(feature = "e2e-encryption")]
+    pub fn with_room_key_rotation_period(mut self, rotation_period: Duration) -> Self {
+        self.room_key_rotation_period = Some(rotation_period);
+        self
+    }
+
+    /// Set the maximum number of messages a room key is used for, when sending
+    /// encrypted messages.
+    ///
+    /// It only applies if it's lower than the rotation period set in the
+    /// `m.room.encryption` event of the room.
+    #[cfg(feature = "e2e-encryption")]
+    pub fn with_room_key_rotation_period_messages(mut self, rotation_period_msgs: u64) -> Self {
+        self.room_key_rotation_period_messages = Some(rotation_period_msgs);
+        self
+    }
+
     /// Set the trust requirement to be used when decrypting events.
     #[cfg(feature = "e2e-encryption")]
     pub fn with_decryption_settings(mut self, decryption_settings: DecryptionSettings) -> Self {
@@ -508,7 +571,7 @@ impl ClientBuilder {
             HttpConfig::Custom(c) => c,
         };
 
-        let base_client = if let Some(base_client) = self.base_client {
+        let mut base_client = if let Some(base_client) = self.base_client {
             base_client
         } else {
             #[allow(unused_mut)]
@@ -520,13 +583,18 @@ impl ClientBuilder {
             #[cfg(feature = "e2e-encryption")]
             {
                 client.room_key_recipient_strategy = self.room_key_recipient_strategy;
+                client.room_key_rotation_period = self.room_key_rotation_period;
+                client.room_key_rotation_period_messages = self.room_key_rotation_period_messages;
                 client.decryption_settings = self.decryption_settings;
             }
 
             client
         };
 
-        let http_client = HttpClient::new(inner_http_client.clone(), self.request_config);
+        base_client.collect_deserialization_errors |= self.collect_deserialization_errors;
+
+        let http_client = HttpClient::new(inner_http_client.clone(), self.request_config)
+            .with_middlewares(self.request_middlewares);
 
         #[allow(unused_variables)]
         let HomeserverDiscoveryResult { server, homeserver, supported_versions, well_known } =
@@ -17,6 +17,10 @@ use ruma::api::client::discovery::get_authorization_server_metadata::msc2965::Au
 use tokio::sync::RwLock;
 
 use super::ClientServerInfo;
+use crate::media::UrlPreview;
+
+/// The maximum number of URL previews kept in the cache.
+pub(super) const MAX_CACHED_URL_PREVIEWS: usize = 100;
 
 /// A collection of in-memory data that the `Client` might want to cache to
 /// avoid hitting the homeserver every time users request the data.
@@ -25,4 +29,7 @@ pub(crate) struct ClientCaches {
     /// server.
     pub(super) server_info: RwLock<ClientServerInfo>,
     pub(crate) server_metadata: tokio::sync::Mutex<TtlCache<String, AuthorizationServerMetadata>>,
+    /// URL previews, keyed by URL, bounded to [`MAX_CACHED_URL_PREVIEWS`]
+    /// items.
+    pub(crate) url_previews: tokio::sync::Mutex<TtlCache<String, UrlPreview>>,
 }
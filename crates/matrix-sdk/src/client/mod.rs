@@ -22,7 +22,7 @@ use std::{
     sync::{Arc, Mutex as StdMutex, RwLock as StdRwLock, Weak},
 };
 
-use caches::ClientCaches;
+use caches::{ClientCaches, MAX_CACHED_URL_PREVIEWS};
 use eyeball::{SharedObservable, Subscriber};
 use eyeball_im::{Vector, VectorDiff};
 use futures_core::Stream;
@@ -30,6 +30,7 @@ use futures_util::StreamExt;
 #[cfg(feature = "e2e-encryption")]
 use matrix_sdk_base::crypto::{store::LockableCryptoStore, DecryptionSettings};
 use matrix_sdk_base::{
+    deserialized_responses::EventDeserializationError,
     event_cache::store::EventCacheStoreLock,
     store::{DynStateStore, RoomLoadSettings, ServerInfo, WellKnownResponse},
     sync::{Notification, RoomUpdates},
@@ -58,6 +59,7 @@ use ruma::{
             membership::{join_room_by_id, join_room_by_id_or_alias},
             room::create_room,
             session::login::v3::DiscoveryInfo,
+            space::get_hierarchy,
             sync::sync_events,
             uiaa,
             user_directory::search_users,
@@ -66,7 +68,9 @@ use ruma::{
         MatrixVersion, OutgoingRequest,
     },
     assign,
+    events::AnySyncTimelineEvent,
     push::Ruleset,
+    serde::Raw,
     time::Instant,
     DeviceId, OwnedDeviceId, OwnedEventId, OwnedRoomId, OwnedRoomOrAliasId, OwnedServerName,
     RoomAliasId, RoomId, RoomOrAliasId, ServerName, UInt, UserId,
@@ -84,7 +88,7 @@ use crate::{
     },
     config::RequestConfig,
     deduplicating_handler::DeduplicatingHandler,
-    error::HttpResult,
+    error::{HttpResult, NotificationSettingsError},
     event_cache::EventCache,
     event_handler::{
         EventHandler, EventHandlerContext, EventHandlerDropGuard, EventHandlerHandle,
@@ -93,7 +97,7 @@ use crate::{
     http_client::HttpClient,
     latest_events::LatestEvents,
     media::MediaError,
-    notification_settings::NotificationSettings,
+    notification_settings::{NotificationAction, NotificationSettings, RoomNotificationMode},
     room::RoomMember,
     room_preview::RoomPreview,
     send_queue::{SendQueue, SendQueueData},
@@ -125,6 +129,11 @@ type NotificationHandlerFn =
 #[cfg(target_family = "wasm")]
 type NotificationHandlerFn = Box<dyn Fn(Notification, Room, Client) -> NotificationHandlerFut>;
 
+#[cfg(not(target_family = "wasm"))]
+type RawSyncCallback = Arc<dyn Fn(&sync_events::v3::Response) + Send + Sync>;
+#[cfg(target_family = "wasm")]
+type RawSyncCallback = Arc<dyn Fn(&sync_events::v3::Response)>;
+
 /// Enum controlling if a loop running callbacks should continue or abort.
 ///
 /// This is mainly used in the [`sync_with_callback`] method, the return value
@@ -151,6 +160,27 @@ pub enum SessionChange {
     TokensRefreshed,
 }
 
+/// The result of a [`Client::health_check()`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct HealthStatus {
+    /// Whether the homeserver answered the `/versions` request, and the
+    /// `/whoami` request if the client is logged in.
+    ///
+    /// A `/whoami` request failing because the access token was rejected
+    /// doesn't make the homeserver unreachable, any other failure does.
+    pub reachable: bool,
+
+    /// Whether the homeserver accepted the access token of the client.
+    ///
+    /// Always `false` if the homeserver isn't reachable, or if the client
+    /// isn't logged in.
+    pub authenticated: bool,
+
+    /// The Matrix versions supported by the homeserver, empty if the
+    /// `/versions` request failed.
+    pub server_versions: Vec<MatrixVersion>,
+}
+
 /// An async/await enabled Matrix client.
 ///
 /// All of the state is held in an `Arc` so the `Client` can be cloned freely.
@@ -299,6 +329,10 @@ pub(crate) struct ClientInner {
     /// Notification handlers. See `register_notification_handler`.
     notification_handlers: RwLock<Vec<NotificationHandlerFn>>,
 
+    /// Callback called with the raw `/sync` responses. See
+    /// `set_raw_sync_callback`.
+    raw_sync_callback: StdRwLock<Option<RawSyncCallback>>,
+
     /// The sender-side of channels used to receive room updates.
     pub(crate) room_update_channels: StdMutex<BTreeMap<OwnedRoomId, broadcast::Sender<RoomUpdate>>>,
 
@@ -378,6 +412,7 @@ impl ClientInner {
         let caches = ClientCaches {
             server_info: server_info.into(),
             server_metadata: Mutex::new(TtlCache::new()),
+            url_previews: Mutex::new(TtlCache::with_max_items(MAX_CACHED_URL_PREVIEWS)),
         };
 
         let client = Self {
@@ -393,6 +428,7 @@ impl ClientInner {
             typing_notice_times: Default::default(),
             event_handlers: Default::default(),
             notification_handlers: Default::default(),
+            raw_sync_callback: Default::default(),
             room_update_channels: Default::default(),
             // A single `RoomUpdates` is sent once per sync, so we assume that 32 is sufficient
             // ballast for all observers to catch up.
@@ -1108,6 +1144,41 @@ impl Client {
         self
     }
 
+    /// Set a callback that is called with every `/sync` response, before the
+    /// client processes it.
+    ///
+    /// This allows to inspect or record the raw response before the state of
+    /// the client is updated. Setting a new callback replaces the previous one.
+    ///
+    /// The callback runs inline in the sync loop, which waits for it to return
+    /// before processing the response: it must not block, otherwise the sync
+    /// is stalled, and it can't wait for the response to be processed.
+    ///
+    /// The callback is stored in the client, so it must not hold a [`Client`],
+    /// as it would then never be dropped. Use a [`WeakClient`] instead:
+    ///
+    /// ```no_run
+    /// # use matrix_sdk::{Client, WeakClient};
+    /// # let client: Client = unimplemented!();
+    /// let weak_client = WeakClient::from_client(&client);
+    ///
+    /// client.set_raw_sync_callback(move |response| {
+    ///     let Some(client) = weak_client.get() else { return };
+    ///
+    ///     for room_id in response.rooms.join.keys() {
+    ///         if client.get_room(room_id).is_none() {
+    ///             println!("Joined a new room: {room_id}");
+    ///         }
+    ///     }
+    /// });
+    /// ```
+    pub fn set_raw_sync_callback(
+        &self,
+        callback: impl Fn(&sync_events::v3::Response) + SendOutsideWasm + SyncOutsideWasm + 'static,
+    ) {
+        *self.inner.raw_sync_callback.write().unwrap() = Some(Arc::new(callback));
+    }
+
     /// Subscribe to all updates for the room with the given ID.
     ///
     /// The returned receiver will receive a new message for each sync response
@@ -1135,6 +1206,15 @@ impl Client {
         self.inner.notification_handlers.read().await
     }
 
+    /// Get the state events that couldn't be deserialized, and have thus been
+    /// ignored, while processing the last sync response.
+    ///
+    /// This is always empty unless
+    /// [`ClientBuilder::collect_deserialization_errors()`] has been called.
+    pub fn last_deserialization_errors(&self) -> Vec<EventDeserializationError> {
+        self.base_client().last_deserialization_errors()
+    }
+
     /// Get all the rooms the client knows about.
     ///
     /// This will return the list of joined, invited, and left rooms.
@@ -1556,6 +1636,57 @@ impl Client {
         self.finish_join_room(&response.room_id, pre_join_info).await
     }
 
+    /// Leave and forget several rooms at once.
+    ///
+    /// The rooms are left concurrently, with a bounded number of requests in
+    /// flight at the same time. A failure to leave one room doesn't prevent the
+    /// others from being left.
+    ///
+    /// Returns one result per room, in the same order as `room_ids`. Rooms
+    /// unknown to the client result in an [`Error::UnknownRoom`].
+    ///
+    /// # Arguments
+    ///
+    /// * `room_ids` - The IDs of the rooms to leave.
+    pub async fn leave_rooms(&self, room_ids: &[&RoomId]) -> Vec<Result<()>> {
+        const MAX_CONCURRENT_LEAVES: usize = 8;
+
+        futures_util::stream::iter(room_ids)
+            .map(|room_id| async move {
+                let room =
+                    self.get_room(room_id).ok_or_else(|| Error::UnknownRoom(room_id.to_owned()))?;
+                room.leave_and_forget().await
+            })
+            .buffered(MAX_CONCURRENT_LEAVES)
+            .collect()
+            .await
+    }
+
+    /// Evaluate the client owner's push rules against an event, to know how
+    /// they would be notified about it.
+    ///
+    /// If the push context of the room can't be computed, for instance
+    /// because the current user's membership isn't known yet,
+    /// [`NotificationAction::None`] is returned.
+    ///
+    /// # Arguments
+    ///
+    /// * `room_id` - The ID of the room the event belongs to.
+    /// * `event` - The event to evaluate.
+    pub async fn notification_action_for_event(
+        &self,
+        room_id: &RoomId,
+        event: &Raw<AnySyncTimelineEvent>,
+    ) -> Result<NotificationAction> {
+        let room = self.get_room(room_id).ok_or_else(|| Error::UnknownRoom(room_id.to_owned()))?;
+
+        Ok(room
+            .event_push_actions(event)
+            .await?
+            .map(|actions| NotificationAction::from_actions(&actions))
+            .unwrap_or(NotificationAction::None))
+    }
+
     /// Search the homeserver's directory of public rooms.
     ///
     /// Sends a request to "_matrix/client/r0/publicRooms", returns
@@ -1720,6 +1851,40 @@ impl Client {
         self.send(request).await
     }
 
+    /// Get the rooms and subspaces of a space, by traversing its hierarchy.
+    ///
+    /// Sends a request to `/_matrix/client/v1/rooms/{roomId}/hierarchy`. The
+    /// request can limit the depth of the traversal, only return the suggested
+    /// rooms, or continue a previous traversal with the `next_batch` token of
+    /// its response.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use url::Url;
+    /// # use matrix_sdk::Client;
+    /// # async {
+    /// # let homeserver = Url::parse("http://example.com")?;
+    /// use matrix_sdk::ruma::{api::client::space::get_hierarchy, room_id};
+    /// # let client = Client::new(homeserver).await?;
+    ///
+    /// let request = get_hierarchy::v1::Request::new(
+    ///     room_id!("!space:example.org").to_owned(),
+    /// );
+    /// let response = client.space_hierarchy(request).await?;
+    ///
+    /// for room in response.rooms {
+    ///     println!("Found room {} in the space", room.room_id);
+    /// }
+    /// # anyhow::Ok(()) };
+    /// ```
+    pub async fn space_hierarchy(
+        &self,
+        request: get_hierarchy::v1::Request,
+    ) -> HttpResult<get_hierarchy::v1::Response> {
+        self.send(request).await
+    }
+
     /// Send an arbitrary request to the server, without updating client state.
     ///
     /// **Warning:** Because this method *does not* update the client state, it
@@ -2261,12 +2426,23 @@ impl Client {
             timeout: sync_settings.timeout,
         });
         let mut request_config = self.request_config();
+        if let Some(sync_timeout) = request_config.sync_timeout {
+            request_config.timeout = sync_timeout;
+        }
         if let Some(timeout) = sync_settings.timeout {
             request_config.timeout += timeout;
         }
 
         let response = self.send(request).with_request_config(request_config).await?;
         let next_batch = response.next_batch.clone();
+
+        // Don't hold the lock while calling the callback, so it can set a new callback
+        // without deadlocking.
+        let callback = self.inner.raw_sync_callback.read().unwrap().clone();
+        if let Some(callback) = callback {
+            callback(&response);
+        }
+
         let response = self.process_sync(response).await?;
 
         #[cfg(feature = "e2e-encryption")]
@@ -2585,6 +2761,98 @@ impl Client {
         self.send(request).await
     }
 
+    /// Check that the access token of the client belongs to the current
+    /// session.
+    ///
+    /// This sends a `/whoami` request and compares the user ID and device ID it
+    /// returns with the ones of the session, to detect the access token of
+    /// another session being used by mistake, e.g. in multi-account setups.
+    ///
+    /// Returns [`Error::SessionMismatch`] if they differ.
+    pub async fn validate_session(&self) -> Result<whoami::v3::Response> {
+        let Some(session_meta) = self.session_meta() else {
+            return Err(Error::AuthenticationRequired);
+        };
+
+        let response = self.whoami().await?;
+
+        let same_device = response
+            .device_id
+            .as_ref()
+            .is_none_or(|device_id| *device_id == session_meta.device_id);
+
+        if response.user_id != session_meta.user_id || !same_device {
+            warn!(
+                expected_user_id = %session_meta.user_id,
+                expected_device_id = %session_meta.device_id,
+                user_id = %response.user_id,
+                device_id = ?response.device_id,
+                "The access token belongs to another session"
+            );
+            return Err(Error::SessionMismatch);
+        }
+
+        Ok(response)
+    }
+
+    /// Check whether the homeserver is reachable, and whether the session of
+    /// the client is still valid, without syncing.
+    ///
+    /// This sends a `/versions` request, bypassing the cached server versions,
+    /// then a `/whoami` request if the homeserver is reachable and the client
+    /// is logged in. The requests are not retried, and their errors are
+    /// reported in the returned [`HealthStatus`]: only a `/whoami` request
+    /// rejected with an `M_UNKNOWN_TOKEN` error or a `401 Unauthorized` status
+    /// means that the client isn't authenticated, other errors mean that the
+    /// homeserver isn't reachable.
+    pub async fn health_check(&self) -> HealthStatus {
+        let request_config = self.request_config().disable_retry();
+
+        let server_versions = match self.fetch_server_versions(Some(request_config)).await {
+            Ok(response) => response.known_versions().collect(),
+            Err(error) => {
+                debug!(?error, "The homeserver is unreachable");
+                return HealthStatus {
+                    reachable: false,
+                    authenticated: false,
+                    server_versions: Vec::new(),
+                };
+            }
+        };
+
+        let authenticated = if self.session_meta().is_some() {
+            let request = whoami::v3::Request::new();
+
+            match self.send(request).with_request_config(request_config).await {
+                Ok(_) => true,
+                Err(error) => {
+                    let token_rejected = matches!(
+                        error.client_api_error_kind(),
+                        Some(ErrorKind::UnknownToken { .. })
+                    ) || error
+                        .as_client_api_error()
+                        .is_some_and(|error| error.status_code == http::StatusCode::UNAUTHORIZED);
+
+                    if !token_rejected {
+                        debug!(?error, "The homeserver failed to answer the `/whoami` request");
+                        return HealthStatus {
+                            reachable: false,
+                            authenticated: false,
+                            server_versions,
+                        };
+                    }
+
+                    debug!(?error, "The access token was rejected");
+                    false
+                }
+            }
+        } else {
+            false
+        };
+
+        HealthStatus { reachable: true, authenticated, server_versions }
+    }
+
     /// Subscribes a new receiver to client SessionChange broadcasts.
     pub fn subscribe_to_session_changes(&self) -> broadcast::Receiver<SessionChange> {
         let broadcast = &self.auth_ctx().session_change_sender;
@@ -2595,6 +2863,13 @@ impl Client {
     ///
     /// This is another mechanism to get synchronous updates to session tokens,
     /// while [`Self::subscribe_to_session_changes`] provides an async update.
+    ///
+    /// The save session callback is called whenever the tokens change, notably
+    /// after they've been refreshed automatically with
+    /// [`ClientBuilder::handle_refresh_tokens()`], and before the request that
+    /// triggered the refresh is retried. [`Client::session_tokens()`] returns
+    /// the new tokens by then.
+    #[doc(alias = "tokens_changed")]
     pub fn set_session_callbacks(
         &self,
         reload_session_callback: Box<ReloadSessionCallback>,
@@ -2621,6 +2896,38 @@ impl Client {
         NotificationSettings::new(self.clone(), ruleset)
     }
 
+    /// Add or enable a push rule notifying for messages containing the given
+    /// keyword.
+    ///
+    /// This is a shortcut for [`NotificationSettings::add_keyword`]; prefer
+    /// keeping a [`NotificationSettings`] around when doing several changes.
+    pub async fn add_keyword_rule(&self, keyword: String) -> Result<(), NotificationSettingsError> {
+        self.notification_settings().await.add_keyword(keyword).await
+    }
+
+    /// Remove the push rules for the given keyword.
+    ///
+    /// This is a shortcut for [`NotificationSettings::remove_keyword`].
+    pub async fn remove_keyword_rule(
+        &self,
+        keyword: &str,
+    ) -> Result<(), NotificationSettingsError> {
+        self.notification_settings().await.remove_keyword(keyword).await
+    }
+
+    /// Set the notification mode of a room, by creating or removing the
+    /// matching push rules.
+    ///
+    /// This is a shortcut for
+    /// [`NotificationSettings::set_room_notification_mode`].
+    pub async fn set_room_push_rule(
+        &self,
+        room_id: &RoomId,
+        mode: RoomNotificationMode,
+    ) -> Result<(), NotificationSettingsError> {
+        self.notification_settings().await.set_room_notification_mode(room_id, mode).await
+    }
+
     /// Create a new specialized `Client` that can process notifications.
     ///
     /// See [`CrossProcessStoreLock::new`] to learn more about
@@ -2762,14 +3069,17 @@ impl Client {
 
 /// A weak reference to the inner client, useful when trying to get a handle
 /// on the owning client.
+///
+/// Unlike a [`Client`], it doesn't keep the client alive, so it can be stored
+/// in callbacks that are themselves stored in the client.
 #[derive(Clone)]
-pub(crate) struct WeakClient {
+pub struct WeakClient {
     client: Weak<ClientInner>,
 }
 
 impl WeakClient {
     /// Construct a [`WeakClient`] from a `Arc<ClientInner>`.
-    pub fn from_inner(client: &Arc<ClientInner>) -> Self {
+    pub(crate) fn from_inner(client: &Arc<ClientInner>) -> Self {
         Self { client: Arc::downgrade(client) }
     }
 
@@ -2791,6 +3101,12 @@ impl WeakClient {
     }
 }
 
+impl Debug for WeakClient {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(fmt, "WeakClient")
+    }
+}
+
 #[derive(Clone)]
 struct ClientServerInfo {
     /// The Matrix versions the server supports (known ones only).
@@ -2893,7 +3209,7 @@ pub(crate) mod tests {
             logged_in_client, mocks::MatrixMockServer, no_retry_test_client, set_client_session,
             test_client_builder, test_client_builder_with_server,
         },
-        Error, TransmissionProgress,
+        Error, HttpError, TransmissionProgress,
     };
 
     #[async_test]
@@ -3051,6 +3367,51 @@ pub(crate) mod tests {
         client.matrix_auth().login_username("example", "wordpass").send().await.unwrap_err();
     }
 
+    #[async_test]
+    async fn test_sync_timeout() {
+        let server = MockServer::start().await;
+        let client = test_client_builder(Some(server.uri()))
+            .request_config(
+                RequestConfig::new()
+                    .disable_retry()
+                    .timeout(Duration::from_millis(100))
+                    .sync_timeout(Duration::from_secs(5)),
+            )
+            .build()
+            .await
+            .unwrap();
+        set_client_session(&client).await;
+
+        Mock::given(method("GET"))
+            .and(path("/_matrix/client/r0/sync"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(&*test_json::SYNC)
+                    .set_delay(Duration::from_millis(500)),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path("/_matrix/client/r0/account/whoami"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(&*test_json::WHOAMI)
+                    .set_delay(Duration::from_millis(500)),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        // The short request timeout aborts the slow `/whoami` request…
+        assert_let!(Err(HttpError::Reqwest(error)) = client.whoami().await);
+        assert!(error.is_timeout());
+
+        // …but not the equally slow `/sync` request, which uses the sync timeout.
+        client.sync_once(SyncSettings::new().timeout(Duration::ZERO)).await.unwrap();
+    }
+
     #[async_test]
     async fn test_short_retry_initial_http_requests() {
         let server = MockServer::start().await;
@@ -44,6 +44,7 @@ use crate::http_client::DEFAULT_REQUEST_TIMEOUT;
 #[derive(Copy, Clone)]
 pub struct RequestConfig {
     pub(crate) timeout: Duration,
+    pub(crate) sync_timeout: Option<Duration>,
     pub(crate) retry_limit: Option<usize>,
     pub(crate) max_retry_time: Option<Duration>,
     pub(crate) max_concurrent_requests: Option<NonZeroUsize>,
@@ -56,6 +57,7 @@ impl Debug for RequestConfig {
     fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
         let Self {
             timeout,
+            sync_timeout,
             retry_limit,
             max_retry_time: retry_timeout,
             force_auth,
@@ -65,6 +67,7 @@ impl Debug for RequestConfig {
 
         let mut res = fmt.debug_struct("RequestConfig");
         res.field("timeout", timeout)
+            .maybe_field("sync_timeout", sync_timeout)
             .maybe_field("retry_limit", retry_limit)
             .maybe_field("max_retry_time", retry_timeout)
             .maybe_field("max_concurrent_requests", max_concurrent_requests)
@@ -82,6 +85,7 @@ impl Default for RequestConfig {
     fn default() -> Self {
         Self {
             timeout: DEFAULT_REQUEST_TIMEOUT,
+            sync_timeout: Default::default(),
             retry_limit: Default::default(),
             max_retry_time: Default::default(),
             max_concurrent_requests: Default::default(),
@@ -131,12 +135,31 @@ impl RequestConfig {
     }
 
     /// Set the timeout duration for all HTTP requests.
+    ///
+    /// The `/sync` requests use the [sync timeout] instead, if it is set.
+    ///
+    /// [sync timeout]: RequestConfig::sync_timeout
+    #[doc(alias = "request_timeout")]
     #[must_use]
     pub fn timeout(mut self, timeout: Duration) -> Self {
         self.timeout = timeout;
         self
     }
 
+    /// Set the timeout duration for the `/sync` HTTP requests.
+    ///
+    /// The long-polling timeout of the [`SyncSettings`] is added to this
+    /// duration. The default is to use the [same timeout] as the other
+    /// requests.
+    ///
+    /// [`SyncSettings`]: crate::config::SyncSettings
+    /// [same timeout]: RequestConfig::timeout
+    #[must_use]
+    pub fn sync_timeout(mut self, timeout: Duration) -> Self {
+        self.sync_timeout = Some(timeout);
+        self
+    }
+
     /// Set a time limit for how long a request should be retried. The default
     /// is that there isn't a limit, meaning requests are retried forever.
     ///
@@ -180,12 +203,14 @@ mod tests {
             .force_auth()
             .max_retry_time(Duration::from_secs(32))
             .retry_limit(4)
-            .timeout(Duration::from_secs(600));
+            .timeout(Duration::from_secs(600))
+            .sync_timeout(Duration::from_secs(60));
 
         assert!(cfg.force_auth);
         assert_eq!(cfg.retry_limit, Some(4));
         assert_eq!(cfg.max_retry_time, Some(Duration::from_secs(32)));
         assert_eq!(cfg.timeout, Duration::from_secs(600));
+        assert_eq!(cfg.sync_timeout, Some(Duration::from_secs(60)));
     }
 
     #[test]
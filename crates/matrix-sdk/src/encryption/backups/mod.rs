@@ -54,9 +54,7 @@ pub(crate) mod types;
 pub use types::{BackupState, UploadState};
 
 use self::futures::WaitForSteadyState;
-use crate::{
-    crypto::olm::ExportedRoomKey, encryption::BackupDownloadStrategy, Client, Error, Room,
-};
+use crate::{encryption::BackupDownloadStrategy, Client, Error, Room};
 
 /// The backups manager for the [`Client`].
 #[derive(Debug, Clone)]
@@ -550,44 +548,14 @@ impl Backups {
         backup_version: &str,
         olm_machine: &OlmMachine,
     ) -> Result<(), Error> {
-        let mut decrypted_room_keys: Vec<_> = Vec::new();
-
-        for (room_id, room_keys) in backed_up_keys.rooms {
-            for (session_id, room_key) in room_keys.sessions {
-                let room_key = match room_key.deserialize() {
-                    Ok(k) => k,
-                    Err(e) => {
-                        warn!(
-                            "Couldn't deserialize a room key we downloaded from backups, session \
-                             ID: {session_id}, error: {e:?}"
-                        );
-                        continue;
-                    }
-                };
-
-                let room_key =
-                    match backup_decryption_key.decrypt_session_data(room_key.session_data) {
-                        Ok(k) => k,
-                        Err(e) => {
-                            warn!(
-                                "Couldn't decrypt a room key we downloaded from backups, session \
-                                 ID: {session_id}, error: {e:?}"
-                            );
-                            continue;
-                        }
-                    };
-
-                decrypted_room_keys.push(ExportedRoomKey::from_backed_up_room_key(
-                    room_id.to_owned(),
-                    session_id,
-                    room_key,
-                ));
-            }
-        }
-
         let result = olm_machine
-            .store()
-            .import_room_keys(decrypted_room_keys, Some(backup_version), |_, _| {})
+            .backup_machine()
+            .decrypt_and_import_room_keys(
+                &backup_decryption_key,
+                backup_version,
+                backed_up_keys.rooms,
+                |_, _| {},
+            )
             .await?;
 
         // Since we can't use the usual room keys stream from the `OlmMachine`
@@ -1045,7 +1013,10 @@ mod test {
     };
 
     use super::*;
-    use crate::test_utils::{logged_in_client, mocks::MatrixMockServer};
+    use crate::{
+        crypto::olm::ExportedRoomKey,
+        test_utils::{logged_in_client, mocks::MatrixMockServer},
+    };
 
     fn room_key() -> ExportedRoomKey {
         let json = json!({
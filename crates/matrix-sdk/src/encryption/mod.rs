@@ -32,12 +32,15 @@ use futures_util::{
     future::try_join,
     stream::{self, StreamExt},
 };
-use matrix_sdk_base::crypto::{
-    store::types::{RoomKeyBundleInfo, RoomKeyInfo},
-    types::requests::{
-        OutgoingRequest, OutgoingVerificationRequest, RoomMessageRequest, ToDeviceRequest,
+use matrix_sdk_base::{
+    crypto::{
+        store::types::{DeviceKeyChanged, RoomKeyBundleInfo, RoomKeyInfo},
+        types::requests::{
+            OutgoingRequest, OutgoingVerificationRequest, RoomMessageRequest, ToDeviceRequest,
+        },
+        CrossSigningBootstrapRequests, OlmMachine,
     },
-    CrossSigningBootstrapRequests, OlmMachine,
+    deserialized_responses::TimelineEvent,
 };
 use matrix_sdk_common::{executor::spawn, locks::Mutex as StdMutex};
 use ruma::{
@@ -57,13 +60,14 @@ use ruma::{
         direct::DirectUserIdentifier,
         room::{MediaSource, ThumbnailInfo},
     },
-    DeviceId, MilliSecondsSinceUnixEpoch, OwnedDeviceId, OwnedUserId, TransactionId, UserId,
+    DeviceId, MilliSecondsSinceUnixEpoch, OwnedDeviceId, OwnedRoomId, OwnedUserId, TransactionId,
+    UserId,
 };
 #[cfg(feature = "experimental-send-custom-to-device")]
 use ruma::{events::AnyToDeviceEventContent, serde::Raw, to_device::DeviceIdOrAllDevices};
 use serde::Deserialize;
-use tokio::sync::{Mutex, RwLockReadGuard};
-use tokio_stream::wrappers::errors::BroadcastStreamRecvError;
+use tokio::sync::{broadcast, Mutex, RwLockReadGuard};
+use tokio_stream::wrappers::{errors::BroadcastStreamRecvError, BroadcastStream};
 use tracing::{debug, error, instrument, trace, warn};
 use url::Url;
 use vodozemac::Curve25519PublicKey;
@@ -74,7 +78,7 @@ use self::{
     identities::{Device, DeviceUpdates, IdentityUpdates, UserDevices, UserIdentity},
     recovery::{Recovery, RecoveryState},
     secret_storage::SecretStorage,
-    tasks::{BackupDownloadTask, BackupUploadingTask, ClientTasks},
+    tasks::{BackupDownloadTask, BackupUploadingTask, ClientTasks, RedecryptionTask},
     verification::{SasVerification, Verification, VerificationRequest},
 };
 use crate::{
@@ -121,6 +125,10 @@ pub(crate) struct EncryptionData {
 
     /// All state related to secret storage recovery.
     pub recovery_state: SharedObservable<RecoveryState>,
+
+    /// The events which couldn't be decrypted at first, and have been
+    /// decrypted after the room key they were encrypted with was received.
+    pub redecrypted_events: broadcast::Sender<RedecryptedEvent>,
 }
 
 impl EncryptionData {
@@ -131,6 +139,7 @@ impl EncryptionData {
             tasks: StdMutex::new(Default::default()),
             backup_state: Default::default(),
             recovery_state: Default::default(),
+            redecrypted_events: broadcast::Sender::new(100),
         }
     }
 
@@ -139,6 +148,7 @@ impl EncryptionData {
 
         let mut tasks = self.tasks.lock();
         tasks.upload_room_keys = Some(BackupUploadingTask::new(weak_client.clone()));
+        tasks.redecrypt_utds = Some(RedecryptionTask::new(weak_client.clone()));
 
         if self.encryption_settings.backup_download_strategy
             == BackupDownloadStrategy::AfterDecryptionFailure
@@ -162,6 +172,19 @@ impl EncryptionData {
     }
 }
 
+/// An event which couldn't be decrypted at first, and has been decrypted after
+/// the room key it was encrypted with was received.
+///
+/// See [`Encryption::redecrypted_events_stream()`].
+#[derive(Clone, Debug)]
+pub struct RedecryptedEvent {
+    /// The room the event was sent in.
+    pub room_id: OwnedRoomId,
+
+    /// The decrypted event.
+    pub event: TimelineEvent,
+}
+
 /// Settings for end-to-end encryption features.
 #[derive(Clone, Copy, Debug, Default)]
 pub struct EncryptionSettings {
@@ -372,6 +395,11 @@ struct OAuthCrossSigningResetUiaaResetParameter {
     url: Url,
 }
 
+/// The maximum number of users in a single `/keys/query` request sent by
+/// [`Client::keys_query_users`], the same as the one used by the
+/// `OlmMachine` for its own key queries.
+const MAX_KEYS_QUERY_USERS: usize = 250;
+
 impl Client {
     pub(crate) async fn olm_machine(&self) -> RwLockReadGuard<'_, Option<OlmMachine>> {
         self.base_client().olm_machine().await
@@ -413,6 +441,35 @@ impl Client {
         Ok(response)
     }
 
+    /// Query the server for the device keys of all the given users, batching
+    /// them in as few requests as possible.
+    ///
+    /// This avoids one round-trip per user when prefetching the devices of
+    /// many users, e.g. the members of a large room. The users are split into
+    /// `/keys/query` requests of at most 250 users, sent one after the other.
+    /// The received devices are stored in the crypto store, and can be
+    /// retrieved with [`Encryption::get_user_devices`].
+    ///
+    /// These queries happen outside of the tracking of the users' devices:
+    /// the users don't become tracked, and their devices aren't marked as up
+    /// to date, so the regular key queries of the sync loop still happen for
+    /// the tracked users whose devices are outdated.
+    #[instrument(skip_all, fields(num_users = users.len()))]
+    pub async fn keys_query_users(&self, users: &[OwnedUserId]) -> Result<()> {
+        for users in users.chunks(MAX_KEYS_QUERY_USERS) {
+            let (request_id, request) = self
+                .olm_machine()
+                .await
+                .as_ref()
+                .ok_or(Error::NoOlmMachine)?
+                .query_keys_for_users(users.iter().map(|user_id| user_id.as_ref()));
+
+            self.keys_query(&request_id, request.device_keys).await?;
+        }
+
+        Ok(())
+    }
+
     /// Construct a [`EncryptedFile`][ruma::events::room::EncryptedFile] by
     /// encrypting and uploading a provided reader.
     ///
@@ -610,6 +667,7 @@ impl Client {
         room
     }
 
+    #[instrument(skip_all, fields(request_id = %r.request_id()))]
     async fn send_outgoing_request(&self, r: OutgoingRequest) -> Result<()> {
         use matrix_sdk_base::crypto::types::requests::AnyOutgoingRequest;
 
@@ -1043,6 +1101,20 @@ impl Encryption {
             .map(move |updates| DeviceUpdates::new(client.to_owned(), updates)))
     }
 
+    /// Returns a stream of devices whose Ed25519 key changed in a
+    /// `/keys/query` response.
+    ///
+    /// The Ed25519 key of a device is pinned the first time the device is
+    /// seen, so such changes are rejected and the device keeps its original
+    /// keys. This stream allows to warn the user about them, since they might
+    /// indicate that the homeserver is misbehaving.
+    pub async fn device_key_changes_stream(&self) -> Result<impl Stream<Item = DeviceKeyChanged>> {
+        let olm = self.client.olm_machine().await;
+        let olm = olm.as_ref().ok_or(Error::NoOlmMachine)?;
+
+        Ok(olm.store().device_key_changes_stream())
+    }
+
     /// Returns a stream of user identity updates, allowing users to listen for
     /// notifications about new or changed user identities.
     ///
@@ -1478,6 +1550,38 @@ impl Encryption {
         Some(olm.store().room_keys_received_stream())
     }
 
+    /// Receive the events which couldn't be decrypted at first, once they've
+    /// been decrypted, as a [`Stream`].
+    ///
+    /// When [`Room::decrypt_event()`] fails to decrypt an event because the
+    /// room key is missing, the event is remembered. Once the room key is
+    /// received, be it from the key backup, a key forward or a key share, the
+    /// event is decrypted again, and sent to this stream if it succeeded.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use matrix_sdk::Client;
+    /// # use url::Url;
+    /// # async {
+    /// # let homeserver = Url::parse("http://example.com")?;
+    /// # let client = Client::new(homeserver).await?;
+    /// use futures_util::StreamExt;
+    ///
+    /// let mut redecrypted_events =
+    ///     client.encryption().redecrypted_events_stream();
+    ///
+    /// while let Some(Ok(redecrypted)) = redecrypted_events.next().await {
+    ///     println!("Decrypted an event in {}", redecrypted.room_id);
+    /// }
+    /// # anyhow::Ok(()) };
+    /// ```
+    pub fn redecrypted_events_stream(
+        &self,
+    ) -> impl Stream<Item = Result<RedecryptedEvent, BroadcastStreamRecvError>> {
+        BroadcastStream::new(self.client.inner.e2ee.redecrypted_events.subscribe())
+    }
+
     /// Receive notifications of historic room key bundles as a [`Stream`].
     ///
     /// Historic room key bundles are defined in [MSC4268](https://github.com/matrix-org/matrix-spec-proposals/pull/4268).
@@ -68,6 +68,7 @@ use matrix_sdk_base::crypto::{
 };
 use ruma::{
     events::{
+        secret::request::SecretName,
         secret_storage::{
             default_key::SecretStorageDefaultKeyEventContent, key::SecretStorageKeyEventContent,
         },
@@ -137,6 +138,11 @@ pub enum SecretStorageError {
     /// Error describing a decryption failure of a secret.
     #[error(transparent)]
     Decryption(#[from] DecryptionError),
+
+    /// The secret can't be imported into the local store because the SDK
+    /// doesn't know how to use it.
+    #[error("The secret {0} can't be imported, only well-known secrets are supported")]
+    UnsupportedSecret(SecretName),
 }
 
 /// Error type describing decryption failures of the secret-storage system.
@@ -30,7 +30,7 @@ use tracing::{
 };
 use zeroize::Zeroize;
 
-use super::{DecryptionError, Result};
+use super::{DecryptionError, Result, SecretStorageError};
 use crate::Client;
 
 #[cfg_attr(doc, aquamarine::aquamarine)]
@@ -262,6 +262,43 @@ impl SecretStore {
         Ok(())
     }
 
+    /// List the well-known secrets which are stored in the secret store, and
+    /// which can be imported with [`SecretStore::import_secret()`].
+    ///
+    /// Only the secrets which are encrypted with the [`SecretStorageKey`] of
+    /// this [`SecretStore`] are listed. The secrets aren't decrypted.
+    ///
+    /// The following secrets are considered by this method:
+    ///
+    /// - `m.cross_signing.master`: The master cross-signing key.
+    /// - `m.cross_signing.self_signing`: The self-signing cross-signing key.
+    /// - `m.cross_signing.user_signing`: The user-signing cross-signing key.
+    /// - `m.megolm_backup.v1`: The backup recovery key.
+    pub async fn list_secrets(&self) -> Result<Vec<SecretName>> {
+        let mut secrets = Vec::new();
+
+        for secret_name in [
+            SecretName::CrossSigningMasterKey,
+            SecretName::CrossSigningSelfSigningKey,
+            SecretName::CrossSigningUserSigningKey,
+            SecretName::RecoveryKey,
+        ] {
+            let event_type = GlobalAccountDataEventType::from(secret_name.to_owned());
+
+            if let Some(secret_content) =
+                self.client.account().fetch_account_data(event_type).await?
+            {
+                let secret_content = secret_content.deserialize_as::<SecretEventContent>()?;
+
+                if secret_content.encrypted.contains_key(self.key.key_id()) {
+                    secrets.push(secret_name);
+                }
+            }
+        }
+
+        Ok(secrets)
+    }
+
     /// Get all the well-known private parts/keys of the [`OwnUserIdentity`] as
     /// a [`CrossSigningKeyExport`].
     ///
@@ -293,6 +330,52 @@ impl SecretStore {
         Ok(())
     }
 
+    /// Import the given private cross-signing keys into the [`OlmMachine`],
+    /// and sign our own device if the self-signing key could be imported.
+    async fn import_cross_signing_keys(&self, export: CrossSigningKeyExport) -> Result<()> {
+        let olm_machine = self.client.olm_machine().await;
+        let olm_machine = olm_machine.as_ref().ok_or(crate::Error::NoOlmMachine)?;
+
+        // We need to ensure that we have the public parts of the cross-signing keys,
+        // those are represented as the `OwnUserIdentity` struct. The public
+        // parts from the server are compared to the public parts re-derived from the
+        // private parts. We will only import the private parts of the cross-signing
+        // keys if they match to the public parts, otherwise we would risk
+        // importing some stale cross-signing keys leftover in the secret store.
+        let (request_id, request) = olm_machine.query_keys_for_users([olm_machine.user_id()]);
+        self.client.keys_query(&request_id, request.device_keys).await?;
+
+        // Let's now try to import our private cross-signing keys.
+        let status = olm_machine.import_cross_signing_keys(export).await?;
+
+        Span::current().record("cross_signing_status", debug(&status));
+
+        info!("Done importing the cross signing keys");
+
+        if status.has_self_signing {
+            info!("Successfully imported the self-signing key, attempting to sign our own device");
+
+            // Now that we successfully imported them, the self-signing key can be used to
+            // verify our own device so other devices and user identities trust
+            // it if the trust our user identity.
+            if let Some(own_device) = self.client.encryption().get_own_device().await? {
+                own_device.verify().await?;
+
+                // Another /keys/query request to ensure that the signatures we uploaded using
+                // `own_device.verify()` are attached to the `Device` we have in storage.
+                let (request_id, request) =
+                    olm_machine.query_keys_for_users([olm_machine.user_id()]);
+                self.client.keys_query(&request_id, request.device_keys).await?;
+
+                info!("Successfully signed our own device, the device is now verified");
+            } else {
+                error!("Couldn't find our own device in the store");
+            }
+        }
+
+        Ok(())
+    }
+
     async fn maybe_enable_backups(&self) -> Result<()> {
         if let Some(mut secret) = self.get_secret(SecretName::RecoveryKey).await? {
             let ret = self.client.encryption().backups().maybe_enable_backups(&secret).await;
@@ -377,46 +460,44 @@ impl SecretStore {
 
         info!(cross_signing_keys = ?export, "Received the cross signing keys from the server");
 
-        // We need to ensure that we have the public parts of the cross-signing keys,
-        // those are represented as the `OwnUserIdentity` struct. The public
-        // parts from the server are compared to the public parts re-derived from the
-        // private parts. We will only import the private parts of the cross-signing
-        // keys if they match to the public parts, otherwise we would risk
-        // importing some stale cross-signing keys leftover in the secret store.
-        let (request_id, request) = olm_machine.query_keys_for_users([olm_machine.user_id()]);
-        self.client.keys_query(&request_id, request.device_keys).await?;
-
-        // Let's now try to import our private cross-signing keys.
-        let status = olm_machine.import_cross_signing_keys(export).await?;
-
-        Span::current().record("cross_signing_status", debug(&status));
+        self.import_cross_signing_keys(export).await?;
 
-        info!("Done importing the cross signing keys");
-
-        if status.has_self_signing {
-            info!("Successfully imported the self-signing key, attempting to sign our own device");
+        self.maybe_enable_backups().await?;
 
-            // Now that we successfully imported them, the self-signing key can be used to
-            // verify our own device so other devices and user identities trust
-            // it if the trust our user identity.
-            if let Some(own_device) = self.client.encryption().get_own_device().await? {
-                own_device.verify().await?;
+        Ok(())
+    }
 
-                // Another /keys/query request to ensure that the signatures we uploaded using
-                // `own_device.verify()` are attached to the `Device` we have in storage.
-                let (request_id, request) =
-                    olm_machine.query_keys_for_users([olm_machine.user_id()]);
-                self.client.keys_query(&request_id, request.device_keys).await?;
+    /// Retrieve and store a single well-known secret locally.
+    ///
+    /// This is the granular counterpart of [`SecretStore::import_secrets()`],
+    /// the secrets which can be imported can be listed with
+    /// [`SecretStore::list_secrets()`].
+    ///
+    /// Importing the `m.cross_signing.self_signing` key also signs our own
+    /// [`Device`], and importing the `m.megolm_backup.v1` key enables backups
+    /// if possible. Secrets that the SDK doesn't know how to import result in a
+    /// [`SecretStorageError::UnsupportedSecret`] error.
+    ///
+    /// [`Device`]: crate::encryption::identities::Device
+    #[instrument(skip(self), fields(cross_signing_status))]
+    pub async fn import_secret(&self, secret_name: SecretName) -> Result<()> {
+        let mut export = CrossSigningKeyExport::default();
 
-                info!("Successfully signed our own device, the device is now verified");
-            } else {
-                error!("Couldn't find our own device in the store");
+        match secret_name {
+            SecretName::CrossSigningMasterKey => {
+                export.master_key = self.get_secret(secret_name).await?;
+            }
+            SecretName::CrossSigningSelfSigningKey => {
+                export.self_signing_key = self.get_secret(secret_name).await?;
+            }
+            SecretName::CrossSigningUserSigningKey => {
+                export.user_signing_key = self.get_secret(secret_name).await?;
             }
+            SecretName::RecoveryKey => return self.maybe_enable_backups().await,
+            _ => return Err(SecretStorageError::UnsupportedSecret(secret_name)),
         }
 
-        self.maybe_enable_backups().await?;
-
-        Ok(())
+        self.import_cross_signing_keys(export).await
     }
 
     pub(super) async fn export_secrets(&self) -> Result<()> {
@@ -14,7 +14,10 @@
 
 use std::{collections::BTreeMap, sync::Arc, time::Duration};
 
-use matrix_sdk_common::failures_cache::FailuresCache;
+use futures_core::Stream;
+use futures_util::{pin_mut, StreamExt};
+use matrix_sdk_base::crypto::{store::types::RoomKeyInfo as ReceivedRoomKeyInfo, OlmMachine};
+use matrix_sdk_common::{failures_cache::FailuresCache, locks::Mutex as StdMutex};
 use ruma::{
     events::room::encrypted::{EncryptedEventScheme, OriginalSyncRoomEncryptedEvent},
     serde::Raw,
@@ -24,11 +27,12 @@ use tokio::sync::{
     mpsc::{self, UnboundedReceiver},
     Mutex,
 };
+use tokio_stream::wrappers::errors::BroadcastStreamRecvError;
 use tracing::{debug, trace, warn};
 
 use crate::{
     client::WeakClient,
-    encryption::backups::UploadState,
+    encryption::{backups::UploadState, RedecryptedEvent},
     executor::{spawn, JoinHandle},
     Client,
 };
@@ -40,6 +44,7 @@ type DownloadCache = FailuresCache<RoomKeyInfo>;
 pub(crate) struct ClientTasks {
     pub(crate) upload_room_keys: Option<BackupUploadingTask>,
     pub(crate) download_room_keys: Option<BackupDownloadTask>,
+    pub(crate) redecrypt_utds: Option<RedecryptionTask>,
     pub(crate) update_recovery_state_after_backup: Option<JoinHandle<()>>,
     pub(crate) setup_e2ee: Option<JoinHandle<()>>,
 }
@@ -385,6 +390,255 @@ impl BackupDownloadTaskListenerState {
     }
 }
 
+/// The maximum number of events waiting for their room key in
+/// [`PendingUtds`].
+const MAX_PENDING_UTDS: usize = 1000;
+
+/// The events we couldn't decrypt, grouped by the room key they were encrypted
+/// with, and indexed by event ID.
+///
+/// The number of events is bounded: when it's full, the events recorded the
+/// longest time ago are forgotten first.
+struct PendingUtds {
+    /// The events, along with the sequence number of their recording.
+    events:
+        BTreeMap<RoomKeyInfo, BTreeMap<OwnedEventId, (u64, Raw<OriginalSyncRoomEncryptedEvent>)>>,
+    /// The room key and event ID of the events, ordered by the sequence number
+    /// of their recording.
+    recording_order: BTreeMap<u64, (RoomKeyInfo, OwnedEventId)>,
+    /// The sequence number of the next recorded event.
+    next_sequence_number: u64,
+    /// The maximum number of events.
+    max_len: usize,
+}
+
+impl Default for PendingUtds {
+    fn default() -> Self {
+        Self::new(MAX_PENDING_UTDS)
+    }
+}
+
+impl PendingUtds {
+    fn new(max_len: usize) -> Self {
+        Self {
+            events: Default::default(),
+            recording_order: Default::default(),
+            next_sequence_number: 0,
+            max_len,
+        }
+    }
+
+    /// The number of events waiting for their room key.
+    fn len(&self) -> usize {
+        self.recording_order.len()
+    }
+
+    /// Record an event encrypted with the given room key, forgetting the
+    /// oldest event if there are too many.
+    fn insert(
+        &mut self,
+        room_key: RoomKeyInfo,
+        event_id: OwnedEventId,
+        event: Raw<OriginalSyncRoomEncryptedEvent>,
+    ) {
+        let sequence_number = self.next_sequence_number;
+        self.next_sequence_number += 1;
+
+        let previous = self
+            .events
+            .entry(room_key.clone())
+            .or_default()
+            .insert(event_id.clone(), (sequence_number, event));
+
+        if let Some((previous_sequence_number, _)) = previous {
+            self.recording_order.remove(&previous_sequence_number);
+        }
+
+        self.recording_order.insert(sequence_number, (room_key, event_id));
+
+        while self.len() > self.max_len {
+            let Some((_, (room_key, event_id))) = self.recording_order.pop_first() else {
+                break;
+            };
+
+            trace!(?room_key, %event_id, "Too many pending UTDs, forgetting the oldest one");
+
+            if let Some(events) = self.events.get_mut(&room_key) {
+                events.remove(&event_id);
+
+                if events.is_empty() {
+                    self.events.remove(&room_key);
+                }
+            }
+        }
+    }
+
+    /// Remove the events encrypted with the given room key.
+    fn remove(
+        &mut self,
+        room_key: &RoomKeyInfo,
+    ) -> Option<(RoomKeyInfo, BTreeMap<OwnedEventId, Raw<OriginalSyncRoomEncryptedEvent>>)> {
+        let (room_key, events) = self.events.remove_entry(room_key)?;
+
+        let events = events
+            .into_iter()
+            .map(|(event_id, (sequence_number, event))| {
+                self.recording_order.remove(&sequence_number);
+                (event_id, event)
+            })
+            .collect();
+
+        Some((room_key, events))
+    }
+
+    /// Remove all the events.
+    fn take_all(
+        &mut self,
+    ) -> Vec<(RoomKeyInfo, BTreeMap<OwnedEventId, Raw<OriginalSyncRoomEncryptedEvent>>)> {
+        self.recording_order.clear();
+
+        std::mem::take(&mut self.events)
+            .into_iter()
+            .map(|(room_key, events)| {
+                (
+                    room_key,
+                    events.into_iter().map(|(event_id, (_, event))| (event_id, event)).collect(),
+                )
+            })
+            .collect()
+    }
+}
+
+/// A task decrypting again the events we couldn't decrypt, once the room key
+/// they were encrypted with is received.
+pub(crate) struct RedecryptionTask {
+    client: WeakClient,
+    pending_utds: Arc<StdMutex<PendingUtds>>,
+    join_handle: Option<JoinHandle<()>>,
+}
+
+impl Drop for RedecryptionTask {
+    fn drop(&mut self) {
+        #[cfg(not(target_family = "wasm"))]
+        if let Some(join_handle) = &self.join_handle {
+            join_handle.abort();
+        }
+    }
+}
+
+impl RedecryptionTask {
+    pub(crate) fn new(client: WeakClient) -> Self {
+        Self { client, pending_utds: Default::default(), join_handle: None }
+    }
+
+    /// Record an event we couldn't decrypt, so it's decrypted again once the
+    /// room key it was encrypted with is received.
+    ///
+    /// Does nothing unless the event is encrypted using `m.megolm.v1.aes-sha2`.
+    pub(crate) fn record_utd(
+        &mut self,
+        machine: &OlmMachine,
+        room_id: OwnedRoomId,
+        event: Raw<OriginalSyncRoomEncryptedEvent>,
+    ) {
+        let Ok(deserialized_event) = event.deserialize() else {
+            return;
+        };
+        let EncryptedEventScheme::MegolmV1AesSha2(c) = deserialized_event.content.scheme else {
+            return;
+        };
+
+        self.pending_utds.lock().insert(
+            (room_id, c.session_id),
+            deserialized_event.event_id,
+            event,
+        );
+
+        // Start listening to the received room keys, unless we're already doing so. The
+        // stream ends when the `OlmMachine` is regenerated, so the listener may need to
+        // be started again.
+        if self.join_handle.as_ref().is_none_or(|join_handle| join_handle.is_finished()) {
+            let room_keys_stream = machine.store().room_keys_received_stream();
+            self.join_handle = Some(spawn(Self::listen(
+                self.client.clone(),
+                self.pending_utds.clone(),
+                room_keys_stream,
+            )));
+        }
+    }
+
+    /// Listen to the received room keys, and decrypt again the pending events
+    /// encrypted with them.
+    ///
+    /// This will keep running until either the room keys stream ends, or all
+    /// other references to `Client` are dropped.
+    async fn listen(
+        client: WeakClient,
+        pending_utds: Arc<StdMutex<PendingUtds>>,
+        room_keys_stream: impl Stream<Item = Result<Vec<ReceivedRoomKeyInfo>, BroadcastStreamRecvError>>,
+    ) {
+        pin_mut!(room_keys_stream);
+
+        while let Some(room_keys) = room_keys_stream.next().await {
+            let Some(client) = client.get() else {
+                trace!("Client got dropped, shutting down the task");
+                break;
+            };
+
+            let retried_utds: Vec<_> = {
+                let mut pending_utds = pending_utds.lock();
+
+                match room_keys {
+                    Ok(room_keys) => room_keys
+                        .into_iter()
+                        .filter_map(|room_key| {
+                            pending_utds.remove(&(room_key.room_id, room_key.session_id))
+                        })
+                        .collect(),
+
+                    Err(BroadcastStreamRecvError::Lagged(lag)) => {
+                        // We don't know which room keys we missed, so try all the pending events
+                        // again.
+                        warn!(lag, "Lagged behind the received room keys, retrying all the UTDs");
+                        pending_utds.take_all()
+                    }
+                }
+            };
+
+            for ((room_id, _), events) in retried_utds {
+                let Some(room) = client.get_room(&room_id) else {
+                    continue;
+                };
+
+                let push_ctx = room.push_context().await.unwrap_or_else(|error| {
+                    warn!(?error, "Couldn't compute the push context");
+                    None
+                });
+
+                for (event_id, event) in events {
+                    // If the event still can't be decrypted, `decrypt_event` records it again.
+                    match room.decrypt_event(&event, push_ctx.as_ref()).await {
+                        Ok(event) if event.encryption_info().is_some() => {
+                            debug!(%room_id, %event_id, "Decrypted a previously undecryptable event");
+
+                            // There might be no subscribers, that's fine.
+                            let _ = client
+                                .inner
+                                .e2ee
+                                .redecrypted_events
+                                .send(RedecryptedEvent { room_id: room_id.clone(), event });
+                        }
+                        Ok(_) => {}
+                        Err(error) => {
+                            warn!(%room_id, %event_id, ?error, "Couldn't decrypt an event again");
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
 #[cfg(all(test, not(target_family = "wasm")))]
 mod test {
     use matrix_sdk_test::async_test;
@@ -451,4 +705,35 @@ mod test {
             )
         }
     }
+
+    #[test]
+    fn test_pending_utds_are_bounded() {
+        let room_id = room_id!("!DovneieKSTkdHKpIXy:morpheus.localhost");
+        let room_key = |session_id: &str| (room_id.to_owned(), session_id.to_owned());
+        let event = Raw::new(&json!({})).unwrap().cast();
+
+        let mut pending_utds = PendingUtds::new(2);
+        pending_utds.insert(room_key("a"), event_id!("$1").to_owned(), event.clone());
+        pending_utds.insert(room_key("b"), event_id!("$2").to_owned(), event.clone());
+        assert_eq!(pending_utds.len(), 2);
+
+        // The oldest event is forgotten when there are too many events.
+        pending_utds.insert(room_key("b"), event_id!("$3").to_owned(), event.clone());
+        assert_eq!(pending_utds.len(), 2);
+        assert!(pending_utds.remove(&room_key("a")).is_none());
+
+        // Recording an event again makes it the most recent one.
+        pending_utds.insert(room_key("b"), event_id!("$2").to_owned(), event.clone());
+        pending_utds.insert(room_key("c"), event_id!("$4").to_owned(), event);
+        assert_eq!(pending_utds.len(), 2);
+
+        let (_, events) = pending_utds.remove(&room_key("b")).unwrap();
+        assert_eq!(events.into_keys().collect::<Vec<_>>(), vec![event_id!("$2").to_owned()]);
+        assert_eq!(pending_utds.len(), 1);
+
+        let remaining = pending_utds.take_all();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].0, room_key("c"));
+        assert_eq!(pending_utds.len(), 0);
+    }
 }
@@ -39,15 +39,19 @@ use ruma::{
     },
     events::tag::InvalidUserTagName,
     push::{InsertPushRuleError, RemovePushRuleError},
-    IdParseError,
+    IdParseError, OwnedRoomId,
 };
 use serde_json::Error as JsonError;
 use thiserror::Error;
 use url::ParseError as UrlParseError;
 
 use crate::{
-    authentication::oauth::OAuthError, event_cache::EventCacheError, media::MediaError,
-    room::reply::ReplyError, sliding_sync::Error as SlidingSyncError, store_locks::LockStoreError,
+    authentication::oauth::OAuthError,
+    event_cache::EventCacheError,
+    media::MediaError,
+    room::{edit::EditError, reaction::ReactionError, reply::ReplyError},
+    sliding_sync::Error as SlidingSyncError,
+    store_locks::LockStoreError,
 };
 
 /// Result type of the matrix-sdk.
@@ -367,6 +371,20 @@ pub enum Error {
     #[error("wrong room state: {0}")]
     WrongRoomState(Box<WrongRoomState>),
 
+    /// Attempted to call a method that is only available on spaces on a room
+    /// that isn't a space.
+    #[error("the room isn't a space")]
+    NotASpace,
+
+    /// The room isn't known to the client.
+    #[error("unknown room: {0}")]
+    UnknownRoom(OwnedRoomId),
+
+    /// The access token of the client doesn't belong to the user or device of
+    /// the current session.
+    #[error("the access token doesn't belong to the current session")]
+    SessionMismatch,
+
     /// Session callbacks have been set multiple times.
     #[error("session callbacks have been set multiple times")]
     MultipleSessionCallbacks,
@@ -415,6 +433,14 @@ pub enum Error {
     /// An error happened while attempting to reply to an event.
     #[error(transparent)]
     ReplyError(#[from] ReplyError),
+
+    /// An error happened while attempting to edit an event.
+    #[error(transparent)]
+    EditError(#[from] EditError),
+
+    /// An error happened while attempting to react to an event.
+    #[error(transparent)]
+    ReactionError(#[from] ReactionError),
 }
 
 #[rustfmt::skip] // stop rustfmt breaking the `<code>` in docs across multiple lines
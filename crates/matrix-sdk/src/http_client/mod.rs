@@ -27,6 +27,7 @@ use bytes::{Bytes, BytesMut};
 use bytesize::ByteSize;
 use eyeball::SharedObservable;
 use http::Method;
+use matrix_sdk_common::{SendOutsideWasm, SyncOutsideWasm};
 use ruma::api::{
     error::{FromHttpResponseError, IntoHttpError},
     AuthScheme, MatrixVersion, OutgoingRequest, SendAccessToken,
@@ -46,6 +47,30 @@ pub(crate) use native::HttpSettings;
 
 pub(crate) const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
 
+/// A hook into the HTTP requests sent by the [`Client`](crate::Client).
+///
+/// Middlewares are registered with
+/// [`ClientBuilder::add_request_middleware()`](crate::ClientBuilder::add_request_middleware),
+/// and are called in the order in which they were added. They can be used to
+/// add custom headers to the requests, or to observe the responses, e.g. to
+/// collect metrics.
+pub trait RequestMiddleware: Debug + SendOutsideWasm + SyncOutsideWasm {
+    /// Called before a request is sent, with the serialized request.
+    ///
+    /// It is called once per request, even if the request is retried.
+    fn on_request(&self, request: &mut http::Request<Bytes>) {
+        let _ = request;
+    }
+
+    /// Called when a response is received, before it is deserialized.
+    ///
+    /// It is called for every attempt to send a request, so it observes the
+    /// responses of the requests that will be retried too.
+    fn on_response(&self, response: &http::Response<Bytes>) {
+        let _ = response;
+    }
+}
+
 #[derive(Clone, Debug)]
 struct MaybeSemaphore(Arc<Option<Semaphore>>);
 
@@ -76,6 +101,7 @@ pub(crate) struct HttpClient {
     pub(crate) request_config: RequestConfig,
     concurrent_request_semaphore: MaybeSemaphore,
     next_request_id: Arc<AtomicU64>,
+    middlewares: Arc<[Arc<dyn RequestMiddleware>]>,
 }
 
 impl HttpClient {
@@ -87,6 +113,20 @@ impl HttpClient {
                 request_config.max_concurrent_requests,
             ),
             next_request_id: AtomicU64::new(0).into(),
+            middlewares: Arc::new([]),
+        }
+    }
+
+    /// Set the middlewares to call when sending requests.
+    pub(crate) fn with_middlewares(mut self, middlewares: Vec<Arc<dyn RequestMiddleware>>) -> Self {
+        self.middlewares = middlewares.into();
+        self
+    }
+
+    /// Let the middlewares observe the given response.
+    fn on_response(&self, response: &http::Response<Bytes>) {
+        for middleware in self.middlewares.iter() {
+            middleware.on_response(response);
         }
     }
 
@@ -176,10 +216,14 @@ impl HttpClient {
                 }
             }
 
-            let request = self
+            let mut request = self
                 .serialize_request(request, config, homeserver, access_token, server_versions)
                 .map_err(HttpError::IntoHttp)?;
 
+            for middleware in self.middlewares.iter() {
+                middleware.on_request(&mut request);
+            }
+
             let method = request.method();
 
             let mut uri_parts = request.uri().clone().into_parts();
@@ -258,16 +302,22 @@ mod tests {
             atomic::{AtomicU8, Ordering},
             Arc,
         },
-        time::Duration,
+        time::{Duration, Instant},
     };
 
-    use matrix_sdk_common::executor::spawn;
+    use assert_matches2::assert_matches;
+    use bytes::Bytes;
+    use http::{HeaderValue, StatusCode};
+    use matrix_sdk_common::{executor::spawn, locks::Mutex};
     use matrix_sdk_test::{async_test, test_json};
+    use ruma::api::client::error::ErrorKind;
+    use serde_json::json;
     use wiremock::{
-        matchers::{method, path},
+        matchers::{header, method, path},
         Mock, Request, ResponseTemplate,
     };
 
+    use super::RequestMiddleware;
     use crate::{
         http_client::RequestConfig,
         test_utils::{set_client_session, test_client_builder_with_server},
@@ -357,4 +407,107 @@ mod tests {
         assert_eq!(counter.load(Ordering::SeqCst), 254, "Not all requests passed through");
         bg_task.abort();
     }
+
+    #[async_test]
+    async fn test_rate_limited_request_is_retried_after_delay() {
+        let (client_builder, server) = test_client_builder_with_server().await;
+        let client = client_builder
+            .request_config(RequestConfig::default().retry_limit(3))
+            .build()
+            .await
+            .unwrap();
+
+        set_client_session(&client).await;
+
+        Mock::given(method("GET"))
+            .and(path("_matrix/client/r0/account/whoami"))
+            .respond_with(ResponseTemplate::new(429).set_body_json(json!({
+                "errcode": "M_LIMIT_EXCEEDED",
+                "error": "Too many requests",
+                "retry_after_ms": 1500,
+            })))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path("_matrix/client/r0/account/whoami"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(&*test_json::WHOAMI))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let start = Instant::now();
+        client.whoami().await.unwrap();
+
+        // The request was retried after the delay requested by the server, rather than
+        // after the default backoff delay.
+        assert!(start.elapsed() >= Duration::from_millis(1500));
+    }
+
+    #[async_test]
+    async fn test_rate_limited_request_fails_after_retry_limit() {
+        let (client_builder, server) = test_client_builder_with_server().await;
+        let client = client_builder
+            .request_config(RequestConfig::default().retry_limit(2))
+            .build()
+            .await
+            .unwrap();
+
+        set_client_session(&client).await;
+
+        Mock::given(method("GET"))
+            .and(path("_matrix/client/r0/account/whoami"))
+            .respond_with(ResponseTemplate::new(429).set_body_json(json!({
+                "errcode": "M_LIMIT_EXCEEDED",
+                "error": "Too many requests",
+                "retry_after_ms": 100,
+            })))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let error = client.whoami().await.unwrap_err();
+        assert_matches!(error.client_api_error_kind(), Some(ErrorKind::LimitExceeded { .. }));
+    }
+
+    #[derive(Debug, Default)]
+    struct TestMiddleware {
+        statuses: Arc<Mutex<Vec<StatusCode>>>,
+    }
+
+    impl RequestMiddleware for TestMiddleware {
+        fn on_request(&self, request: &mut http::Request<Bytes>) {
+            request.headers_mut().insert("x-trace-id", HeaderValue::from_static("trace-1234"));
+        }
+
+        fn on_response(&self, response: &http::Response<Bytes>) {
+            self.statuses.lock().push(response.status());
+        }
+    }
+
+    #[async_test]
+    async fn test_request_middleware() {
+        let (client_builder, server) = test_client_builder_with_server().await;
+
+        let middleware = TestMiddleware::default();
+        let statuses = middleware.statuses.clone();
+
+        let client = client_builder.add_request_middleware(middleware).build().await.unwrap();
+
+        set_client_session(&client).await;
+
+        Mock::given(method("GET"))
+            .and(path("_matrix/client/r0/account/whoami"))
+            .and(header("x-trace-id", "trace-1234"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(&*test_json::WHOAMI))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        client.whoami().await.unwrap();
+
+        assert_eq!(*statuses.lock(), [StatusCode::OK]);
+    }
 }
@@ -80,6 +80,8 @@ impl HttpClient {
                 let response =
                     send_request(&self.inner, &request, config.timeout, send_progress).await?;
 
+                self.on_response(&response);
+
                 let status_code = response.status();
                 let response_size = ByteSize(response.body().len().try_into().unwrap_or(u64::MAX));
                 tracing::Span::current()
@@ -139,6 +141,11 @@ impl HttpClient {
     }
 }
 
+/// The user agent used when none was set with
+/// [`ClientBuilder::user_agent()`](crate::ClientBuilder::user_agent).
+#[cfg(not(target_family = "wasm"))]
+const DEFAULT_USER_AGENT: &str = concat!("matrix-rust-sdk/", env!("CARGO_PKG_VERSION"));
+
 #[cfg(not(target_family = "wasm"))]
 #[derive(Clone, Debug)]
 pub(crate) struct HttpSettings {
@@ -168,7 +175,7 @@ impl Default for HttpSettings {
 impl HttpSettings {
     /// Build a client with the specified configuration.
     pub(crate) fn make_client(&self) -> Result<reqwest::Client, HttpError> {
-        let user_agent = self.user_agent.clone().unwrap_or_else(|| "matrix-rust-sdk".to_owned());
+        let user_agent = self.user_agent.clone().unwrap_or_else(|| DEFAULT_USER_AGENT.to_owned());
         let mut http_client = reqwest::Client::builder()
             .user_agent(user_agent)
             .timeout(self.timeout)
@@ -38,6 +38,8 @@ impl HttpClient {
         let request = reqwest::Request::try_from(request)?;
         let response = response_to_http_response(self.inner.execute(request).await?).await?;
 
+        self.on_response(&response);
+
         let status_code = response.status();
         let response_size = ByteSize(response.body().len().try_into().unwrap_or(u64::MAX));
         tracing::Span::current()
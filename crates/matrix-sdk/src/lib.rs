@@ -53,6 +53,7 @@ pub mod pusher;
 pub mod room;
 pub mod room_directory_search;
 pub mod room_preview;
+pub mod search;
 pub mod send_queue;
 pub mod utils;
 pub mod futures {
@@ -68,13 +69,14 @@ pub mod widget;
 pub use account::Account;
 pub use authentication::{AuthApi, AuthSession, SessionTokens};
 pub use client::{
-    sanitize_server_name, Client, ClientBuildError, ClientBuilder, LoopCtrl, SessionChange,
+    sanitize_server_name, Client, ClientBuildError, ClientBuilder, HealthStatus, LoopCtrl,
+    SessionChange, WeakClient,
 };
 pub use error::{
     Error, HttpError, HttpResult, NotificationSettingsError, RefreshTokenError, Result,
     RumaApiError,
 };
-pub use http_client::TransmissionProgress;
+pub use http_client::{RequestMiddleware, TransmissionProgress};
 #[cfg(all(feature = "e2e-encryption", feature = "sqlite"))]
 pub use matrix_sdk_sqlite::SqliteCryptoStore;
 #[cfg(feature = "sqlite")]
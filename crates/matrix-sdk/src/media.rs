@@ -28,13 +28,16 @@ pub use matrix_sdk_base::{event_cache::store::media::MediaRetentionPolicy, media
 use mime::Mime;
 use ruma::{
     api::{
-        client::{authenticated_media, error::ErrorKind, media},
+        client::{
+            authenticated_media, error::ErrorKind, media, media::get_content_thumbnail::v3::Method,
+        },
         MatrixVersion,
     },
     assign,
     events::room::{MediaSource, ThumbnailInfo},
-    MilliSecondsSinceUnixEpoch, MxcUri, OwnedMxcUri, TransactionId, UInt,
+    uint, MilliSecondsSinceUnixEpoch, MxcUri, OwnedMxcUri, TransactionId, UInt,
 };
+use serde::Deserialize;
 #[cfg(not(target_family = "wasm"))]
 use tempfile::{Builder as TempFileBuilder, NamedTempFile, TempDir};
 #[cfg(not(target_family = "wasm"))]
@@ -160,6 +163,54 @@ pub enum MediaError {
     FetchMaxUploadSizeFailed(String),
 }
 
+/// A preview of a URL, as returned by [`Media::get_url_preview()`].
+///
+/// The fields are extracted from the OpenGraph data of the page, and are all
+/// optional since pages don't necessarily provide them.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+pub struct UrlPreview {
+    /// The title of the page.
+    #[serde(rename = "og:title")]
+    pub title: Option<String>,
+
+    /// The description of the page.
+    #[serde(rename = "og:description")]
+    pub description: Option<String>,
+
+    /// The MXC URI of the image of the page, uploaded by the homeserver.
+    #[serde(rename = "og:image")]
+    pub image: Option<OwnedMxcUri>,
+}
+
+/// The size of an avatar to fetch with [`Media::get_avatar()`].
+///
+/// The dimensions match the thumbnail sizes that homeservers usually generate
+/// ahead of time, so the thumbnail can be returned right away.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AvatarSize {
+    /// A 32x32 pixels avatar, cropped.
+    Small,
+
+    /// A 96x96 pixels avatar, cropped.
+    Medium,
+
+    /// A 320x240 pixels avatar, scaled.
+    Large,
+}
+
+impl AvatarSize {
+    /// The settings of the thumbnail to request for this size.
+    pub fn thumbnail_settings(self) -> MediaThumbnailSettings {
+        match self {
+            Self::Small => MediaThumbnailSettings::with_method(Method::Crop, uint!(32), uint!(32)),
+            Self::Medium => MediaThumbnailSettings::with_method(Method::Crop, uint!(96), uint!(96)),
+            Self::Large => {
+                MediaThumbnailSettings::with_method(Method::Scale, uint!(320), uint!(240))
+            }
+        }
+    }
+}
+
 impl Media {
     pub(crate) fn new(client: Client) -> Self {
         Self { client }
@@ -434,27 +485,7 @@ impl Media {
             }
         };
 
-        // Use the authenticated endpoints when the server supports Matrix 1.11 or the
-        // authenticated media stable feature.
-        const AUTHENTICATED_MEDIA_STABLE_FEATURE: &str = "org.matrix.msc3916.stable";
-
-        let (use_auth, request_config) =
-            if self.client.server_versions().await?.contains(&MatrixVersion::V1_11) {
-                (true, None)
-            } else if self
-                .client
-                .unstable_features()
-                .await?
-                .get(AUTHENTICATED_MEDIA_STABLE_FEATURE)
-                .is_some_and(|is_supported| *is_supported)
-            {
-                // We need to force the use of the stable endpoint with the Matrix version
-                // because Ruma does not handle stable features.
-                let request_config = self.client.request_config();
-                (true, Some(request_config.force_matrix_version(MatrixVersion::V1_11)))
-            } else {
-                (false, None)
-            };
+        let (use_auth, request_config) = self.authenticated_media_config().await?;
 
         let content: Vec<u8> = match &request.source {
             MediaSource::Encrypted(file) => {
@@ -540,6 +571,70 @@ impl Media {
         Ok(content)
     }
 
+    /// Get a preview of the given URL, built by the homeserver from the
+    /// OpenGraph data of the page.
+    ///
+    /// Previews are cached in memory by URL, so the homeserver is only asked
+    /// once per URL for a while.
+    ///
+    /// Note that the homeserver fetches the URL, so it learns which links are
+    /// previewed. Clients might want to avoid previewing links of encrypted
+    /// rooms.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The URL to get a preview of.
+    pub async fn get_url_preview(&self, url: &str) -> Result<UrlPreview> {
+        if let Some(preview) = self.client.inner.caches.url_previews.lock().await.get(url) {
+            return Ok(preview);
+        }
+
+        let (use_auth, request_config) = self.authenticated_media_config().await?;
+
+        let data = if use_auth {
+            let request = authenticated_media::get_media_preview::v1::Request::new(url.to_owned());
+            self.client.send(request).with_request_config(request_config).await?.data
+        } else {
+            #[allow(deprecated)]
+            let request = media::get_media_preview::v3::Request::new(url.to_owned());
+            self.client.send(request).await?.data
+        };
+
+        let preview = match data {
+            Some(data) => serde_json::from_str(data.get())?,
+            None => UrlPreview::default(),
+        };
+
+        self.client.inner.caches.url_previews.lock().await.insert(url.to_owned(), preview.clone());
+
+        Ok(preview)
+    }
+
+    /// Whether the authenticated media endpoints should be used, along with
+    /// the request config to use with them.
+    async fn authenticated_media_config(&self) -> Result<(bool, Option<RequestConfig>)> {
+        // Use the authenticated endpoints when the server supports Matrix 1.11 or the
+        // authenticated media stable feature.
+        const AUTHENTICATED_MEDIA_STABLE_FEATURE: &str = "org.matrix.msc3916.stable";
+
+        if self.client.server_versions().await?.contains(&MatrixVersion::V1_11) {
+            Ok((true, None))
+        } else if self
+            .client
+            .unstable_features()
+            .await?
+            .get(AUTHENTICATED_MEDIA_STABLE_FEATURE)
+            .is_some_and(|is_supported| *is_supported)
+        {
+            // We need to force the use of the stable endpoint with the Matrix version
+            // because Ruma does not handle stable features.
+            let request_config = self.client.request_config();
+            Ok((true, Some(request_config.force_matrix_version(MatrixVersion::V1_11))))
+        } else {
+            Ok((false, None))
+        }
+    }
+
     /// Get a media file's content that is only available in the media cache.
     ///
     /// # Arguments
@@ -686,6 +781,38 @@ impl Media {
         Ok(())
     }
 
+    /// Get the content of an avatar, like a room or user avatar, at the given
+    /// size.
+    ///
+    /// The thumbnail is read from the media cache if possible. Since the
+    /// homeserver can't generate thumbnails of encrypted media, the full file
+    /// is requested for [`MediaSource::Encrypted`] sources.
+    ///
+    /// Returns `Ok(None)` if the homeserver doesn't know about the media.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The source of the avatar.
+    ///
+    /// * `size` - The _desired_ size of the avatar. The actual avatar may not
+    ///   match this size.
+    pub async fn get_avatar(
+        &self,
+        source: MediaSource,
+        size: AvatarSize,
+    ) -> Result<Option<Vec<u8>>> {
+        let format = match &source {
+            MediaSource::Plain(_) => MediaFormat::Thumbnail(size.thumbnail_settings()),
+            MediaSource::Encrypted(_) => MediaFormat::File,
+        };
+
+        match self.get_media_content(&MediaRequestParameters { source, format }, true).await {
+            Ok(content) => Ok(Some(content)),
+            Err(error) if error.client_api_error_kind() == Some(&ErrorKind::NotFound) => Ok(None),
+            Err(error) => Err(error),
+        }
+    }
+
     /// Set the [`MediaRetentionPolicy`] to use for deciding whether to store or
     /// keep media content.
     ///
@@ -82,6 +82,31 @@ impl From<bool> for IsOneToOne {
     }
 }
 
+/// How the client owner would be notified about an event, according to their
+/// push rules.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotificationAction {
+    /// The event should notify and be highlighted.
+    Highlight,
+    /// The event should notify.
+    Notify,
+    /// The event shouldn't notify.
+    None,
+}
+
+impl NotificationAction {
+    /// Compute the notification action from the push actions of an event.
+    pub fn from_actions(actions: &[Action]) -> Self {
+        if actions.iter().any(Action::is_highlight) {
+            Self::Highlight
+        } else if actions.iter().any(Action::should_notify) {
+            Self::Notify
+        } else {
+            Self::None
+        }
+    }
+}
+
 /// A high-level API to manage the client owner's push notification settings.
 #[derive(Debug, Clone)]
 pub struct NotificationSettings {
@@ -28,7 +28,7 @@ use ruma::{
         AnySyncTimelineEvent, AnyTimelineEvent, Mentions, MessageLikeEvent,
         OriginalMessageLikeEvent, SyncMessageLikeEvent,
     },
-    EventId, RoomId, UserId,
+    EventId, OwnedEventId, RoomId, UserId,
 };
 use thiserror::Error;
 use tracing::{instrument, warn};
@@ -124,6 +124,32 @@ impl Room {
     ) -> Result<AnyMessageLikeEventContent, EditError> {
         make_edit_event(self, self.room_id(), self.own_user_id(), event_id, new_content).await
     }
+
+    /// Edit a `m.room.message` previously sent by the current user.
+    ///
+    /// This builds the edit event with [`Room::make_edit_event`], i.e. with the
+    /// `m.replace` relation and the `m.new_content` field, and sends it
+    /// immediately. The target event must have been sent by the current user,
+    /// otherwise [`EditError::NotAuthor`] is returned without sending
+    /// anything.
+    ///
+    /// The edit is sent without a local echo; use a
+    /// [`crate::send_queue::RoomSendQueue`] with the result of
+    /// [`Room::make_edit_event`] to get one.
+    ///
+    /// Returns the event ID of the edit event.
+    #[instrument(skip(self, new_content), fields(room = %self.room_id()))]
+    pub async fn edit_message(
+        &self,
+        original_event_id: &EventId,
+        new_content: RoomMessageEventContent,
+    ) -> crate::Result<OwnedEventId> {
+        let content = self
+            .make_edit_event(original_event_id, EditedContent::RoomMessage(new_content.into()))
+            .await?;
+
+        Ok(self.send(content).await?.event_id)
+    }
 }
 
 async fn make_edit_event<S: EventSource>(
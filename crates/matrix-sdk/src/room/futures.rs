@@ -72,6 +72,7 @@ impl<'a> SendMessageLikeEvent<'a> {
     ///   corresponding [`SyncMessageLikeEvent`], but only for the *sending*
     ///   device. Other devices will not see it. This is then used to ignore
     ///   events sent by our own device and/or to implement local echo.
+    #[doc(alias = "send_with_txn_id")]
     pub fn with_transaction_id(mut self, txn_id: OwnedTransactionId) -> Self {
         self.transaction_id = Some(txn_id);
         self
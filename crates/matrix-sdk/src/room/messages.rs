@@ -149,6 +149,17 @@ pub struct Messages {
     pub state: Vec<Raw<AnyStateEvent>>,
 }
 
+impl Messages {
+    /// Whether there are more events to paginate in the same direction.
+    ///
+    /// If this is true, [`Messages::end`] can be used as the
+    /// [`MessagesOptions::from`] token of the next call to
+    /// [`super::Room::messages`].
+    pub fn has_more(&self) -> bool {
+        self.end.is_some()
+    }
+}
+
 /// The result of a [`super::Room::event_with_context`] query.
 ///
 /// This is a wrapper around
@@ -168,6 +168,7 @@ pub mod knock_requests;
 mod member;
 mod messages;
 pub mod power_levels;
+pub mod reaction;
 pub mod reply;
 
 /// Contains all the functionality for modifying the privacy settings in a room.
@@ -321,6 +322,29 @@ impl Room {
     #[doc(alias = "reject_invitation")]
     #[instrument(skip_all, fields(room_id = ?self.inner.room_id()))]
     pub async fn leave(&self) -> Result<()> {
+        self.leave_impl(None).await
+    }
+
+    /// Leave this room, then forget it.
+    ///
+    /// Once this succeeds, the room is removed from the store and won't be
+    /// returned by [`Client::rooms`] anymore.
+    ///
+    /// Only invited and joined rooms can be left.
+    #[instrument(skip_all, fields(room_id = ?self.inner.room_id()))]
+    pub async fn leave_and_forget(&self) -> Result<()> {
+        self.leave_impl(None).await?;
+
+        // Leaving an invited room already forgets it, in which case it's gone from
+        // the client.
+        if self.client.get_room(self.room_id()).is_some() {
+            self.forget().await?;
+        }
+
+        Ok(())
+    }
+
+    async fn leave_impl(&self, reason: Option<String>) -> Result<()> {
         let state = self.state();
         if state == RoomState::Left {
             return Err(Error::WrongRoomState(Box::new(WrongRoomState::new(
@@ -333,7 +357,8 @@ impl Room {
         // invite.
         let should_forget = matches!(self.state(), RoomState::Invited);
 
-        let request = leave_room::v3::Request::new(self.inner.room_id().to_owned());
+        let request =
+            assign!(leave_room::v3::Request::new(self.inner.room_id().to_owned()), { reason });
         let response = self.client.send(request).await;
 
         // The server can return with an error that is acceptable to ignore. Let's find
@@ -390,6 +415,35 @@ impl Room {
         Ok(())
     }
 
+    /// Accept the invite to this room.
+    ///
+    /// Only invited rooms can be accepted. On success, the room is moved to
+    /// the [`RoomState::Joined`] state, and the joined room is returned.
+    pub async fn accept_invite(&self) -> Result<Room> {
+        let state = self.state();
+
+        if state != RoomState::Invited {
+            return Err(Error::WrongRoomState(Box::new(WrongRoomState::new("Invited", state))));
+        }
+
+        self.client.join_room_by_id(self.room_id()).await
+    }
+
+    /// Reject the invite to this room, with an optional reason.
+    ///
+    /// Only invited rooms can be rejected. On success, the room is left and
+    /// forgotten, i.e. it is removed from [`Client::rooms`].
+    #[instrument(skip_all, fields(room_id = ?self.inner.room_id()))]
+    pub async fn reject_invite(&self, reason: Option<String>) -> Result<()> {
+        let state = self.state();
+
+        if state != RoomState::Invited {
+            return Err(Error::WrongRoomState(Box::new(WrongRoomState::new("Invited", state))));
+        }
+
+        self.leave_impl(reason).await
+    }
+
     /// Get the inner client saved in this room instance.
     ///
     /// Returns the client this room is part of.
@@ -572,7 +626,7 @@ impl Room {
     ///
     /// Only logs from the crypto crate will indicate a failure to decrypt.
     #[allow(clippy::unused_async)] // Used only in e2e-encryption.
-    async fn try_decrypt_event(
+    pub(crate) async fn try_decrypt_event(
         &self,
         event: Raw<AnyTimelineEvent>,
         push_ctx: Option<&PushContext>,
@@ -840,11 +894,21 @@ impl Room {
         true
     }
 
-    /// Sync the member list with the server.
+    /// Sync the member list with the server, if it isn't synced yet.
+    ///
+    /// The member list is only fetched if [`BaseRoom::are_members_synced`]
+    /// returns `false`; otherwise this method returns immediately, without
+    /// sending any request. It doesn't return the members: once fetched, they
+    /// are persisted in the store, and can be read with [`Room::members`] or
+    /// [`Room::get_member`].
     ///
-    /// This method will de-duplicate requests if it is called multiple times in
-    /// quick succession, in that case the return value will be `None`. This
-    /// method does nothing if the members are already synced.
+    /// The member list is considered outdated again after a gappy sync, a
+    /// change of the membership of the current user, or an invite sent by the
+    /// current user.
+    ///
+    /// Concurrent calls for the same room are de-duplicated into a single
+    /// request.
+    #[doc(alias = "ensure_members_loaded")]
     pub async fn sync_members(&self) -> Result<()> {
         if !self.are_events_visible() {
             return Ok(());
@@ -1165,6 +1229,116 @@ impl Room {
             .collect::<FuturesUnordered<_>>())
     }
 
+    /// Add a room as a child of this space.
+    ///
+    /// This sends an `m.space.child` state event to this room. If the current
+    /// user is joined to the child room and is allowed to, an `m.space.parent`
+    /// state event pointing back to this space is sent to the child room too.
+    ///
+    /// The two state events can't be sent atomically. The `m.space.parent`
+    /// event is sent first, so if sending the `m.space.child` event fails,
+    /// the child room only points to a space which doesn't list it. Since the
+    /// hierarchy of the space is defined by its `m.space.child` events, the
+    /// room isn't part of the space in this case, and calling this method
+    /// again is safe.
+    ///
+    /// # Arguments
+    ///
+    /// * `child_room_id` - The ID of the room to add to the space.
+    ///
+    /// * `via` - The servers that can be used to join the child room.
+    ///
+    /// * `suggested` - Whether the child room should be suggested to the
+    ///   members of the space.
+    ///
+    /// Returns [`Error::NotASpace`] if this room isn't a space.
+    pub async fn add_space_child(
+        &self,
+        child_room_id: &RoomId,
+        via: Vec<OwnedServerName>,
+        suggested: bool,
+    ) -> Result<()> {
+        if !self.is_space() {
+            return Err(Error::NotASpace);
+        }
+
+        if let Some(child_room) = self.child_room_with_parent_rights(child_room_id).await? {
+            child_room
+                .send_state_event_for_key(self.room_id(), SpaceParentEventContent::new(via.clone()))
+                .await?;
+        }
+
+        let content = assign!(SpaceChildEventContent::new(via), { suggested });
+        self.send_state_event_for_key(child_room_id, content).await?;
+
+        Ok(())
+    }
+
+    /// Remove a room from the children of this space.
+    ///
+    /// This replaces the `m.space.child` state event of the child room in this
+    /// room with an empty one. If the child room has an `m.space.parent` state
+    /// event pointing to this space, and the current user is allowed to, it is
+    /// emptied too.
+    ///
+    /// The two state events can't be sent atomically. The `m.space.child`
+    /// event is emptied first, so the room is removed from the hierarchy of
+    /// the space even if emptying the `m.space.parent` event fails. In this
+    /// case, the child room still points to a space which doesn't list it,
+    /// like after a partial failure of [`Room::add_space_child()`], and
+    /// calling this method again is safe.
+    ///
+    /// Returns [`Error::NotASpace`] if this room isn't a space.
+    pub async fn remove_space_child(&self, child_room_id: &RoomId) -> Result<()> {
+        if !self.is_space() {
+            return Err(Error::NotASpace);
+        }
+
+        self.send_state_event_raw(
+            StateEventType::SpaceChild.as_str(),
+            child_room_id.as_str(),
+            serde_json::json!({}),
+        )
+        .await?;
+
+        if let Some(child_room) = self.child_room_with_parent_rights(child_room_id).await? {
+            let has_parent = child_room
+                .get_state_event_static_for_key::<SpaceParentEventContent, _>(self.room_id())
+                .await?
+                .is_some();
+
+            if has_parent {
+                child_room
+                    .send_state_event_raw(
+                        StateEventType::SpaceParent.as_str(),
+                        self.room_id().as_str(),
+                        serde_json::json!({}),
+                    )
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Get the given child room of this space, if the current user is joined
+    /// to it and allowed to send `m.space.parent` state events to it.
+    async fn child_room_with_parent_rights(&self, child_room_id: &RoomId) -> Result<Option<Room>> {
+        let Some(child_room) = self.client.get_room(child_room_id) else {
+            return Ok(None);
+        };
+
+        if child_room.state() != RoomState::Joined {
+            return Ok(None);
+        }
+
+        let Some(member) = child_room.get_member_no_sync(self.own_user_id()).await? else {
+            return Ok(None);
+        };
+
+        Ok(member.can_send_state(StateEventType::SpaceParent).then_some(child_room))
+    }
+
     /// Read account data in this room, from storage.
     pub async fn account_data(
         &self,
@@ -1467,6 +1641,14 @@ impl Room {
     /// Returns the decrypted event. In the case of a decryption error, returns
     /// a `TimelineEvent` representing the decryption error.
     #[cfg(feature = "e2e-encryption")]
+    #[instrument(
+        skip_all,
+        fields(
+            room_id = %self.room_id(),
+            event_id = ?event.get_field::<OwnedEventId>("event_id").ok().flatten(),
+            session_id,
+        )
+    )]
     pub async fn decrypt_event(
         &self,
         event: &Raw<OriginalSyncRoomEncryptedEvent>,
@@ -1475,7 +1657,7 @@ impl Room {
         let machine = self.client.olm_machine().await;
         let machine = machine.as_ref().ok_or(Error::NoOlmMachine)?;
 
-        match machine
+        let event = match machine
             .try_decrypt_room_event(
                 event.cast_ref(),
                 self.inner.room_id(),
@@ -1485,16 +1667,26 @@ impl Room {
         {
             RoomEventDecryptionResult::Decrypted(decrypted) => {
                 let push_actions = push_ctx.map(|push_ctx| push_ctx.for_event(&decrypted.event));
-                Ok(TimelineEvent::from_decrypted(decrypted, push_actions))
+                TimelineEvent::from_decrypted(decrypted, push_actions)
             }
             RoomEventDecryptionResult::UnableToDecrypt(utd_info) => {
                 self.client
                     .encryption()
                     .backups()
                     .maybe_download_room_key(self.room_id().to_owned(), event.clone());
-                Ok(TimelineEvent::from_utd(event.clone().cast(), utd_info))
+
+                // Try again once the room key has been received.
+                if let Some(task) = self.client.inner.e2ee.tasks.lock().redecrypt_utds.as_mut() {
+                    task.record_utd(machine, self.room_id().to_owned(), event.clone());
+                }
+
+                TimelineEvent::from_utd(event.clone().cast(), utd_info)
             }
-        }
+        };
+
+        tracing::Span::current().record("session_id", event.kind.session_id());
+
+        Ok(event)
     }
 
     /// Fetches the [`EncryptionInfo`] for an event decrypted with the supplied
@@ -1979,7 +2171,13 @@ impl Room {
     ///
     /// If you want to set a transaction ID for the event, use
     /// [`.with_transaction_id()`][SendMessageLikeEvent::with_transaction_id]
-    /// on the returned value before `.await`ing it.
+    /// on the returned value before `.await`ing it. Otherwise, one is
+    /// generated.
+    ///
+    /// Either way, the same transaction ID is used when the request is retried
+    /// after a network error, so the homeserver doesn't create the event twice
+    /// if it received the first attempt. To retry sending the event after this
+    /// method returned an error, pin the transaction ID and reuse it.
     ///
     /// # Arguments
     ///
@@ -4412,4 +4610,93 @@ mod tests {
         assert!(ret.sender_info.is_some());
         assert_eq!(ret.sender_info.unwrap().event().user_id(), sender_id);
     }
+
+    #[cfg(feature = "e2e-encryption")]
+    #[async_test]
+    async fn test_decrypt_event_span_fields() {
+        use std::{
+            collections::BTreeMap,
+            fmt,
+            sync::{Arc, Mutex},
+        };
+
+        use ruma::{events::room::encrypted::OriginalSyncRoomEncryptedEvent, serde::Raw};
+        use serde_json::json;
+        use tracing::{
+            field::{Field, Visit},
+            span::{Attributes, Id, Record},
+            Subscriber,
+        };
+        use tracing_subscriber::{
+            layer::{Context, SubscriberExt},
+            registry::LookupSpan,
+            Layer,
+        };
+
+        /// The non-empty fields of the spans, by span name.
+        #[derive(Clone, Default)]
+        struct SpanFields(Arc<Mutex<BTreeMap<&'static str, BTreeMap<String, String>>>>);
+
+        struct FieldsVisitor<'a>(&'a mut BTreeMap<String, String>);
+
+        impl Visit for FieldsVisitor<'_> {
+            fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+                self.0.insert(field.name().to_owned(), format!("{value:?}"));
+            }
+        }
+
+        impl<S> Layer<S> for SpanFields
+        where
+            S: Subscriber + for<'a> LookupSpan<'a>,
+        {
+            fn on_new_span(&self, attrs: &Attributes<'_>, _id: &Id, _ctx: Context<'_, S>) {
+                let mut spans = self.0.lock().unwrap();
+                let fields = spans.entry(attrs.metadata().name()).or_default();
+                attrs.record(&mut FieldsVisitor(fields));
+            }
+
+            fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+                let Some(span) = ctx.span(id) else { return };
+                let mut spans = self.0.lock().unwrap();
+                let fields = spans.entry(span.name()).or_default();
+                values.record(&mut FieldsVisitor(fields));
+            }
+        }
+
+        let span_fields = SpanFields::default();
+        let _guard = tracing::subscriber::set_default(
+            tracing_subscriber::registry().with(span_fields.clone()),
+        );
+
+        let server = MatrixMockServer::new().await;
+        let client = server.client_builder().build().await;
+
+        let room_id = room_id!("!galette:saucisse.bzh");
+        let room = server.sync_joined_room(&client, room_id).await;
+
+        let event: Raw<OriginalSyncRoomEncryptedEvent> = serde_json::from_value(json!({
+            "event_id": "$JbFHtZpEJiH8uaajZjPLz0QUZc1xtBR9rPGBOjF6WFM",
+            "origin_server_ts": 1698579035927u64,
+            "sender": "@example2:morpheus.localhost",
+            "type": "m.room.encrypted",
+            "content": {
+                "algorithm": "m.megolm.v1.aes-sha2",
+                "ciphertext": "AwgAEpABhetEzzZzyYrxtEVUtlJnZtJcURBlQUQJ9irVeklCTs06LwgTMQj61PMUS4Vy",
+                "device_id": "KIUVQQSDTM",
+                "sender_key": "LvryVyoCjdONdBCi2vvoSbI34yTOx7YrCFACUEKoXnc",
+                "session_id": "64H7XKokIx0ASkYDHZKlT5zd/Zccz/cQspPNdvnNULA"
+            }
+        }))
+        .unwrap();
+
+        // We don't have the room key, so the decryption fails, but it's traced anyways.
+        let event = room.decrypt_event(&event, None).await.unwrap();
+        assert!(event.encryption_info().is_none());
+
+        let spans = span_fields.0.lock().unwrap();
+        let fields = &spans["decrypt_event"];
+        assert_eq!(fields["room_id"], room_id.as_str());
+        assert!(fields["event_id"].contains("$JbFHtZpEJiH8uaajZjPLz0QUZc1xtBR9rPGBOjF6WFM"));
+        assert!(fields["session_id"].contains("64H7XKokIx0ASkYDHZKlT5zd/Zccz/cQspPNdvnNULA"));
+    }
 }
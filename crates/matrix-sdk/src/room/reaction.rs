@@ -0,0 +1,123 @@
+// Copyright 2025 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Facilities to react to existing events.
+
+use ruma::{
+    events::{
+        reaction::ReactionEventContent,
+        relation::{Annotation, RelationType},
+        AnySyncMessageLikeEvent, AnySyncTimelineEvent, SyncMessageLikeEvent,
+    },
+    EventId, OwnedEventId,
+};
+use thiserror::Error;
+use tracing::instrument;
+
+use super::{IncludeRelations, RelationsOptions, Room};
+use crate::Result;
+
+/// An error occurring while reacting to an event.
+#[derive(Debug, Error)]
+pub enum ReactionError {
+    /// The current user already reacted to the event with the same key.
+    #[error("you already reacted to this event with this key")]
+    AlreadyReacted,
+
+    /// The current user didn't react to the event with the given key.
+    #[error("you didn't react to this event with this key")]
+    NotFound,
+}
+
+impl Room {
+    /// Send a reaction to the target event, with the given key.
+    ///
+    /// The reaction is sent immediately, without a local echo; use
+    /// [`crate::send_queue::SendHandle::react`] to get one.
+    ///
+    /// Returns [`ReactionError::AlreadyReacted`] if the current user already
+    /// reacted to the target event with the same key.
+    ///
+    /// Returns the event ID of the reaction event.
+    #[instrument(skip(self), fields(room = %self.room_id()))]
+    pub async fn send_reaction(&self, target: &EventId, key: &str) -> Result<OwnedEventId> {
+        if self.find_own_reaction(target, key).await?.is_some() {
+            return Err(ReactionError::AlreadyReacted.into());
+        }
+
+        let content = ReactionEventContent::new(Annotation::new(target.to_owned(), key.to_owned()));
+
+        Ok(self.send(content).await?.event_id)
+    }
+
+    /// Redact the current user's reaction to the target event, with the given
+    /// key.
+    ///
+    /// Returns [`ReactionError::NotFound`] if the current user didn't react to
+    /// the target event with this key.
+    #[instrument(skip(self), fields(room = %self.room_id()))]
+    pub async fn redact_reaction(&self, target: &EventId, key: &str) -> Result<()> {
+        let reaction_event_id =
+            self.find_own_reaction(target, key).await?.ok_or(ReactionError::NotFound)?;
+
+        self.redact(&reaction_event_id, None, None).await?;
+
+        Ok(())
+    }
+
+    /// Find the event ID of the current user's reaction to the target event,
+    /// with the given key, by paginating its annotations.
+    ///
+    /// The annotations aren't filtered by event type on the server side,
+    /// since reactions are sent as `m.room.encrypted` events in encrypted
+    /// rooms; they're decrypted and filtered here instead.
+    async fn find_own_reaction(&self, target: &EventId, key: &str) -> Result<Option<OwnedEventId>> {
+        let own_user_id = self.own_user_id();
+        let mut from = None;
+
+        loop {
+            let relations = self
+                .relations(
+                    target.to_owned(),
+                    RelationsOptions {
+                        from,
+                        include_relations: IncludeRelations::RelationsOfType(
+                            RelationType::Annotation,
+                        ),
+                        ..Default::default()
+                    },
+                )
+                .await?;
+
+            // The events of the chunk have already been decrypted, when possible.
+            for event in relations.chunk {
+                let Ok(AnySyncTimelineEvent::MessageLike(AnySyncMessageLikeEvent::Reaction(
+                    SyncMessageLikeEvent::Original(reaction),
+                ))) = event.raw().deserialize()
+                else {
+                    continue;
+                };
+
+                if reaction.sender == own_user_id && reaction.content.relates_to.key == key {
+                    return Ok(Some(reaction.event_id));
+                }
+            }
+
+            match relations.next_batch_token {
+                Some(token) => from = Some(token),
+                None => return Ok(None),
+            }
+        }
+    }
+}
@@ -116,6 +116,48 @@ impl Room {
     ) -> Result<RoomMessageEventContent, ReplyError> {
         make_reply_event(self, self.room_id(), self.own_user_id(), content, reply).await
     }
+
+    /// Send a reply to the target event id with the specified content.
+    ///
+    /// The reply relation is created with [`Room::make_reply_event`]. No rich
+    /// reply fallback is added to the content, as they have been removed from
+    /// the specification in Matrix 1.13.
+    ///
+    /// Returns the event ID of the sent reply.
+    ///
+    /// # Arguments
+    ///
+    /// * `content` - The content to reply with
+    /// * `replied_to` - ID of the event to reply to
+    /// * `thread` - ID of the thread root, if the reply must be sent in a
+    ///   thread
+    #[instrument(skip(self, content), fields(room = %self.room_id()))]
+    pub async fn send_reply(
+        &self,
+        content: RoomMessageEventContentWithoutRelation,
+        replied_to: &EventId,
+        thread: Option<&EventId>,
+    ) -> crate::Result<OwnedEventId> {
+        let enforce_thread = if thread.is_some() {
+            EnforceThread::Threaded(ReplyWithinThread::Yes)
+        } else {
+            EnforceThread::Unthreaded
+        };
+
+        let mut content = self
+            .make_reply_event(content, Reply { event_id: replied_to.to_owned(), enforce_thread })
+            .await?;
+
+        // The thread root of the replied-to event is used by default, make sure it's
+        // the one that was asked for.
+        if let (Some(thread_root), Some(Relation::Thread(thread))) =
+            (thread, content.relates_to.as_mut())
+        {
+            thread.event_id = thread_root.to_owned();
+        }
+
+        Ok(self.send(content).await?.event_id)
+    }
 }
 
 async fn make_reply_event<S: EventSource>(
@@ -0,0 +1,139 @@
+// Copyright 2025 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Server-side search of messages, using the `/search` endpoint.
+
+use std::collections::BTreeMap;
+
+use matrix_sdk_base::deserialized_responses::TimelineEvent;
+use ruma::{
+    api::client::{
+        filter::RoomEventFilter,
+        search::search_events::v3::{Categories, Criteria, Request},
+    },
+    assign, OwnedRoomId, UInt,
+};
+use tracing::{instrument, warn};
+
+use crate::{room::PushContext, Client, Result};
+
+/// The parameters of a [`Client::search_messages`] query.
+#[derive(Clone, Debug)]
+pub struct SearchRequest {
+    /// The term to search for in the messages.
+    pub search_term: String,
+
+    /// The rooms to search into.
+    ///
+    /// If `None`, all the rooms the user is in are searched.
+    pub rooms: Option<Vec<OwnedRoomId>>,
+
+    /// The maximum number of results to return.
+    pub limit: Option<UInt>,
+
+    /// The token to continue a previous search, as returned in
+    /// [`SearchResults::next_batch`].
+    pub next_batch: Option<String>,
+}
+
+impl SearchRequest {
+    /// Create a new `SearchRequest` for the given search term, in all the
+    /// rooms.
+    pub fn new(search_term: impl Into<String>) -> Self {
+        Self { search_term: search_term.into(), rooms: None, limit: None, next_batch: None }
+    }
+}
+
+/// The result of a [`Client::search_messages`] query.
+#[derive(Debug)]
+pub struct SearchResults {
+    /// An approximation of the total number of results, if the server
+    /// provided it.
+    pub count: Option<UInt>,
+
+    /// The matching events, decrypted if needs be.
+    pub results: Vec<SearchResult>,
+
+    /// The token to get the next batch of results, if there are more.
+    pub next_batch: Option<String>,
+}
+
+/// A single result of a [`Client::search_messages`] query.
+#[derive(Debug)]
+pub struct SearchResult {
+    /// The score of the result, as computed by the server.
+    pub rank: Option<f64>,
+
+    /// The matching event.
+    pub event: TimelineEvent,
+}
+
+impl Client {
+    /// Search for messages containing a term, using the homeserver's
+    /// full-text search.
+    ///
+    /// Note that homeservers can't search into encrypted events; only the
+    /// encrypted events matching the search on their unencrypted fields are
+    /// returned, and they're decrypted if possible.
+    #[instrument(skip_all, fields(rooms = ?request.rooms, limit = ?request.limit))]
+    pub async fn search_messages(&self, request: SearchRequest) -> Result<SearchResults> {
+        let filter = assign!(RoomEventFilter::default(), {
+            rooms: request.rooms,
+            limit: request.limit,
+        });
+        let criteria = assign!(Criteria::new(request.search_term), { filter });
+        let request = assign!(Request::new(assign!(Categories::new(), {
+            room_events: Some(criteria),
+        })), {
+            next_batch: request.next_batch,
+        });
+
+        let response = self.send(request).await?.search_categories.room_events;
+
+        // Cache the push contexts, to avoid computing them for each result in the same
+        // room.
+        let mut push_contexts: BTreeMap<OwnedRoomId, Option<PushContext>> = BTreeMap::new();
+        let mut results = Vec::with_capacity(response.results.len());
+
+        for result in response.results {
+            let Some(raw_event) = result.result else {
+                continue;
+            };
+
+            let room = raw_event
+                .get_field::<OwnedRoomId>("room_id")
+                .ok()
+                .flatten()
+                .and_then(|room_id| self.get_room(&room_id));
+
+            let event = if let Some(room) = room {
+                if !push_contexts.contains_key(room.room_id()) {
+                    let push_ctx = room.push_context().await.unwrap_or_else(|error| {
+                        warn!(?error, "Couldn't compute the push context");
+                        None
+                    });
+                    push_contexts.insert(room.room_id().to_owned(), push_ctx);
+                }
+
+                room.try_decrypt_event(raw_event, push_contexts[room.room_id()].as_ref()).await
+            } else {
+                TimelineEvent::from_plaintext(raw_event.cast())
+            };
+
+            results.push(SearchResult { rank: result.rank, event });
+        }
+
+        Ok(SearchResults { count: response.count, results, next_batch: response.next_batch })
+    }
+}
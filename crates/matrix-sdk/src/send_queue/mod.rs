@@ -509,6 +509,67 @@ impl RoomSendQueue {
         Ok((local_echoes, self.inner.updates.subscribe()))
     }
 
+    /// Find the [`SendHandle`] of the local echo with the given transaction
+    /// ID, if it's still in the send queue.
+    async fn find_send_handle(
+        &self,
+        transaction_id: &TransactionId,
+    ) -> Result<Option<SendHandle>, RoomSendQueueStorageError> {
+        let local_echoes = self.inner.queue.local_echoes(self).await?;
+
+        Ok(local_echoes.into_iter().find_map(|echo| match echo.content {
+            LocalEchoContent::Event { send_handle, .. }
+                if echo.transaction_id == transaction_id =>
+            {
+                Some(send_handle)
+            }
+            _ => None,
+        }))
+    }
+
+    /// Cancel the sending of the queued (or failed) event with the given
+    /// transaction ID, and remove its local echo.
+    ///
+    /// This is the same as calling [`SendHandle::abort`] on the handle of the
+    /// local echo, for callers that only kept the transaction ID around (e.g.
+    /// after a restart).
+    ///
+    /// Returns true if the event was cancelled, false if it couldn't be found
+    /// in the send queue or if it was already being sent.
+    #[instrument(skip(self))]
+    pub async fn cancel_send(
+        &self,
+        transaction_id: &TransactionId,
+    ) -> Result<bool, RoomSendQueueStorageError> {
+        match self.find_send_handle(transaction_id).await? {
+            Some(handle) => handle.abort().await,
+            None => Ok(false),
+        }
+    }
+
+    /// Retry sending the event with the given transaction ID, after it failed
+    /// with an unrecoverable error.
+    ///
+    /// This is the same as calling [`SendHandle::unwedge`] on the handle of
+    /// the local echo. The request will be sent again with the same
+    /// transaction ID, so the homeserver can deduplicate it.
+    ///
+    /// Returns true if the event was found in the send queue, false
+    /// otherwise.
+    #[instrument(skip(self))]
+    pub async fn retry_send(
+        &self,
+        transaction_id: &TransactionId,
+    ) -> Result<bool, RoomSendQueueError> {
+        match self.find_send_handle(transaction_id).await? {
+            Some(handle) => {
+                handle.unwedge().await?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
     /// A task that must be spawned in the async runtime, running in the
     /// background for each room that has a send queue.
     ///
@@ -1,5 +1,5 @@
 use std::{
-    collections::BTreeMap,
+    collections::{btree_map::Entry, BTreeMap},
     fmt::Debug,
     sync::{Arc, RwLock as StdRwLock},
     time::Duration,
@@ -67,7 +67,9 @@ impl SlidingSyncBuilder {
 
     /// Add the given list to the lists.
     ///
-    /// Replace any list with the same name.
+    /// As lists need to have a unique `.name`, [`Self::build`] returns an
+    /// [`Error::DuplicateListName`] error if several lists with the same name
+    /// have been added, like [`SlidingSync::add_list`] does.
     pub fn add_list(mut self, list_builder: SlidingSyncListBuilder) -> Self {
         self.lists.push(list_builder);
         self
@@ -79,7 +81,7 @@ impl SlidingSyncBuilder {
     /// This will raise an error if there was a I/O error reading from the
     /// cache.
     ///
-    /// Replace any list with the same name.
+    /// The rest of the semantics is the same as [`Self::add_list`].
     pub async fn add_cached_list(self, mut list: SlidingSyncListBuilder) -> Result<Self> {
         let _timer = timer!(format!("restoring (loading+processing) list {}", list.name));
 
@@ -222,6 +224,9 @@ impl SlidingSyncBuilder {
     }
 
     /// Build the Sliding Sync.
+    ///
+    /// Returns an [`Error::DuplicateListName`] error if several lists with the
+    /// same name have been added.
     #[allow(clippy::unused_async)] // Async is only used if the e2e-encryption feature is enabled.
     pub async fn build(self) -> Result<SlidingSync> {
         let client = self.client;
@@ -239,7 +244,15 @@ impl SlidingSyncBuilder {
         for list_builder in self.lists {
             let list = list_builder.build(internal_channel_sender.clone());
 
-            lists.insert(list.name().to_owned(), list);
+            match lists.entry(list.name().to_owned()) {
+                Entry::Occupied(entry) => {
+                    return Err(Error::DuplicateListName(entry.key().clone()).into());
+                }
+
+                Entry::Vacant(entry) => {
+                    entry.insert(list);
+                }
+            }
         }
 
         let (share_pos, pos) = {
@@ -40,6 +40,10 @@ pub enum Error {
     #[error("Unauthenticated user in sliding sync")]
     UnauthenticatedUser,
 
+    /// A list with the same name already exists.
+    #[error("A list named `{0}` already exists")]
+    DuplicateListName(String),
+
     /// The internal channel of `SlidingSync` seems to be broken.
     #[error("SlidingSync's internal channel is broken")]
     InternalChannelIsBroken,
@@ -88,6 +88,18 @@ impl SlidingSyncList {
         );
     }
 
+    /// Change the batch size of the [`SlidingSyncMode::Growing`] sync-mode.
+    ///
+    /// Contrary to [`Self::set_sync_mode`], the list isn't “reset”: its range
+    /// keeps growing from the rooms which have already been fetched, by
+    /// `batch_size` rooms per request.
+    ///
+    /// Returns `false`, and does nothing, if the list isn't in the growing
+    /// sync-mode.
+    pub fn set_growing_batch_size(&self, batch_size: u32) -> bool {
+        self.inner.set_growing_batch_size(batch_size)
+    }
+
     /// Get the current state.
     pub fn state(&self) -> SlidingSyncListLoadingState {
         self.inner.state.read().unwrap().clone()
@@ -290,6 +302,26 @@ impl SlidingSyncListInner {
         }
     }
 
+    /// Change the batch size of the growing sync-mode, without changing the
+    /// request generator.
+    ///
+    /// Returns `false` if the list isn't in the growing sync-mode.
+    pub fn set_growing_batch_size(&self, new_batch_size: u32) -> bool {
+        let changed =
+            self.request_generator.write().unwrap().set_growing_batch_size(new_batch_size);
+
+        #[cfg(any(test, feature = "testing"))]
+        {
+            if let SlidingSyncMode::Growing { batch_size, .. } =
+                &mut *self.sync_mode.write().unwrap()
+            {
+                *batch_size = new_batch_size;
+            }
+        }
+
+        changed
+    }
+
     /// Update the state to the next request, and return it.
     fn next_request(&self, txn_id: &mut LazyTransactionId) -> Result<http::request::List, Error> {
         let ranges = {
@@ -801,6 +833,54 @@ mod tests {
         };
     }
 
+    #[test]
+    fn test_generator_growing_with_a_new_batch_size() {
+        let (sender, _receiver) = channel(1);
+
+        let mut list = SlidingSyncList::builder("testing")
+            .sync_mode(SlidingSyncMode::new_growing(10))
+            .build(sender);
+
+        assert_ranges! {
+            list = list,
+            list_state = NotLoaded,
+            maximum_number_of_rooms = 50,
+            requires_timeout = false,
+            next => {
+                ranges = 0..=9,
+                is_fully_loaded = false,
+                list_state = PartiallyLoaded,
+                requires_timeout = false,
+            },
+        };
+
+        // The range keeps growing from the fetched rooms, with the new batch size.
+        assert!(list.set_growing_batch_size(20));
+
+        assert_ranges! {
+            list = list,
+            list_state = PartiallyLoaded,
+            maximum_number_of_rooms = 50,
+            requires_timeout = false,
+            next => {
+                ranges = 0..=29,
+                is_fully_loaded = false,
+                list_state = PartiallyLoaded,
+                requires_timeout = false,
+            },
+            next => {
+                ranges = 0..=49,
+                is_fully_loaded = true,
+                list_state = FullyLoaded,
+                requires_timeout = true,
+            },
+        };
+
+        // It's not possible in another sync-mode.
+        list.set_sync_mode(SlidingSyncMode::new_selective().add_range(0..=9));
+        assert!(!list.set_growing_batch_size(30));
+    }
+
     #[test]
     fn test_generator_growing_full_sync_with_a_maximum_number_of_rooms_to_fetch() {
         let (sender, _receiver) = channel(1);
@@ -120,6 +120,22 @@ impl SlidingSyncListRequestGenerator {
         matches!(self.kind, SlidingSyncListRequestGeneratorKind::Selective)
     }
 
+    /// Change the batch size, if this request generator is of kind
+    /// [`SlidingSyncListRequestGeneratorKind::Growing`].
+    ///
+    /// Returns whether the batch size has been changed.
+    pub(super) fn set_growing_batch_size(&mut self, new_batch_size: u32) -> bool {
+        match &mut self.kind {
+            SlidingSyncListRequestGeneratorKind::Growing { batch_size, .. } => {
+                *batch_size = new_batch_size;
+                true
+            }
+
+            SlidingSyncListRequestGeneratorKind::Paging { .. }
+            | SlidingSyncListRequestGeneratorKind::Selective => false,
+        }
+    }
+
     /// Return a view on the ranges requested by this generator.
     ///
     /// For generators in the selective mode, this is the initial set of ranges.
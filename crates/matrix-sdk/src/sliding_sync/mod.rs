@@ -202,9 +202,35 @@ impl SlidingSync {
     /// Add the list to the list of lists.
     ///
     /// As lists need to have a unique `.name`, if a list with the same name
+    /// already exists, the new list is not added and
+    /// [`Error::DuplicateListName`] is returned. Use [`Self::try_add_list`] to
+    /// replace the existing list instead.
+    pub async fn add_list(&self, list_builder: SlidingSyncListBuilder) -> Result<()> {
+        let list = list_builder.build(self.inner.internal_channel.clone());
+
+        match self.inner.lists.write().await.entry(list.name().to_owned()) {
+            Entry::Occupied(entry) => {
+                return Err(Error::DuplicateListName(entry.key().clone()).into());
+            }
+
+            Entry::Vacant(entry) => {
+                entry.insert(list);
+            }
+        }
+
+        self.inner.internal_channel_send_if_possible(
+            SlidingSyncInternalMessage::SyncLoopSkipOverCurrentIteration,
+        );
+
+        Ok(())
+    }
+
+    /// Add the list to the list of lists, or replace the existing one.
+    ///
+    /// As lists need to have a unique `.name`, if a list with the same name
     /// is found the new list will replace the old one and the return it or
     /// `None`.
-    pub async fn add_list(
+    pub async fn try_add_list(
         &self,
         list_builder: SlidingSyncListBuilder,
     ) -> Result<Option<SlidingSyncList>> {
@@ -225,10 +251,7 @@ impl SlidingSync {
     /// was a I/O error reading from the cache.
     ///
     /// The rest of the semantics is the same as [`Self::add_list`].
-    pub async fn add_cached_list(
-        &self,
-        mut list_builder: SlidingSyncListBuilder,
-    ) -> Result<Option<SlidingSyncList>> {
+    pub async fn add_cached_list(&self, mut list_builder: SlidingSyncListBuilder) -> Result<()> {
         let _timer = timer!(format!("restoring (loading+processing) list {}", list_builder.name));
 
         list_builder.set_cached_and_reload(&self.inner.client, &self.inner.storage_key).await?;
@@ -237,7 +260,7 @@ impl SlidingSync {
     }
 
     /// Handle the HTTP response.
-    #[instrument(skip_all)]
+    #[instrument(skip_all, fields(pos = %sliding_sync_response.pos))]
     async fn handle_response(
         &self,
         sliding_sync_response: http::Response,
@@ -935,7 +958,7 @@ mod tests {
     use super::{
         http,
         sticky_parameters::{LazyTransactionId, SlidingSyncStickyManager},
-        SlidingSync, SlidingSyncList, SlidingSyncListBuilder, SlidingSyncMode,
+        Error, SlidingSync, SlidingSyncList, SlidingSyncListBuilder, SlidingSyncMode,
         SlidingSyncStickyParameters,
     };
     use crate::{
@@ -1176,6 +1199,57 @@ mod tests {
         Ok(())
     }
 
+    #[async_test]
+    async fn test_add_list_with_duplicate_name() -> Result<()> {
+        let (_server, sliding_sync) = new_sliding_sync(vec![SlidingSyncList::builder("foo")
+            .sync_mode(SlidingSyncMode::new_selective().add_range(0..=10))])
+        .await?;
+
+        sliding_sync.add_list(SlidingSyncList::builder("bar").timeline_limit(1)).await?;
+
+        // Adding a list with the same name is refused.
+        assert_matches!(
+            sliding_sync.add_list(SlidingSyncList::builder("bar").timeline_limit(2)).await,
+            Err(crate::Error::SlidingSync(error)) => {
+                assert_matches!(*error, Error::DuplicateListName(name) => {
+                    assert_eq!(name, "bar");
+                });
+            }
+        );
+
+        // The existing list has not been replaced…
+        assert_eq!(sliding_sync.on_list("bar", |list| ready(list.timeline_limit())).await, Some(1));
+
+        // … unless `try_add_list` is used.
+        let old_list =
+            sliding_sync.try_add_list(SlidingSyncList::builder("bar").timeline_limit(2)).await?;
+
+        assert_eq!(old_list.map(|list| list.timeline_limit()), Some(1));
+        assert_eq!(sliding_sync.on_list("bar", |list| ready(list.timeline_limit())).await, Some(2));
+
+        Ok(())
+    }
+
+    #[async_test]
+    async fn test_builder_add_list_with_duplicate_name() -> Result<()> {
+        // Building a sliding sync with two lists with the same name is refused too.
+        assert_matches!(
+            new_sliding_sync(vec![
+                SlidingSyncList::builder("foo").timeline_limit(1),
+                SlidingSyncList::builder("foo").timeline_limit(2),
+            ])
+            .await
+            .map(|_| ()),
+            Err(crate::Error::SlidingSync(error)) => {
+                assert_matches!(*error, Error::DuplicateListName(name) => {
+                    assert_eq!(name, "foo");
+                });
+            }
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn test_sticky_parameters_api_invalidated_flow() {
         let r0 = room_id!("!r0.matrix.org");
@@ -107,6 +107,13 @@ impl MockClientBuilder {
         self
     }
 
+    /// Use the given [`RequestConfig`] instead of the default one, which
+    /// disables retries.
+    pub fn request_config(mut self, request_config: RequestConfig) -> Self {
+        self.builder = self.builder.request_config(request_config);
+        self
+    }
+
     /// Finish building the client into the final [`Client`] instance.
     pub async fn build(self) -> Client {
         let client = self.builder.build().await.expect("building client failed");
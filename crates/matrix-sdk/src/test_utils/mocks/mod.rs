@@ -40,8 +40,8 @@ use ruma::{
     },
     serde::Raw,
     time::Duration,
-    DeviceId, MxcUri, OwnedDeviceId, OwnedEventId, OwnedOneTimeKeyId, OwnedRoomId, OwnedUserId,
-    RoomId, ServerName, UserId,
+    user_id, DeviceId, MxcUri, OwnedDeviceId, OwnedEventId, OwnedOneTimeKeyId, OwnedRoomId,
+    OwnedUserId, RoomId, ServerName, UserId,
 };
 use serde::Deserialize;
 use serde_json::{json, Value};
@@ -1087,6 +1087,13 @@ impl MatrixMockServer {
         self.mock_endpoint(mock, UploadCrossSigningSignaturesEndpoint).expect_default_access_token()
     }
 
+    /// Creates a prebuilt mock for the endpoint used to join a room.
+    pub fn mock_room_join(&self) -> MockEndpoint<'_, RoomJoinEndpoint> {
+        let mock =
+            Mock::given(method("POST")).and(path_regex(r"^/_matrix/client/v3/rooms/.*/join"));
+        self.mock_endpoint(mock, RoomJoinEndpoint).expect_default_access_token()
+    }
+
     /// Creates a prebuilt mock for the endpoint used to leave a room.
     pub fn mock_room_leave(&self) -> MockEndpoint<'_, RoomLeaveEndpoint> {
         let mock =
@@ -1122,6 +1129,12 @@ impl MatrixMockServer {
         self.mock_endpoint(mock, RoomRelationsEndpoint::default()).expect_default_access_token()
     }
 
+    /// Create a prebuilt mock for the endpoint used to search for messages.
+    pub fn mock_search(&self) -> MockEndpoint<'_, SearchEndpoint> {
+        let mock = Mock::given(method("POST")).and(path("/_matrix/client/v3/search"));
+        self.mock_endpoint(mock, SearchEndpoint).expect_default_access_token()
+    }
+
     /// Create a prebuilt mock for the endpoint used to get the global account
     /// data.
     ///
@@ -2676,8 +2689,17 @@ impl<'a> MockEndpoint<'a, WhoAmIEndpoint> {
 
     /// Returns a successful response with the given device ID.
     pub fn ok_with_device_id(self, device_id: &DeviceId) -> MatrixMock<'a> {
+        self.ok_with_user_and_device_id(user_id!("@joe:example.org"), device_id)
+    }
+
+    /// Returns a successful response with the given user ID and device ID.
+    pub fn ok_with_user_and_device_id(
+        self,
+        user_id: &UserId,
+        device_id: &DeviceId,
+    ) -> MatrixMock<'a> {
         self.respond_with(ResponseTemplate::new(200).set_body_json(json!({
-            "user_id": "@joe:example.org",
+            "user_id": user_id,
             "device_id": device_id,
         })))
     }
@@ -2806,6 +2828,22 @@ impl<'a> MockEndpoint<'a, UploadCrossSigningSignaturesEndpoint> {
 pub struct RoomLeaveEndpoint;
 
 impl<'a> MockEndpoint<'a, RoomLeaveEndpoint> {
+    /// Sets up the endpoint to only intercept requests for the given room.
+    pub fn for_room(self, room_id: &RoomId) -> Self {
+        Self {
+            mock: self.mock.and(path_regex(format!(
+                r"^/_matrix/client/v3/rooms/{}/leave",
+                percent_encoded_path(room_id.as_str())
+            ))),
+            ..self
+        }
+    }
+
+    /// Ensures that the leave request contains the given reason.
+    pub fn match_reason(self, reason: &str) -> Self {
+        Self { mock: self.mock.and(body_partial_json(json!({ "reason": reason }))), ..self }
+    }
+
     /// Returns a successful response with some default data for the given room
     /// id.
     pub fn ok(self, room_id: &RoomId) -> MatrixMock<'a> {
@@ -2823,6 +2861,19 @@ impl<'a> MockEndpoint<'a, RoomLeaveEndpoint> {
     }
 }
 
+/// A prebuilt mock for the room join endpoint.
+pub struct RoomJoinEndpoint;
+
+impl<'a> MockEndpoint<'a, RoomJoinEndpoint> {
+    /// Returns a successful response with some default data for the given room
+    /// id.
+    pub fn ok(self, room_id: &RoomId) -> MatrixMock<'a> {
+        self.respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "room_id": room_id,
+        })))
+    }
+}
+
 /// A prebuilt mock for the room forget endpoint.
 pub struct RoomForgetEndpoint;
 
@@ -2867,6 +2918,45 @@ impl<'a> MockEndpoint<'a, RoomThreadsEndpoint> {
     }
 }
 
+/// A prebuilt mock for a `POST /search` request.
+pub struct SearchEndpoint;
+
+impl<'a> MockEndpoint<'a, SearchEndpoint> {
+    /// Expects the request to search for the given term.
+    pub fn match_search_term(self, search_term: &str) -> Self {
+        Self {
+            mock: self.mock.and(body_partial_json(json!({
+                "search_categories": {
+                    "room_events": {
+                        "search_term": search_term,
+                    },
+                },
+            }))),
+            ..self
+        }
+    }
+
+    /// Returns a successful response with the given matching events and
+    /// pagination token.
+    pub fn ok(
+        self,
+        results: Vec<Raw<AnyTimelineEvent>>,
+        next_batch: Option<&str>,
+    ) -> MatrixMock<'a> {
+        let results: Vec<_> = results.into_iter().map(|event| json!({ "result": event })).collect();
+
+        self.respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "search_categories": {
+                "room_events": {
+                    "count": results.len(),
+                    "results": results,
+                    "next_batch": next_batch,
+                },
+            },
+        })))
+    }
+}
+
 /// A prebuilt mock for a `GET /rooms/{roomId}/relations/{eventId}` family of
 /// requests.
 #[derive(Default)]
@@ -6,17 +6,20 @@ use futures_util::FutureExt;
 use matrix_sdk::{
     authentication::oauth::{error::OAuthTokenRevocationError, OAuthError},
     config::{RequestConfig, StoreConfig, SyncSettings},
+    notification_settings::{NotificationAction, RoomNotificationMode},
     store::RoomLoadSettings,
     sync::RoomUpdate,
     test_utils::{
         client::mock_matrix_session, mocks::MatrixMockServer, no_retry_test_client_with_server,
     },
-    Client, Error, MemoryStore, StateChanges, StateStore,
+    Client, Error, MemoryStore, StateChanges, StateStore, WeakClient,
 };
 use matrix_sdk_base::{sync::RoomUpdates, RoomState};
 use matrix_sdk_common::executor::spawn;
 use matrix_sdk_test::{
-    async_test, sync_state_event,
+    async_test,
+    event_factory::EventFactory,
+    sync_state_event,
     test_json::{
         self,
         sync::{
@@ -29,12 +32,16 @@ use matrix_sdk_test::{
     GlobalAccountDataTestEvent, JoinedRoomBuilder, SyncResponseBuilder, DEFAULT_TEST_ROOM_ID,
 };
 use ruma::{
-    api::client::{
-        directory::{
-            get_public_rooms,
-            get_public_rooms_filtered::{self, v3::Request as PublicRoomsFilterRequest},
+    api::{
+        client::{
+            directory::{
+                get_public_rooms,
+                get_public_rooms_filtered::{self, v3::Request as PublicRoomsFilterRequest},
+            },
+            space::get_hierarchy,
+            uiaa,
         },
-        uiaa,
+        MatrixVersion,
     },
     assign, device_id,
     directory::Filter,
@@ -43,15 +50,17 @@ use ruma::{
         direct::{DirectEventContent, OwnedDirectUserIdentifier},
         AnyInitialStateEvent,
     },
+    owned_server_name,
+    room::RoomType,
     room_id,
     serde::Raw,
-    user_id, OwnedUserId,
+    uint, user_id, OwnedUserId,
 };
 use serde_json::{json, Value as JsonValue};
 use stream_assert::{assert_next_matches, assert_pending};
 use tokio_stream::wrappers::BroadcastStream;
 use wiremock::{
-    matchers::{header, method, path, path_regex},
+    matchers::{body_partial_json, header, method, path, path_regex, query_param},
     Mock, Request, ResponseTemplate,
 };
 
@@ -314,6 +323,246 @@ async fn test_whoami() {
     assert_eq!(client.whoami().await.unwrap().user_id, user_id);
 }
 
+#[async_test]
+async fn test_health_check_reachable_and_authenticated() {
+    let server = MatrixMockServer::new().await;
+    let client = server.client_builder().build().await;
+
+    server.mock_versions().ok().mock_once().mount().await;
+    server.mock_who_am_i().ok().mock_once().mount().await;
+
+    let status = client.health_check().await;
+    assert!(status.reachable);
+    assert!(status.authenticated);
+    assert!(status.server_versions.contains(&MatrixVersion::V1_0));
+}
+
+#[async_test]
+async fn test_health_check_reachable_and_unauthenticated() {
+    let server = MatrixMockServer::new().await;
+    let client = server.client_builder().build().await;
+
+    server.mock_versions().ok().mock_once().mount().await;
+    server.mock_who_am_i().err_unknown_token().mock_once().mount().await;
+
+    let status = client.health_check().await;
+    assert!(status.reachable);
+    assert!(!status.authenticated);
+    assert!(!status.server_versions.is_empty());
+}
+
+#[async_test]
+async fn test_health_check_whoami_server_error() {
+    let server = MatrixMockServer::new().await;
+    let client = server.client_builder().build().await;
+
+    server.mock_versions().ok().mock_once().mount().await;
+    server.mock_who_am_i().error500().mock_once().mount().await;
+
+    // An error that isn't about the access token doesn't mean that the client
+    // isn't authenticated anymore.
+    let status = client.health_check().await;
+    assert!(!status.reachable);
+    assert!(!status.authenticated);
+    assert!(!status.server_versions.is_empty());
+}
+
+#[async_test]
+async fn test_health_check_unreachable() {
+    let server = MatrixMockServer::new().await;
+    let client = server.client_builder().build().await;
+
+    server.mock_versions().error500().mock_once().mount().await;
+    server.mock_who_am_i().ok().never().mount().await;
+
+    let status = client.health_check().await;
+    assert!(!status.reachable);
+    assert!(!status.authenticated);
+    assert!(status.server_versions.is_empty());
+}
+
+#[async_test]
+async fn test_space_hierarchy() {
+    let (client, server) = logged_in_client_with_server().await;
+    let space_id = room_id!("!space:localhost");
+
+    Mock::given(method("GET"))
+        .and(path_regex(r"^/_matrix/client/v1/rooms/.*/hierarchy"))
+        .and(query_param("from", "prev_token"))
+        .and(query_param("max_depth", "2"))
+        .and(query_param("suggested_only", "true"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "next_batch": "next_token",
+            "rooms": [
+                {
+                    "room_id": space_id,
+                    "name": "The space",
+                    "num_joined_members": 5,
+                    "world_readable": false,
+                    "guest_can_join": false,
+                    "join_rule": "public",
+                    "room_type": "m.space",
+                    "children_state": [
+                        {
+                            "type": "m.space.child",
+                            "state_key": "!room:localhost",
+                            "sender": "@example:localhost",
+                            "origin_server_ts": 1432735824653u64,
+                            "content": {
+                                "via": ["localhost"],
+                                "suggested": true,
+                            },
+                        },
+                    ],
+                },
+                {
+                    "room_id": "!room:localhost",
+                    "name": "A room",
+                    "topic": "A room in the space",
+                    "num_joined_members": 3,
+                    "world_readable": true,
+                    "guest_can_join": false,
+                    "join_rule": "public",
+                    "children_state": [],
+                },
+            ],
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let request = assign!(get_hierarchy::v1::Request::new(space_id.to_owned()), {
+        from: Some("prev_token".to_owned()),
+        max_depth: Some(uint!(2)),
+        suggested_only: true,
+    });
+    let response = client.space_hierarchy(request).await.unwrap();
+
+    assert_eq!(response.next_batch.as_deref(), Some("next_token"));
+    assert_eq!(response.rooms.len(), 2);
+
+    let space = &response.rooms[0];
+    assert_eq!(space.room_id, space_id);
+    assert_eq!(space.room_type, Some(RoomType::Space));
+
+    let child = space.children_state[0].deserialize().unwrap();
+    assert_eq!(child.state_key, room_id!("!room:localhost"));
+    assert!(child.content.suggested);
+    assert_eq!(child.content.via, [owned_server_name!("localhost")]);
+
+    let room = &response.rooms[1];
+    assert_eq!(room.room_id, room_id!("!room:localhost"));
+    assert_eq!(room.topic.as_deref(), Some("A room in the space"));
+    assert_eq!(room.num_joined_members, uint!(3));
+}
+
+#[async_test]
+async fn test_raw_sync_callback() {
+    use std::sync::{Arc, Mutex};
+
+    let server = MatrixMockServer::new().await;
+    let client = server.client_builder().build().await;
+    let room_id = *DEFAULT_TEST_ROOM_ID;
+
+    // Record whether the room is in the raw response, and whether the client
+    // already knows about it when the callback is called.
+    let observed = Arc::new(Mutex::new(Vec::new()));
+    client.set_raw_sync_callback({
+        let observed = observed.clone();
+        // Don't hold a strong reference to the client in a callback it owns.
+        let client = WeakClient::from_client(&client);
+        move |response| {
+            let client = client.get().expect("the client should still be alive");
+            observed.lock().unwrap().push((
+                response.rooms.join.contains_key(room_id),
+                client.get_room(room_id).is_some(),
+            ));
+        }
+    });
+
+    server
+        .mock_sync()
+        .ok_and_run(&client, |builder| {
+            builder.add_joined_room(JoinedRoomBuilder::default());
+        })
+        .await;
+
+    // The callback saw the room before the client processed the response.
+    assert_eq!(*observed.lock().unwrap(), vec![(true, false)]);
+    assert!(client.get_room(room_id).is_some());
+}
+
+#[cfg(not(target_family = "wasm"))]
+#[async_test]
+async fn test_user_agent() {
+    let server = MatrixMockServer::new().await;
+
+    // The default user agent includes the version of the SDK.
+    let client = Client::builder().homeserver_url(server.server().uri()).build().await.unwrap();
+
+    Mock::given(method("GET"))
+        .and(path("/_matrix/client/versions"))
+        .and(header("user-agent", concat!("matrix-rust-sdk/", env!("CARGO_PKG_VERSION"))))
+        .respond_with(ResponseTemplate::new(200).set_body_json(&*test_json::VERSIONS))
+        .expect(1)
+        .named("default user agent")
+        .mount(server.server())
+        .await;
+
+    client.fetch_server_versions(None).await.unwrap();
+
+    // A custom user agent can be set.
+    let client = Client::builder()
+        .homeserver_url(server.server().uri())
+        .user_agent("MyApp/1.0")
+        .build()
+        .await
+        .unwrap();
+
+    Mock::given(method("GET"))
+        .and(path("/_matrix/client/versions"))
+        .and(header("user-agent", "MyApp/1.0"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(&*test_json::VERSIONS))
+        .expect(1)
+        .named("custom user agent")
+        .mount(server.server())
+        .await;
+
+    client.fetch_server_versions(None).await.unwrap();
+}
+
+#[async_test]
+async fn test_validate_session() {
+    let server = MatrixMockServer::new().await;
+    let client = server.client_builder().build().await;
+
+    server
+        .mock_who_am_i()
+        .ok_with_user_and_device_id(client.user_id().unwrap(), client.device_id().unwrap())
+        .mock_once()
+        .mount()
+        .await;
+
+    let response = client.validate_session().await.unwrap();
+    assert_eq!(response.user_id, client.user_id().unwrap());
+}
+
+#[async_test]
+async fn test_validate_session_mismatch() {
+    let server = MatrixMockServer::new().await;
+    let client = server.client_builder().build().await;
+
+    // The access token belongs to another user.
+    server
+        .mock_who_am_i()
+        .ok_with_user_and_device_id(user_id!("@mallory:localhost"), client.device_id().unwrap())
+        .mock_once()
+        .mount()
+        .await;
+
+    assert_matches!(client.validate_session().await, Err(Error::SessionMismatch));
+}
+
 #[async_test]
 async fn test_room_update_channel() {
     let (client, server) = logged_in_client_with_server().await;
@@ -657,6 +906,77 @@ async fn test_cross_signing_status() {
     server.verify().await;
 }
 
+#[cfg(feature = "e2e-encryption")]
+#[async_test]
+async fn test_keys_query_users() {
+    use matrix_sdk_test::test_json::keys_query_sets::{
+        KeyQueryResponseTemplate, KeyQueryResponseTemplateDeviceOptions,
+    };
+    use vodozemac::{Curve25519PublicKey, Ed25519SecretKey};
+
+    let server = MatrixMockServer::new().await;
+    let client = server.client_builder().build().await;
+
+    let users: Vec<OwnedUserId> = vec![
+        user_id!("@alice:example.org").to_owned(),
+        user_id!("@bob:example.org").to_owned(),
+        user_id!("@carol:example.org").to_owned(),
+    ];
+
+    let mut device_keys = serde_json::Map::new();
+    for (i, user_id) in users.iter().enumerate() {
+        let response = KeyQueryResponseTemplate::new(user_id.clone())
+            .with_device(
+                device_id!("DEVICE"),
+                &Curve25519PublicKey::from([i as u8 + 10; 32]),
+                &Ed25519SecretKey::from_slice(&[i as u8 + 1; 32]),
+                KeyQueryResponseTemplateDeviceOptions::new(),
+            )
+            .build_response();
+        device_keys.insert(user_id.to_string(), json!(response.device_keys[user_id]));
+    }
+
+    // A single request is sent for all the users.
+    server
+        .mock_query_keys()
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "device_keys": device_keys,
+        })))
+        .expect(1)
+        .mount()
+        .await;
+
+    client.keys_query_users(&users).await.unwrap();
+
+    // The devices of all the users have been stored.
+    for user_id in &users {
+        let devices = client.encryption().get_user_devices(user_id).await.unwrap();
+        assert!(devices.get(device_id!("DEVICE")).is_some(), "missing device for {user_id}");
+    }
+}
+
+#[cfg(feature = "e2e-encryption")]
+#[async_test]
+async fn test_keys_query_users_in_chunks() {
+    let server = MatrixMockServer::new().await;
+    let client = server.client_builder().build().await;
+
+    let users: Vec<OwnedUserId> =
+        (0..251).map(|i| OwnedUserId::try_from(format!("@user{i}:example.org")).unwrap()).collect();
+
+    // The users are split in two requests.
+    server
+        .mock_query_keys()
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "device_keys": {},
+        })))
+        .expect(2)
+        .mount()
+        .await;
+
+    client.keys_query_users(&users).await.unwrap();
+}
+
 #[cfg(feature = "e2e-encryption")]
 #[async_test]
 async fn test_encrypt_room_event() {
@@ -959,8 +1279,10 @@ async fn test_test_ambiguity_changes() {
 
     let example = room.get_member_no_sync(example_id).await.unwrap().unwrap();
     assert!(!example.name_ambiguous());
+    assert_eq!(example.disambiguated_name(), example.name());
     let example_2 = room.get_member_no_sync(example_2_id).await.unwrap().unwrap();
     assert!(!example_2.name_ambiguous());
+    assert_eq!(example_2.disambiguated_name(), example_2.name());
 
     let changes = assert_next_matches!(updates, Ok(RoomUpdate::Joined { updates, .. }) => updates.ambiguity_changes);
 
@@ -1031,10 +1353,13 @@ async fn test_test_ambiguity_changes() {
 
     let example = room.get_member_no_sync(example_id).await.unwrap().unwrap();
     assert!(example.name_ambiguous());
+    assert_eq!(example.disambiguated_name(), format!("{} ({example_id})", example.name()));
     let example_2 = room.get_member_no_sync(example_2_id).await.unwrap().unwrap();
     assert!(example_2.name_ambiguous());
+    assert_eq!(example_2.disambiguated_name(), format!("{} ({example_2_id})", example_2.name()));
     let example_3 = room.get_member_no_sync(example_3_id).await.unwrap().unwrap();
     assert!(example_3.name_ambiguous());
+    assert_eq!(example_3.disambiguated_name(), "example (@example3:localhost)");
 
     let changes = assert_next_matches!(updates, Ok(RoomUpdate::Joined { updates, .. }) => updates.ambiguity_changes);
 
@@ -1437,3 +1762,174 @@ async fn test_logout() {
     assert_matches!(res, Err(Error::OAuth(oauth_error)));
     assert_matches!(*oauth_error, OAuthError::Logout(OAuthTokenRevocationError::Url(_)));
 }
+
+#[async_test]
+async fn test_notification_action_for_event() {
+    let server = MatrixMockServer::new().await;
+    let client = server.client_builder().build().await;
+    let user_id = client.user_id().unwrap().to_owned();
+    let room_id = room_id!("!a:b.c");
+    let f = EventFactory::new().room(room_id).sender(user_id!("@bob:b.c"));
+
+    server
+        .mock_sync()
+        .ok_and_run(&client, |builder| {
+            builder.add_joined_room(
+                JoinedRoomBuilder::new(room_id).add_state_bulk([f
+                    .member(&user_id)
+                    .display_name("Alice")
+                    .into_raw_sync()
+                    .cast()]),
+            );
+            builder.add_global_account_data_event(GlobalAccountDataTestEvent::Custom(json!({
+                "type": "m.push_rules",
+                "content": {
+                    "global": {
+                        "override": [{
+                            "rule_id": ".m.rule.contains_display_name",
+                            "default": true,
+                            "enabled": true,
+                            "conditions": [{ "kind": "contains_display_name" }],
+                            "actions": ["notify", { "set_tweak": "highlight" }],
+                        }],
+                    },
+                },
+            })));
+        })
+        .await;
+
+    // A message mentioning the display name is highlighted.
+    let mention = f.text_msg("Hello Alice!").into_raw_sync();
+    assert_eq!(
+        client.notification_action_for_event(room_id, &mention).await.unwrap(),
+        NotificationAction::Highlight
+    );
+
+    // Another message doesn't notify.
+    let other = f.text_msg("Hello Bob!").into_raw_sync();
+    assert_eq!(
+        client.notification_action_for_event(room_id, &other).await.unwrap(),
+        NotificationAction::None
+    );
+
+    // Events from unknown rooms are rejected.
+    assert_matches!(
+        client.notification_action_for_event(room_id!("!unknown:b.c"), &other).await,
+        Err(Error::UnknownRoom(_))
+    );
+}
+
+#[async_test]
+async fn test_push_rules_mutations() {
+    let server = MatrixMockServer::new().await;
+    let client = server.client_builder().build().await;
+    let room_id = room_id!("!a:b.c");
+
+    Mock::given(method("PUT"))
+        .and(path("/_matrix/client/v3/pushrules/global/content/banana"))
+        .and(body_partial_json(json!({
+            "pattern": "banana",
+            "actions": ["notify", { "set_tweak": "sound", "value": "default" }],
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({})))
+        .expect(1)
+        .named("add keyword rule")
+        .mount(server.server())
+        .await;
+
+    client.add_keyword_rule("banana".to_owned()).await.unwrap();
+
+    Mock::given(method("PUT"))
+        .and(path_regex(r"^/_matrix/client/v3/pushrules/global/override/.*a:b\.c$"))
+        .and(body_partial_json(json!({
+            "conditions": [{ "kind": "event_match", "key": "room_id", "pattern": room_id }],
+            "actions": [],
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({})))
+        .expect(1)
+        .named("mute room rule")
+        .mount(server.server())
+        .await;
+
+    client.set_room_push_rule(room_id, RoomNotificationMode::Mute).await.unwrap();
+}
+
+#[cfg(feature = "e2e-encryption")]
+#[async_test]
+async fn test_search_messages() {
+    use std::sync::{Arc, Mutex};
+
+    use matrix_sdk::search::SearchRequest;
+
+    let server = MatrixMockServer::new().await;
+    let client = server.client_builder().build().await;
+    let user_id = client.user_id().unwrap().to_owned();
+
+    let room = server.sync_joined_room(&client, &DEFAULT_TEST_ROOM_ID).await;
+    server.mock_room_state_encryption().encrypted().mount().await;
+    server.mock_get_members().ok(Vec::new()).mock_once().mount().await;
+
+    // Send an encrypted message, and keep its content to be able to return it in
+    // the search results.
+    let encrypted_content = Arc::new(Mutex::new(None));
+    server
+        .mock_room_send()
+        .respond_with({
+            let encrypted_content = encrypted_content.clone();
+            move |request: &Request| {
+                let content: JsonValue = request.body_json().unwrap();
+                *encrypted_content.lock().unwrap() = Some(content);
+                ResponseTemplate::new(200).set_body_json(json!({ "event_id": "$encrypted" }))
+            }
+        })
+        .mock_once()
+        .mount()
+        .await;
+
+    room.send_raw("m.room.message", json!({ "body": "Hello", "msgtype": "m.text" })).await.unwrap();
+
+    let encrypted_event = Raw::new(&json!({
+        "type": "m.room.encrypted",
+        "room_id": room.room_id(),
+        "event_id": "$encrypted",
+        "origin_server_ts": 1600000u64,
+        "sender": user_id,
+        "content": encrypted_content.lock().unwrap().take().unwrap(),
+    }))
+    .unwrap()
+    .cast();
+
+    let plain_event = EventFactory::new()
+        .room(&DEFAULT_TEST_ROOM_ID)
+        .sender(user_id!("@bob:b.c"))
+        .text_msg("Hello there")
+        .event_id(event_id!("$plain"))
+        .into_raw_timeline();
+
+    server
+        .mock_search()
+        .match_search_term("Hello")
+        .ok(vec![plain_event, encrypted_event], Some("next_token"))
+        .mock_once()
+        .mount()
+        .await;
+
+    let results = client.search_messages(SearchRequest::new("Hello")).await.unwrap();
+
+    assert_eq!(results.next_batch.as_deref(), Some("next_token"));
+    assert_eq!(results.results.len(), 2);
+
+    let plain = &results.results[0].event;
+    assert_eq!(plain.event_id().as_deref(), Some(event_id!("$plain")));
+    assert!(plain.encryption_info().is_none());
+
+    // The encrypted match has been decrypted.
+    let decrypted = &results.results[1].event;
+    assert!(decrypted.encryption_info().is_some());
+    assert_let!(
+        Ok(ruma::events::AnySyncTimelineEvent::MessageLike(
+            ruma::events::AnySyncMessageLikeEvent::RoomMessage(message)
+        )) = decrypted.raw().deserialize()
+    );
+    assert_eq!(message.as_original().unwrap().content.body(), "Hello");
+}
@@ -42,8 +42,13 @@ use matrix_sdk_test::{async_test, JoinedRoomBuilder, SyncResponseBuilder};
 use ruma::{
     api::client::room::create_room::v3::Request as CreateRoomRequest,
     assign, device_id, event_id,
-    events::room::message::{RoomMessageEvent, RoomMessageEventContent},
-    owned_device_id, owned_user_id, room_id, user_id, EventId, RoomId, TransactionId,
+    events::room::{
+        encrypted::OriginalSyncRoomEncryptedEvent,
+        message::{RoomMessageEvent, RoomMessageEventContent},
+    },
+    owned_device_id, owned_user_id, room_id,
+    serde::Raw,
+    user_id, EventId, RoomId, TransactionId,
 };
 use serde_json::{json, Value};
 use tempfile::tempdir;
@@ -1498,6 +1503,103 @@ async fn test_enable_from_secret_storage_and_download_after_utd_from_old_message
     server.verify().await;
 }
 
+/// An event we couldn't decrypt is decrypted again, and sent to the
+/// redecrypted events stream, once its room key is received.
+#[async_test]
+async fn test_redecrypt_utd_after_room_key_import() {
+    let room_id = room_id!("!DovneieKSTkdHKpIXy:morpheus.localhost");
+    let event_id = event_id!("$JbFHtZpEJiH8uaajZjPLz0QUZc1xtBR9rPGBOjF6WFM");
+
+    let session = matrix_session_example2();
+    let (builder, server) = test_client_builder_with_server().await;
+    let client =
+        builder.request_config(RequestConfig::new().disable_retry()).build().await.unwrap();
+
+    client.restore_session(session).await.unwrap();
+
+    let sync = SyncResponseBuilder::new()
+        .add_joined_room(JoinedRoomBuilder::new(room_id))
+        .build_json_sync_response();
+    mock_sync(&server, sync, None).await;
+
+    client.sync_once(Default::default()).await.expect("We should be able to sync with the server");
+
+    // Create an outbound group session which we will use to encrypt a test event.
+    let sender_identity_keys = IdentityKeys {
+        ed25519: Ed25519SecretKey::new().public_key(),
+        curve25519: Curve25519PublicKey::from(&Curve25519SecretKey::new()),
+    };
+    let outbound_group_session = OutboundGroupSession::new(
+        device_id!("KIUVQQSDTM").to_owned(),
+        Arc::new(sender_identity_keys),
+        room_id,
+        matrix_sdk::crypto::EncryptionSettings::default(),
+    )
+    .unwrap();
+    let inbound_group_session = inbound_session_from_outbound_session(
+        sender_identity_keys.ed25519,
+        room_id,
+        &outbound_group_session,
+    )
+    .await
+    .unwrap();
+
+    let event_body = json!({"body":"tt","msgtype":"m.text"});
+    let encrypted_event_content = serde_json::to_value(
+        outbound_group_session
+            .encrypt("m.room.message", &serde_json::from_value(event_body).unwrap())
+            .await,
+    )
+    .unwrap();
+    let event: Raw<OriginalSyncRoomEncryptedEvent> = Raw::new(&json!({
+        "content": encrypted_event_content,
+        "event_id": event_id,
+        "origin_server_ts": 1698579035927u64,
+        "sender": "@example2:morpheus.localhost",
+        "type": "m.room.encrypted",
+    }))
+    .unwrap()
+    .cast();
+
+    let redecrypted_events = client.encryption().redecrypted_events_stream();
+    pin_mut!(redecrypted_events);
+
+    // We don't have the room key yet, so the decryption fails.
+    let room = client.get_room(room_id).expect("We should have access to the room after the sync");
+    let utd = room.decrypt_event(&event, None).await.unwrap();
+    assert_matches!(utd.encryption_info(), None, "We shouldn't be able to decrypt the event yet");
+    assert!(redecrypted_events.next().now_or_never().is_none());
+
+    // Now, import the room key.
+    {
+        let machine_guard = client.olm_machine_for_testing().await;
+        let olm_machine = machine_guard.as_ref().unwrap();
+        olm_machine
+            .store()
+            .import_room_keys(vec![inbound_group_session.export().await], None, |_, _| ())
+            .await
+            .expect("should be able to import room key");
+    }
+
+    // The event is decrypted again, and sent to the stream.
+    let redecrypted = timeout(redecrypted_events.next(), Duration::from_secs(5))
+        .await
+        .expect("did not get a redecrypted event within 5 seconds")
+        .expect("redecrypted_events.next() returned None")
+        .expect("redecrypted_events.next() returned an error");
+
+    assert_eq!(redecrypted.room_id, room_id);
+    assert_matches!(redecrypted.event.encryption_info(), Some(..));
+    assert_eq!(redecrypted.event.event_id().as_deref(), Some(event_id));
+    let event: RoomMessageEvent = redecrypted
+        .event
+        .raw()
+        .deserialize_as()
+        .expect("We should be able to deserialize the event");
+    let event = event.as_original().unwrap();
+    assert_eq!(event.content.body(), "tt");
+}
+
 /// Set up secret storage, and allow the client to import the backup
 /// decryption key from 4S.
 async fn init_client_secret_storage_and_backup(client: &Client, server: &wiremock::MockServer) {
@@ -6,7 +6,7 @@ use matrix_sdk::{
     encryption::secret_storage::SecretStorageError,
     test_utils::{client::mock_session_tokens, no_retry_test_client_with_server},
 };
-use matrix_sdk_base::SessionMeta;
+use matrix_sdk_base::{crypto::store::types::BackupDecryptionKey, SessionMeta};
 use matrix_sdk_test::async_test;
 use ruma::{
     device_id,
@@ -566,6 +566,181 @@ async fn test_restore_cross_signing_from_secret_store() {
     server.verify().await;
 }
 
+#[async_test]
+async fn test_list_and_import_single_secret() {
+    let (client, server) = logged_in_client_with_server().await;
+
+    mock_secret_store_key(
+        &server,
+        client.user_id().unwrap(),
+        "bmur2d9ypPUH1msSwCxQOJkuKRmJI55e",
+        "xv5b6/p3ExEw++wTyfSHEg==",
+        "ujBBbXahnTAMkmPUX2/0+VTfUh63pGyVRuBcDMgmJC8=",
+    )
+    .await;
+
+    // The private cross-signing keys are in the secret store.
+    for (secret_name, ciphertext, iv, mac) in [
+        (
+            "m.cross_signing.master",
+            "lCRSSA1lChONEXj/8RyogsgAa8ouQwYDnLr4XBCheRikrZykLRzPCx3doCE=",
+            "bdfCwu+ECYgZ/jWTkGrQ/A==",
+            "NXeV1dZaOe2JLvQ6Hh6tFto7AgFFdaQnY0l9pruwdtE=",
+        ),
+        (
+            "m.cross_signing.self_signing",
+            "+B9WD02IvtQ8S4OaquhuYEZAx20xvz0oTN7r2VM9VOBxmlOyi+KkkWOvLAo=",
+            "3BCaKGCaSMkg1x9WnTqUmw==",
+            "xQEDxQbPH0bYeZUFC3wYJh0lsLkP2amcFGdaZ3VdfQg=",
+        ),
+        (
+            "m.cross_signing.user_signing",
+            "atqNy5IDzYRkRC+lkKoflwsyHkd0dr4UeoViwJdUzexiq0M8h1i8JMkADNg=",
+            "bjb1V2n9YmA8j31Z9muMqQ==",
+            "vusvNuV8Kkq50VxtC78oioofVBurnTTVEhiRyZkfu/4=",
+        ),
+    ] {
+        Mock::given(method("GET"))
+            .and(path(format!(
+                "_matrix/client/r0/user/@example:localhost/account_data/{secret_name}"
+            )))
+            .and(header("authorization", "Bearer 1234"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "encrypted": {
+                    "bmur2d9ypPUH1msSwCxQOJkuKRmJI55e": {
+                        "ciphertext": ciphertext,
+                        "iv": iv,
+                        "mac": mac,
+                    }
+                }
+            })))
+            .expect(1)
+            .named(format!("{secret_name} account data GET"))
+            .mount(&server)
+            .await;
+    }
+
+    let secret_store = client
+        .encryption()
+        .secret_storage()
+        .open_secret_store(SECRET_STORE_KEY)
+        .await
+        .expect("We should be able to open our secret store");
+
+    // Put the backup recovery key in the secret store too.
+    let uploaded_content: Arc<Mutex<Option<SecretEventContent>>> = Mutex::new(None).into();
+
+    {
+        let _guard = Mock::given(method("GET"))
+            .and(path("_matrix/client/r0/user/@example:localhost/account_data/m.megolm_backup.v1"))
+            .and(header("authorization", "Bearer 1234"))
+            .respond_with(ResponseTemplate::new(404).set_body_json(json!({
+                "errcode": "M_NOT_FOUND",
+                "error": "Account data not found"
+            })))
+            .expect(1)
+            .named("m.megolm_backup.v1 account data GET")
+            .mount_as_scoped(&server)
+            .await;
+
+        let put_matcher = {
+            let uploaded_content = uploaded_content.to_owned();
+
+            move |request: &wiremock::Request| {
+                let content: SecretEventContent =
+                    request.body_json().expect("The request body should be a SecretEventContent");
+
+                *uploaded_content.lock().unwrap() = Some(content);
+
+                true
+            }
+        };
+
+        let _guard = Mock::given(method("PUT"))
+            .and(path("_matrix/client/r0/user/@example:localhost/account_data/m.megolm_backup.v1"))
+            .and(header("authorization", "Bearer 1234"))
+            .and(put_matcher)
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({})))
+            .expect(1)
+            .named("m.megolm_backup.v1 account data PUT")
+            .mount_as_scoped(&server)
+            .await;
+
+        let backup_key = BackupDecryptionKey::new().unwrap().to_base64();
+        secret_store
+            .put_secret(SecretName::RecoveryKey, &backup_key)
+            .await
+            .expect("We should be able to store the backup recovery key");
+    }
+
+    let uploaded_content = uploaded_content.lock().unwrap().take().unwrap();
+
+    Mock::given(method("GET"))
+        .and(path("_matrix/client/r0/user/@example:localhost/account_data/m.megolm_backup.v1"))
+        .and(header("authorization", "Bearer 1234"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(serde_json::to_value(uploaded_content).unwrap()),
+        )
+        .expect(2)
+        .named("m.megolm_backup.v1 account data GET")
+        .mount(&server)
+        .await;
+
+    // All the well-known secrets are listed.
+    let secrets = secret_store.list_secrets().await.unwrap();
+    assert_eq!(
+        secrets,
+        [
+            SecretName::CrossSigningMasterKey,
+            SecretName::CrossSigningSelfSigningKey,
+            SecretName::CrossSigningUserSigningKey,
+            SecretName::RecoveryKey,
+        ]
+    );
+
+    // Importing the backup recovery key tries to enable backups, there's no
+    // backup on the server though.
+    Mock::given(method("GET"))
+        .and(path("_matrix/client/unstable/room_keys/version"))
+        .and(header("authorization", "Bearer 1234"))
+        .respond_with(ResponseTemplate::new(404).set_body_json(json!({
+            "errcode": "M_NOT_FOUND",
+            "error": "No current backup version"
+        })))
+        .expect(1)
+        .named("room_keys/version GET")
+        .mount(&server)
+        .await;
+
+    secret_store
+        .import_secret(SecretName::RecoveryKey)
+        .await
+        .expect("We should be able to import the backup recovery key");
+
+    // The cross-signing keys haven't been imported.
+    let status = client
+        .encryption()
+        .cross_signing_status()
+        .await
+        .expect("We should be able to check our cross-signing status");
+    assert!(!status.has_master);
+    assert!(!status.has_self_signing);
+    assert!(!status.has_user_signing);
+
+    // Secrets that the SDK doesn't know how to import are rejected.
+    let err = secret_store
+        .import_secret(SecretName::from("org.example.custom_secret"))
+        .await
+        .expect_err("We shouldn't be able to import an unknown secret");
+    assert_matches!(
+        err,
+        SecretStorageError::UnsupportedSecret(name) if name.as_str() == "org.example.custom_secret"
+    );
+
+    server.verify().await;
+}
+
 #[async_test]
 async fn test_is_secret_storage_enabled() {
     let user_id = user_id!("@example:morpheus.localhost");
@@ -1,10 +1,10 @@
 // The http mocking library is not supported for wasm32
 #![cfg(not(target_family = "wasm"))]
-use matrix_sdk::test_utils::logged_in_client_with_server;
+use matrix_sdk::test_utils::{logged_in_client_with_server, mocks::MatrixMockServer};
 use serde::Serialize;
 use wiremock::{
     matchers::{header, method, path, query_param, query_param_is_missing},
-    Mock, MockServer, ResponseTemplate,
+    Mock, MockServer, Request, ResponseTemplate,
 };
 
 mod account;
@@ -43,3 +43,16 @@ async fn mock_sync(server: &MockServer, response_body: impl Serialize, since: Op
         .mount(server)
         .await;
 }
+
+/// Get the `PUT /rooms/{room_id}/send/{event_type}/{txn_id}` requests received
+/// by the given server, in the order they were received.
+async fn received_send_requests(server: &MatrixMockServer) -> Vec<Request> {
+    server
+        .server()
+        .received_requests()
+        .await
+        .expect("request recording should be enabled")
+        .into_iter()
+        .filter(|request| request.url.path().contains("/send/"))
+        .collect()
+}
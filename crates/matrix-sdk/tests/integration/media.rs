@@ -1,6 +1,6 @@
 use matrix_sdk::{
     config::RequestConfig,
-    media::{MediaFormat, MediaRequestParameters, MediaThumbnailSettings},
+    media::{AvatarSize, MediaFormat, MediaRequestParameters, MediaThumbnailSettings},
     store::RoomLoadSettings,
     test_utils::{client::mock_matrix_session, logged_in_client_with_server},
     Client,
@@ -453,3 +453,161 @@ async fn test_async_media_upload() {
         .await
         .unwrap();
 }
+
+#[async_test]
+async fn test_get_avatar() {
+    let (client, server) = logged_in_client_with_server().await;
+
+    // The client will call this endpoint to get the list of unstable features.
+    Mock::given(method("GET"))
+        .and(path("/_matrix/client/versions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "versions": ["r0.6.1"],
+        })))
+        .named("versions")
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let media = client.media();
+    let source = MediaSource::Plain(owned_mxc_uri!("mxc://example.org/avatar"));
+
+    for (size, expected_method, expected_width, expected_height) in [
+        (AvatarSize::Small, "crop", "32", "32"),
+        (AvatarSize::Medium, "crop", "96", "96"),
+        (AvatarSize::Large, "scale", "320", "240"),
+    ] {
+        let _mock_guard = Mock::given(method("GET"))
+            .and(path("/_matrix/media/r0/thumbnail/example.org/avatar"))
+            .and(query_param("method", expected_method))
+            .and(query_param("width", expected_width))
+            .and(query_param("height", expected_height))
+            .respond_with(ResponseTemplate::new(200).set_body_raw("avatardata", "image/jpeg"))
+            .named("get_avatar_thumbnail")
+            .expect(1)
+            .mount_as_scoped(&server)
+            .await;
+
+        // The first request reaches the homeserver, the second one uses the cache.
+        for _ in 0..2 {
+            assert_eq!(
+                media.get_avatar(source.clone(), size).await.unwrap().as_deref(),
+                Some(b"avatardata".as_slice())
+            );
+        }
+    }
+
+    // An unknown avatar is not an error.
+    Mock::given(method("GET"))
+        .and(path("/_matrix/media/r0/thumbnail/example.org/unknown"))
+        .respond_with(ResponseTemplate::new(404).set_body_json(json!({
+            "errcode": "M_NOT_FOUND",
+            "error": "Not found",
+        })))
+        .named("get_unknown_avatar_thumbnail")
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let source = MediaSource::Plain(owned_mxc_uri!("mxc://example.org/unknown"));
+    assert!(media.get_avatar(source, AvatarSize::Small).await.unwrap().is_none());
+}
+
+#[async_test]
+async fn test_get_url_preview_no_auth() {
+    let (client, server) = logged_in_client_with_server().await;
+
+    // The client will call this endpoint to get the list of unstable features.
+    Mock::given(method("GET"))
+        .and(path("/_matrix/client/versions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "versions": ["r0.6.1"],
+        })))
+        .named("versions")
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    Mock::given(method("GET"))
+        .and(path("/_matrix/media/r0/preview_url"))
+        .and(query_param("url", "https://matrix.org/"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "og:title": "Matrix.org",
+            "og:image": "mxc://example.org/preview",
+        })))
+        .named("preview_url")
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let media = client.media();
+
+    // The first request reaches the homeserver, the second one uses the cache.
+    for _ in 0..2 {
+        let preview = media.get_url_preview("https://matrix.org/").await.unwrap();
+        assert_eq!(preview.title.as_deref(), Some("Matrix.org"));
+        assert_eq!(preview.description, None);
+        assert_eq!(preview.image, Some(owned_mxc_uri!("mxc://example.org/preview")));
+    }
+}
+
+#[async_test]
+async fn test_get_url_preview_with_auth_matrix_1_11() {
+    // The server must advertise support for v1.11 for authenticated media support,
+    // so we make the request instead of assuming.
+    let server = wiremock::MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/_matrix/client/versions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "versions": ["v1.7", "v1.8", "v1.9", "v1.10", "v1.11"],
+        })))
+        .named("versions")
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    // Build client.
+    let client = Client::builder()
+        .homeserver_url(server.uri())
+        .request_config(RequestConfig::new().disable_retry())
+        .build()
+        .await
+        .unwrap();
+
+    // Restore session.
+    client
+        .matrix_auth()
+        .restore_session(mock_matrix_session(), RoomLoadSettings::default())
+        .await
+        .unwrap();
+
+    Mock::given(method("GET"))
+        .and(path("/_matrix/client/v1/media/preview_url"))
+        .and(query_param("url", "https://matrix.org/"))
+        .and(header("authorization", "Bearer 1234"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "og:title": "Matrix.org",
+            "og:description": "An open network for secure, decentralised communication",
+            "og:image": "mxc://example.org/preview",
+            "og:image:width": 1200,
+            "matrix:image:size": 102400,
+        })))
+        .named("preview_url")
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let media = client.media();
+
+    // The first request reaches the homeserver, the second one uses the cache.
+    for _ in 0..2 {
+        let preview = media.get_url_preview("https://matrix.org/").await.unwrap();
+        assert_eq!(preview.title.as_deref(), Some("Matrix.org"));
+        assert_eq!(
+            preview.description.as_deref(),
+            Some("An open network for secure, decentralised communication")
+        );
+        assert_eq!(preview.image, Some(owned_mxc_uri!("mxc://example.org/preview")));
+    }
+}
@@ -316,6 +316,74 @@ async fn test_refresh_token_handled_success() {
     assert_eq!(session_changes.try_recv(), Err(TryRecvError::Empty));
 }
 
+#[async_test]
+async fn test_refresh_token_handled_success_saves_tokens() {
+    let (builder, server) = test_client_builder_with_server().await;
+    let client = builder
+        .request_config(RequestConfig::new().disable_retry())
+        .server_versions([MatrixVersion::V1_3])
+        .handle_refresh_tokens()
+        .build()
+        .await
+        .unwrap();
+    let auth = client.matrix_auth();
+
+    let saved_tokens = Arc::new(Mutex::new(Vec::new()));
+    client
+        .set_session_callbacks(Box::new(|_| panic!("reload session never called")), {
+            let saved_tokens = saved_tokens.clone();
+            Box::new(move |client| {
+                saved_tokens.lock().unwrap().push(client.session_tokens().unwrap());
+                Ok(())
+            })
+        })
+        .unwrap();
+
+    auth.restore_session(session(), RoomLoadSettings::default()).await.unwrap();
+
+    Mock::given(method("POST"))
+        .and(path("/_matrix/client/v3/refresh"))
+        .and(body_partial_json(json!({
+            "refresh_token": "abcd",
+        })))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(&*test_json::REFRESH_TOKEN_WITH_REFRESH_TOKEN),
+        )
+        .expect(1)
+        .named("`POST /refresh`")
+        .mount(&server)
+        .await;
+
+    Mock::given(method("GET"))
+        .and(path("/_matrix/client/v3/account/whoami"))
+        .and(header(http::header::AUTHORIZATION, "Bearer 1234"))
+        .respond_with(
+            ResponseTemplate::new(401).set_body_json(&*test_json::UNKNOWN_TOKEN_SOFT_LOGOUT),
+        )
+        .expect(1)
+        .named("`GET /whoami` expired token")
+        .mount(&server)
+        .await;
+
+    Mock::given(method("GET"))
+        .and(path("/_matrix/client/v3/account/whoami"))
+        .and(header(http::header::AUTHORIZATION, "Bearer 9012"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(&*test_json::WHOAMI))
+        .expect(1)
+        .named("`GET /whoami` refreshed token")
+        .mount(&server)
+        .await;
+
+    // The request is retried with the new access token, after the tokens have been
+    // saved.
+    client.whoami().await.unwrap();
+
+    let saved_tokens = saved_tokens.lock().unwrap();
+    assert_eq!(saved_tokens.len(), 1);
+    assert_eq!(saved_tokens[0].access_token, "9012");
+    assert_eq!(saved_tokens[0].refresh_token.as_deref(), Some("wxyz"));
+}
+
 #[async_test]
 async fn test_refresh_token_handled_failure() {
     let (builder, server) = test_client_builder_with_server().await;
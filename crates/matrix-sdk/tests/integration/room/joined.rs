@@ -9,9 +9,15 @@ use assert_matches2::assert_let;
 use futures_util::{future::join_all, pin_mut};
 use matrix_sdk::{
     assert_next_with_timeout, assert_recv_with_timeout,
-    config::SyncSettings,
-    room::{edit::EditedContent, Receipts, ReportedContentScore, RoomMemberRole},
-    test_utils::mocks::MatrixMockServer,
+    config::{RequestConfig, SyncSettings},
+    room::{
+        edit::{EditError, EditedContent},
+        reaction::ReactionError,
+        MessagesOptions, Receipts, ReportedContentScore, RoomMemberRole,
+    },
+    test_utils::mocks::{
+        MatrixMockServer, RoomMessagesResponseTemplate, RoomRelationsResponseTemplate,
+    },
 };
 use matrix_sdk_base::{EncryptionState, RoomMembersUpdate, RoomState};
 use matrix_sdk_common::executor::spawn;
@@ -53,7 +59,7 @@ use wiremock::{
     Mock, ResponseTemplate,
 };
 
-use crate::{logged_in_client_with_server, mock_sync};
+use crate::{logged_in_client_with_server, mock_sync, received_send_requests};
 #[async_test]
 async fn test_invite_user_by_id() {
     let (client, server) = logged_in_client_with_server().await;
@@ -205,6 +211,119 @@ async fn test_leave_invited_room_with_no_permissions() -> Result<(), anyhow::Err
     Ok(())
 }
 
+#[async_test]
+async fn test_accept_invite() -> Result<(), anyhow::Error> {
+    let server = MatrixMockServer::new().await;
+    let client = server.client_builder().build().await;
+    let room_id = *DEFAULT_TEST_ROOM_ID;
+
+    server.mock_room_join().ok(room_id).mock_once().mount().await;
+
+    let invited_room_builder = InvitedRoomBuilder::new(room_id);
+    let room = server.sync_room(&client, invited_room_builder).await;
+
+    let joined_room = room.accept_invite().await?;
+
+    assert_eq!(joined_room.room_id(), room_id);
+    assert_eq!(joined_room.state(), RoomState::Joined);
+    assert_eq!(client.get_room(room_id).unwrap().state(), RoomState::Joined);
+
+    // The invite can't be accepted twice.
+    assert!(joined_room.accept_invite().await.is_err());
+
+    Ok(())
+}
+
+#[async_test]
+async fn test_reject_invite() -> Result<(), anyhow::Error> {
+    let server = MatrixMockServer::new().await;
+    let client = server.client_builder().build().await;
+    let room_id = *DEFAULT_TEST_ROOM_ID;
+
+    server.mock_room_leave().match_reason("Not interested").ok(room_id).mock_once().mount().await;
+    server.mock_room_forget().ok().mock_once().mount().await;
+
+    let invited_room_builder = InvitedRoomBuilder::new(room_id);
+    let room = server.sync_room(&client, invited_room_builder).await;
+
+    room.reject_invite(Some("Not interested".to_owned())).await?;
+
+    assert_eq!(room.state(), RoomState::Left);
+
+    // The room has been forgotten.
+    assert!(client.get_room(room_id).is_none());
+
+    Ok(())
+}
+
+#[async_test]
+async fn test_reject_invite_of_joined_room() {
+    let server = MatrixMockServer::new().await;
+    let client = server.client_builder().build().await;
+    let room_id = *DEFAULT_TEST_ROOM_ID;
+
+    server.mock_room_leave().ok(room_id).never().mount().await;
+
+    let room = server.sync_joined_room(&client, room_id).await;
+
+    assert!(room.reject_invite(None).await.is_err());
+    assert_eq!(room.state(), RoomState::Joined);
+}
+
+#[async_test]
+async fn test_leave_and_forget() {
+    let server = MatrixMockServer::new().await;
+    let client = server.client_builder().build().await;
+    let room_id = *DEFAULT_TEST_ROOM_ID;
+
+    server.mock_room_leave().ok(room_id).mock_once().mount().await;
+    server.mock_room_forget().ok().mock_once().mount().await;
+
+    let room = server.sync_joined_room(&client, room_id).await;
+
+    room.leave_and_forget().await.unwrap();
+
+    assert_eq!(room.state(), RoomState::Left);
+    assert!(client.get_room(room_id).is_none());
+    assert!(client.rooms().is_empty());
+}
+
+#[async_test]
+async fn test_leave_rooms() {
+    let server = MatrixMockServer::new().await;
+    let client = server.client_builder().build().await;
+
+    let first_room_id = room_id!("!first:example.org");
+    let failing_room_id = room_id!("!failing:example.org");
+    let last_room_id = room_id!("!last:example.org");
+    let unknown_room_id = room_id!("!unknown:example.org");
+
+    // The more specific mock must be mounted first to take precedence.
+    server.mock_room_leave().for_room(failing_room_id).error500().mock_once().mount().await;
+    server.mock_room_leave().ok(first_room_id).expect(2).mount().await;
+    server.mock_room_forget().ok().expect(2).mount().await;
+
+    server.sync_joined_room(&client, first_room_id).await;
+    server.sync_joined_room(&client, failing_room_id).await;
+    server.sync_joined_room(&client, last_room_id).await;
+
+    let results =
+        client.leave_rooms(&[first_room_id, failing_room_id, unknown_room_id, last_room_id]).await;
+
+    assert_eq!(results.len(), 4);
+    assert!(results[0].is_ok());
+    assert!(results[1].is_err());
+    assert_matches!(&results[2], Err(matrix_sdk::Error::UnknownRoom(room_id)) => {
+        assert_eq!(room_id, unknown_room_id);
+    });
+    assert!(results[3].is_ok());
+
+    // Only the room that couldn't be left is still known.
+    assert!(client.get_room(first_room_id).is_none());
+    assert!(client.get_room(last_room_id).is_none());
+    assert_eq!(client.get_room(failing_room_id).unwrap().state(), RoomState::Joined);
+}
+
 #[async_test]
 async fn test_ban_user() {
     let (client, server) = logged_in_client_with_server().await;
@@ -549,6 +668,77 @@ async fn test_fetch_members_deduplication() {
     join_all(tasks).await;
 }
 
+#[async_test]
+async fn test_sync_members_only_once() {
+    let server = MatrixMockServer::new().await;
+    let client = server.client_builder().build().await;
+    let user_id = user_id!("@alice:b.c");
+
+    let room = server.sync_joined_room(&client, &DEFAULT_TEST_ROOM_ID).await;
+    assert!(!room.are_members_synced());
+    assert!(room.get_member_no_sync(user_id).await.unwrap().is_none());
+
+    let f = EventFactory::new().room(&DEFAULT_TEST_ROOM_ID);
+    let member_event =
+        f.member(user_id).membership(MembershipState::Join).into_raw_timeline().cast();
+
+    // The members are only requested once.
+    server.mock_get_members().ok(vec![member_event]).mock_once().mount().await;
+
+    room.sync_members().await.unwrap();
+    assert!(room.are_members_synced());
+    assert!(room.get_member_no_sync(user_id).await.unwrap().is_some());
+
+    // The second call doesn't hit the server.
+    room.sync_members().await.unwrap();
+    assert!(room.get_member_no_sync(user_id).await.unwrap().is_some());
+}
+
+#[async_test]
+async fn test_messages_backward_pagination() {
+    let server = MatrixMockServer::new().await;
+    let client = server.client_builder().build().await;
+
+    let room = server.sync_joined_room(&client, &DEFAULT_TEST_ROOM_ID).await;
+    let f = EventFactory::new().room(&DEFAULT_TEST_ROOM_ID).sender(user_id!("@bob:b.c"));
+
+    // The second, and last, batch of events.
+    server
+        .mock_room_messages()
+        .match_from("token1")
+        .ok(RoomMessagesResponseTemplate::default()
+            .events(vec![f.text_msg("first").event_id(event_id!("$1"))]))
+        .mock_once()
+        .mount()
+        .await;
+    // The first batch of events.
+    server
+        .mock_room_messages()
+        .ok(RoomMessagesResponseTemplate::default()
+            .events(vec![
+                f.text_msg("third").event_id(event_id!("$3")),
+                f.text_msg("second").event_id(event_id!("$2")),
+            ])
+            .end_token("token1"))
+        .mock_once()
+        .mount()
+        .await;
+
+    let messages = room.messages(MessagesOptions::backward()).await.unwrap();
+    assert_eq!(messages.chunk.len(), 2);
+    assert_eq!(messages.chunk[0].event_id().as_deref(), Some(event_id!("$3")));
+    assert_eq!(messages.chunk[1].event_id().as_deref(), Some(event_id!("$2")));
+    assert!(messages.has_more());
+
+    let options = MessagesOptions::backward().from(messages.end.as_deref());
+    let messages = room.messages(options).await.unwrap();
+    assert_eq!(messages.chunk.len(), 1);
+    assert_eq!(messages.chunk[0].event_id().as_deref(), Some(event_id!("$1")));
+
+    // The start of the room has been reached.
+    assert!(!messages.has_more());
+}
+
 #[async_test]
 async fn test_set_name() {
     let server = MatrixMockServer::new().await;
@@ -1004,6 +1194,346 @@ async fn test_make_reply_event_doesnt_require_event_cache() {
     room.make_edit_event(event_id, EditedContent::RoomMessage(new_content)).await.unwrap();
 }
 
+#[async_test]
+async fn test_edit_message() {
+    let mock = MatrixMockServer::new().await;
+    let client = mock.client_builder().build().await;
+    let user_id = client.user_id().unwrap().to_owned();
+
+    let room_id = room_id!("!galette:saucisse.bzh");
+    let room = mock.sync_joined_room(&client, room_id).await;
+
+    let event_id = event_id!("$1");
+    let f = EventFactory::new();
+    mock.mock_room_event()
+        .ok(f.text_msg("hi").event_id(event_id).sender(&user_id).room(room_id).into_event())
+        .expect(1)
+        .named("/event")
+        .mount()
+        .await;
+    mock.mock_room_state_encryption().plain().mount().await;
+    mock.mock_room_send()
+        .body_matches_partial_json(json!({
+            "msgtype": "m.text",
+            "body": "* bonjour",
+            "m.new_content": {
+                "msgtype": "m.text",
+                "body": "bonjour",
+            },
+            "m.relates_to": {
+                "rel_type": "m.replace",
+                "event_id": event_id,
+            },
+        }))
+        .ok(event_id!("$edit"))
+        .mock_once()
+        .mount()
+        .await;
+
+    let edit_event_id =
+        room.edit_message(event_id, RoomMessageEventContent::text_plain("bonjour")).await.unwrap();
+    assert_eq!(edit_event_id, event_id!("$edit"));
+}
+
+#[async_test]
+async fn test_edit_message_from_other_user() {
+    let mock = MatrixMockServer::new().await;
+    let client = mock.client_builder().build().await;
+
+    let room_id = room_id!("!galette:saucisse.bzh");
+    let room = mock.sync_joined_room(&client, room_id).await;
+
+    let event_id = event_id!("$1");
+    let f = EventFactory::new();
+    mock.mock_room_event()
+        .ok(f
+            .text_msg("hi")
+            .event_id(event_id)
+            .sender(user_id!("@bob:b.c"))
+            .room(room_id)
+            .into_event())
+        .mount()
+        .await;
+    mock.mock_room_send().ok(event_id!("$edit")).never().mount().await;
+
+    let err = room
+        .edit_message(event_id, RoomMessageEventContent::text_plain("bonjour"))
+        .await
+        .unwrap_err();
+    assert_matches!(err, matrix_sdk::Error::EditError(EditError::NotAuthor));
+}
+
+#[async_test]
+async fn test_send_reply() {
+    let mock = MatrixMockServer::new().await;
+    let client = mock.client_builder().build().await;
+
+    let room_id = room_id!("!galette:saucisse.bzh");
+    let room = mock.sync_joined_room(&client, room_id).await;
+
+    let event_id = event_id!("$1");
+    let f = EventFactory::new();
+    mock.mock_room_event()
+        .ok(f
+            .text_msg("hi")
+            .event_id(event_id)
+            .sender(user_id!("@bob:b.c"))
+            .room(room_id)
+            .into_event())
+        .mount()
+        .await;
+    mock.mock_room_state_encryption().plain().mount().await;
+    mock.mock_room_send()
+        .body_matches_partial_json(json!({
+            "msgtype": "m.text",
+            // No reply fallback is prepended to the body.
+            "body": "hello",
+            "m.relates_to": {
+                "m.in_reply_to": {
+                    "event_id": event_id,
+                },
+            },
+        }))
+        .ok(event_id!("$reply"))
+        .mock_once()
+        .mount()
+        .await;
+
+    let reply_event_id = room
+        .send_reply(RoomMessageEventContentWithoutRelation::text_plain("hello"), event_id, None)
+        .await
+        .unwrap();
+    assert_eq!(reply_event_id, event_id!("$reply"));
+}
+
+#[async_test]
+async fn test_send_reaction() {
+    let mock = MatrixMockServer::new().await;
+    let client = mock.client_builder().build().await;
+
+    let room_id = room_id!("!galette:saucisse.bzh");
+    let room = mock.sync_joined_room(&client, room_id).await;
+
+    let event_id = event_id!("$1");
+    let f = EventFactory::new();
+    mock.mock_room_relations()
+        .match_target_event(event_id.to_owned())
+        .ok(RoomRelationsResponseTemplate::default().events(vec![f
+            .reaction(event_id, "👍")
+            .sender(user_id!("@bob:b.c"))
+            .event_id(event_id!("$bob_reaction"))
+            .into_raw_timeline()]))
+        .mock_once()
+        .mount()
+        .await;
+    mock.mock_room_state_encryption().plain().mount().await;
+    mock.mock_room_send()
+        .body_matches_partial_json(json!({
+            "m.relates_to": {
+                "rel_type": "m.annotation",
+                "event_id": event_id,
+                "key": "👍",
+            },
+        }))
+        .ok(event_id!("$reaction"))
+        .mock_once()
+        .mount()
+        .await;
+
+    let reaction_event_id = room.send_reaction(event_id, "👍").await.unwrap();
+    assert_eq!(reaction_event_id, event_id!("$reaction"));
+}
+
+#[async_test]
+async fn test_send_duplicate_reaction() {
+    let mock = MatrixMockServer::new().await;
+    let client = mock.client_builder().build().await;
+    let user_id = client.user_id().unwrap().to_owned();
+
+    let room_id = room_id!("!galette:saucisse.bzh");
+    let room = mock.sync_joined_room(&client, room_id).await;
+
+    let event_id = event_id!("$1");
+    let f = EventFactory::new();
+    mock.mock_room_relations()
+        .match_target_event(event_id.to_owned())
+        .ok(RoomRelationsResponseTemplate::default().events(vec![f
+            .reaction(event_id, "👍")
+            .sender(&user_id)
+            .event_id(event_id!("$my_reaction"))
+            .into_raw_timeline()]))
+        .mount()
+        .await;
+    mock.mock_room_send().ok(event_id!("$reaction")).never().mount().await;
+    mock.mock_room_redact().ok(event_id!("$redaction")).mock_once().mount().await;
+
+    // Reacting twice with the same key is refused.
+    let err = room.send_reaction(event_id, "👍").await.unwrap_err();
+    assert_matches!(err, matrix_sdk::Error::ReactionError(ReactionError::AlreadyReacted));
+
+    // The existing reaction can be redacted, though.
+    room.redact_reaction(event_id, "👍").await.unwrap();
+
+    // But there's no reaction to redact for another key.
+    let err = room.redact_reaction(event_id, "👎").await.unwrap_err();
+    assert_matches!(err, matrix_sdk::Error::ReactionError(ReactionError::NotFound));
+}
+
+#[cfg(feature = "e2e-encryption")]
+#[async_test]
+async fn test_send_and_redact_reaction_in_encrypted_room() {
+    use matrix_sdk::{
+        crypto::{
+            olm::{InboundGroupSession, OutboundGroupSession, SenderData},
+            types::EventEncryptionAlgorithm,
+            EncryptionSettings,
+        },
+        room::IncludeRelations,
+    };
+    use ruma::{
+        events::{relation::RelationType, AnyTimelineEvent},
+        serde::Raw,
+    };
+    use vodozemac::{
+        olm::IdentityKeys, Curve25519PublicKey, Curve25519SecretKey, Ed25519SecretKey,
+    };
+
+    let mock = MatrixMockServer::new().await;
+    let client = mock.client_builder().build().await;
+    let user_id = client.user_id().unwrap().to_owned();
+
+    let room_id = room_id!("!galette:saucisse.bzh");
+    let room = mock.sync_joined_room(&client, room_id).await;
+    mock.mock_room_state_encryption().encrypted().mount().await;
+
+    // Encrypt our own reaction with a room key that we know about.
+    let sender_identity_keys = IdentityKeys {
+        ed25519: Ed25519SecretKey::new().public_key(),
+        curve25519: Curve25519PublicKey::from(&Curve25519SecretKey::new()),
+    };
+    let outbound_session = OutboundGroupSession::new(
+        client.device_id().unwrap().to_owned(),
+        Arc::new(sender_identity_keys),
+        room_id,
+        EncryptionSettings::default(),
+    )
+    .unwrap();
+    let inbound_session = InboundGroupSession::new(
+        outbound_session.sender_key(),
+        sender_identity_keys.ed25519,
+        room_id,
+        &outbound_session.session_key().await,
+        SenderData::unknown(),
+        EventEncryptionAlgorithm::MegolmV1AesSha2,
+        None,
+        false,
+    )
+    .unwrap();
+    client
+        .olm_machine_for_testing()
+        .await
+        .as_ref()
+        .unwrap()
+        .store()
+        .import_room_keys(vec![inbound_session.export().await], None, |_, _| ())
+        .await
+        .unwrap();
+
+    let event_id = event_id!("$1");
+    let encrypted_content = outbound_session
+        .encrypt(
+            "m.reaction",
+            &Raw::new(&json!({
+                "m.relates_to": {
+                    "rel_type": "m.annotation",
+                    "event_id": event_id,
+                    "key": "👍",
+                },
+            }))
+            .unwrap()
+            .cast(),
+        )
+        .await;
+    let encrypted_reaction: Raw<AnyTimelineEvent> = Raw::new(&json!({
+        "content": encrypted_content,
+        "event_id": "$my_reaction",
+        "origin_server_ts": 1698579035927u64,
+        "room_id": room_id,
+        "sender": user_id,
+        "type": "m.room.encrypted",
+    }))
+    .unwrap()
+    .cast();
+
+    // The annotations are requested without filtering on the event type, so the
+    // encrypted reaction is returned.
+    mock.mock_room_relations()
+        .match_target_event(event_id.to_owned())
+        .match_subrequest(IncludeRelations::RelationsOfType(RelationType::Annotation))
+        .ok(RoomRelationsResponseTemplate::default().events(vec![encrypted_reaction]))
+        .mount()
+        .await;
+    mock.mock_room_send().ok(event_id!("$reaction")).never().mount().await;
+    mock.mock_room_redact().ok(event_id!("$redaction")).mock_once().mount().await;
+
+    // The encrypted reaction is found, so reacting again with the same key is
+    // refused.
+    let err = room.send_reaction(event_id, "👍").await.unwrap_err();
+    assert_matches!(err, matrix_sdk::Error::ReactionError(ReactionError::AlreadyReacted));
+
+    // And the encrypted reaction can be redacted.
+    room.redact_reaction(event_id, "👍").await.unwrap();
+}
+
+#[async_test]
+async fn test_send_threaded_reply() {
+    let mock = MatrixMockServer::new().await;
+    let client = mock.client_builder().build().await;
+
+    let room_id = room_id!("!galette:saucisse.bzh");
+    let room = mock.sync_joined_room(&client, room_id).await;
+
+    let thread_root = event_id!("$root");
+    let event_id = event_id!("$1");
+    let f = EventFactory::new();
+    mock.mock_room_event()
+        .ok(f
+            .text_msg("hi")
+            .in_thread(thread_root, thread_root)
+            .event_id(event_id)
+            .sender(user_id!("@bob:b.c"))
+            .room(room_id)
+            .into_event())
+        .mount()
+        .await;
+    mock.mock_room_state_encryption().plain().mount().await;
+    mock.mock_room_send()
+        .body_matches_partial_json(json!({
+            "msgtype": "m.text",
+            "m.relates_to": {
+                "rel_type": "m.thread",
+                "event_id": thread_root,
+                "m.in_reply_to": {
+                    "event_id": event_id,
+                },
+            },
+        }))
+        .ok(event_id!("$reply"))
+        .mock_once()
+        .mount()
+        .await;
+
+    let reply_event_id = room
+        .send_reply(
+            RoomMessageEventContentWithoutRelation::text_plain("hello"),
+            event_id,
+            Some(thread_root),
+        )
+        .await
+        .unwrap();
+    assert_eq!(reply_event_id, event_id!("$reply"));
+}
+
 #[async_test]
 async fn test_enable_encryption_doesnt_stay_unknown() {
     let mock = MatrixMockServer::new().await;
@@ -1409,3 +1939,47 @@ async fn test_report_room() {
 
     room.report_room(Some(reason.to_owned())).await.unwrap();
 }
+
+#[async_test]
+async fn test_send_retry_reuses_transaction_id() {
+    let server = MatrixMockServer::new().await;
+    let client = server
+        .client_builder()
+        // Two attempts in total: the first one times out, the second one succeeds.
+        .request_config(RequestConfig::new().retry_limit(2).timeout(Duration::from_millis(200)))
+        .build()
+        .await;
+
+    server.mock_room_state_encryption().plain().mount().await;
+
+    let room_id = room_id!("!galette:saucisse.bzh");
+    let room = server.sync_joined_room(&client, room_id).await;
+
+    let event_id = event_id!("$deduplicated");
+
+    // The homeserver processes the first attempt, but answers too late…
+    server
+        .mock_room_send()
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(json!({ "event_id": event_id }))
+                .set_delay(Duration::from_secs(1)),
+        )
+        .mock_once()
+        .mount()
+        .await;
+    // …so the request is retried, and the homeserver returns the same event ID.
+    server.mock_room_send().ok(event_id).mock_once().mount().await;
+
+    let response = room.send(RoomMessageEventContent::text_plain("Hello world")).await.unwrap();
+    assert_eq!(response.event_id, event_id);
+
+    // Both attempts used the same transaction ID.
+    let send_paths: Vec<_> = received_send_requests(&server)
+        .await
+        .into_iter()
+        .map(|request| request.url.path().to_owned())
+        .collect();
+    assert_eq!(send_paths.len(), 2);
+    assert_eq!(send_paths[0], send_paths[1]);
+}
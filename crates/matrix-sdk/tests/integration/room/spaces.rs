@@ -1,14 +1,19 @@
 use std::time::Duration;
 
-use assert_matches2::assert_let;
+use assert_matches2::{assert_let, assert_matches};
 use futures_util::StreamExt;
-use matrix_sdk::{config::SyncSettings, room::ParentSpace, Client};
-use matrix_sdk_test::{async_test, test_json, DEFAULT_TEST_ROOM_ID};
+use matrix_sdk::{
+    config::SyncSettings, room::ParentSpace, test_utils::mocks::MatrixMockServer, Client, Error,
+};
+use matrix_sdk_test::{
+    async_test, event_factory::EventFactory, sync_state_event, test_json, JoinedRoomBuilder,
+    DEFAULT_TEST_ROOM_ID,
+};
 use once_cell::sync::Lazy;
-use ruma::{room_id, RoomId};
+use ruma::{event_id, events::StateEventType, owned_server_name, room_id, RoomId, RoomVersionId};
 use serde_json::{json, Value as JsonValue};
 use wiremock::{
-    matchers::{header, method, path_regex},
+    matchers::{body_json, header, method, path_regex},
     Mock, ResponseTemplate,
 };
 
@@ -405,3 +410,192 @@ async fn test_parent_space_powerlevel_too_low() {
     assert_let!(ParentSpace::Illegitimate(space) = spaces.first().unwrap());
     assert_eq!(space.room_id(), *DEFAULT_TEST_SPACE_ID);
 }
+
+#[async_test]
+async fn test_add_space_child() {
+    let server = MatrixMockServer::new().await;
+    let client = server.client_builder().build().await;
+    let own_user_id = client.user_id().unwrap();
+    let f = EventFactory::new();
+
+    let space_id = room_id!("!space:localhost");
+    let child_id = room_id!("!child:localhost");
+
+    let space = server
+        .sync_room(
+            &client,
+            JoinedRoomBuilder::new(space_id).add_state_event(sync_state_event!({
+                "content": {
+                    "creator": own_user_id,
+                    "room_version": "1",
+                    "type": "m.space",
+                },
+                "event_id": "$space_create",
+                "origin_server_ts": 151957878,
+                "sender": own_user_id,
+                "state_key": "",
+                "type": "m.room.create",
+            })),
+        )
+        .await;
+
+    // We created the child room, so we can add the reciprocal parent event.
+    server
+        .sync_room(
+            &client,
+            JoinedRoomBuilder::new(child_id)
+                .add_state_event(f.create(own_user_id, RoomVersionId::V1))
+                .add_state_event(f.member(own_user_id)),
+        )
+        .await;
+
+    server
+        .mock_room_send_state()
+        .for_type(StateEventType::SpaceChild)
+        .body_matches_partial_json(json!({
+            "via": ["localhost"],
+            "suggested": true,
+        }))
+        .ok(event_id!("$space_child"))
+        .mock_once()
+        .mount()
+        .await;
+
+    server
+        .mock_room_send_state()
+        .for_type(StateEventType::SpaceParent)
+        .body_matches_partial_json(json!({
+            "via": ["localhost"],
+        }))
+        .ok(event_id!("$space_parent"))
+        .mock_once()
+        .mount()
+        .await;
+
+    space.add_space_child(child_id, vec![owned_server_name!("localhost")], true).await.unwrap();
+}
+
+#[async_test]
+async fn test_add_space_child_parent_event_failure() {
+    let server = MatrixMockServer::new().await;
+    let client = server.client_builder().build().await;
+    let own_user_id = client.user_id().unwrap();
+    let f = EventFactory::new();
+
+    let space_id = room_id!("!space:localhost");
+    let child_id = room_id!("!child:localhost");
+
+    let space = server
+        .sync_room(
+            &client,
+            JoinedRoomBuilder::new(space_id).add_state_event(sync_state_event!({
+                "content": {
+                    "creator": own_user_id,
+                    "room_version": "1",
+                    "type": "m.space",
+                },
+                "event_id": "$space_create",
+                "origin_server_ts": 151957878,
+                "sender": own_user_id,
+                "state_key": "",
+                "type": "m.room.create",
+            })),
+        )
+        .await;
+
+    server
+        .sync_room(
+            &client,
+            JoinedRoomBuilder::new(child_id)
+                .add_state_event(f.create(own_user_id, RoomVersionId::V1))
+                .add_state_event(f.member(own_user_id)),
+        )
+        .await;
+
+    // Sending the reciprocal parent event fails…
+    server
+        .mock_room_send_state()
+        .for_type(StateEventType::SpaceParent)
+        .error500()
+        .mock_once()
+        .mount()
+        .await;
+
+    // …so the space isn't changed.
+    server
+        .mock_room_send_state()
+        .for_type(StateEventType::SpaceChild)
+        .ok(event_id!("$space_child"))
+        .never()
+        .mount()
+        .await;
+
+    space.add_space_child(child_id, vec![owned_server_name!("localhost")], true).await.unwrap_err();
+}
+
+#[async_test]
+async fn test_remove_space_child() {
+    let server = MatrixMockServer::new().await;
+    let client = server.client_builder().build().await;
+    let own_user_id = client.user_id().unwrap();
+
+    let space_id = room_id!("!space:localhost");
+    let child_id = room_id!("!child:localhost");
+
+    let space = server
+        .sync_room(
+            &client,
+            JoinedRoomBuilder::new(space_id).add_state_event(sync_state_event!({
+                "content": {
+                    "creator": own_user_id,
+                    "room_version": "1",
+                    "type": "m.space",
+                },
+                "event_id": "$space_create",
+                "origin_server_ts": 151957878,
+                "sender": own_user_id,
+                "state_key": "",
+                "type": "m.room.create",
+            })),
+        )
+        .await;
+
+    // The child event is replaced by an empty one.
+    Mock::given(method("PUT"))
+        .and(path_regex(r"^/_matrix/client/v3/rooms/.*/state/m.space.child/.*"))
+        .and(body_json(json!({})))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(json!({ "event_id": "$space_child" })),
+        )
+        .expect(1)
+        .mount(server.server())
+        .await;
+
+    // We're not in the child room, so its parent event can't be removed.
+    server
+        .mock_room_send_state()
+        .for_type(StateEventType::SpaceParent)
+        .ok(event_id!("$p"))
+        .never()
+        .mount()
+        .await;
+
+    space.remove_space_child(child_id).await.unwrap();
+}
+
+#[async_test]
+async fn test_space_child_in_non_space_room() {
+    let server = MatrixMockServer::new().await;
+    let client = server.client_builder().build().await;
+
+    let room = server.sync_joined_room(&client, room_id!("!room:localhost")).await;
+    let child_id = room_id!("!child:localhost");
+
+    server.mock_room_send_state().ok(event_id!("$state")).never().mount().await;
+
+    assert_matches!(
+        room.add_space_child(child_id, vec![owned_server_name!("localhost")], false).await,
+        Err(Error::NotASpace)
+    );
+    assert_matches!(room.remove_space_child(child_id).await, Err(Error::NotASpace));
+}
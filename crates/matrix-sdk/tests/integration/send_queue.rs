@@ -50,6 +50,8 @@ use tokio::{
 };
 use wiremock::{Request, ResponseTemplate};
 
+use crate::received_send_requests;
+
 /// Queues an attachment whenever the actual data/mime type etc. don't matter.
 ///
 /// Returns the filename, for sanity check purposes.
@@ -1530,6 +1532,82 @@ async fn test_unwedge_unrecoverable_errors() {
     assert_update!(watch => sent { txn=txn1, event_id=event_id!("$42") });
 }
 
+#[async_test]
+async fn test_cancel_send_by_transaction_id() {
+    let mock = MatrixMockServer::new().await;
+
+    // Mark the room as joined.
+    let room_id = room_id!("!a:b.c");
+    let client = mock.client_builder().build().await;
+    let room = mock.sync_joined_room(&client, room_id).await;
+
+    let q = room.send_queue();
+    let (local_echoes, mut watch) = q.subscribe().await.unwrap();
+    assert!(local_echoes.is_empty());
+
+    // Disable the queue, so the event stays in there.
+    client.send_queue().set_enabled(false).await;
+
+    q.send(RoomMessageEventContent::text_plain("hey").into()).await.unwrap();
+    let (txn, _) = assert_update!(watch => local echo { body = "hey" });
+
+    // Cancelling by transaction ID removes the local echo.
+    assert!(q.cancel_send(&txn).await.unwrap());
+    assert_update!(watch => cancelled { txn = txn });
+
+    let (local_echoes, _) = q.subscribe().await.unwrap();
+    assert!(local_echoes.is_empty());
+
+    // Cancelling again, or cancelling an unknown transaction, is a no-op.
+    assert!(!q.cancel_send(&txn).await.unwrap());
+    assert!(!q.cancel_send(&TransactionId::new()).await.unwrap());
+    assert!(watch.is_empty());
+}
+
+#[async_test]
+async fn test_retry_send_by_transaction_id() {
+    let mock = MatrixMockServer::new().await;
+
+    // Mark the room as joined.
+    let room_id = room_id!("!a:b.c");
+    let client = mock.client_builder().build().await;
+    let room = mock.sync_joined_room(&client, room_id).await;
+
+    let q = room.send_queue();
+    let (_, mut watch) = q.subscribe().await.unwrap();
+
+    mock.mock_room_state_encryption().plain().mount().await;
+
+    // Respond to the first /send with an unrecoverable error, then with an OK
+    // response.
+    mock.mock_room_send().error_too_large().mock_once().mount().await;
+    mock.mock_room_send().ok(event_id!("$42")).mock_once().mount().await;
+
+    q.send(RoomMessageEventContent::text_plain("i'm too big for ya").into()).await.unwrap();
+
+    let (txn, _) = assert_update!(watch => local echo { body = "i'm too big for ya" });
+    assert_update!(watch => error { recoverable=false, txn=txn });
+
+    // Re-enable the room queue, and retry the failed event by its transaction ID.
+    q.set_enabled(true);
+    assert!(q.retry_send(&txn).await.unwrap());
+
+    assert_update!(watch => retry { txn=txn });
+    assert_update!(watch => sent { txn=txn, event_id=event_id!("$42") });
+
+    // Both attempts used the original transaction ID.
+    let send_paths: Vec<_> = received_send_requests(&mock)
+        .await
+        .into_iter()
+        .map(|request| request.url.path().to_owned())
+        .collect();
+    assert_eq!(send_paths.len(), 2);
+    assert!(send_paths.iter().all(|path| path.ends_with(&format!("/{txn}"))));
+
+    // The event isn't in the queue anymore.
+    assert!(!q.retry_send(&txn).await.unwrap());
+}
+
 #[async_test]
 async fn test_no_network_access_error_is_recoverable() {
     // This is subtle, but for the `drop(server)` below to be effectful, it needs to
@@ -1663,6 +1741,82 @@ async fn test_reloading_rooms_with_unsent_events() {
     mock.verify_and_reset().await;
 }
 
+#[async_test]
+async fn test_reloading_unsent_events_keeps_order() {
+    let store = Arc::new(MemoryStore::new());
+
+    let room_id = room_id!("!a:b.c");
+
+    let server = wiremock::MockServer::start().await;
+    let mock = MatrixMockServer::from_server(server);
+
+    let client = mock
+        .client_builder()
+        .store_config(
+            StoreConfig::new("cross-process-store-locks-holder-name".to_owned())
+                .state_store(store.clone()),
+        )
+        .build()
+        .await;
+
+    let room = mock.sync_joined_room(&client, room_id).await;
+
+    // Queue a few messages while offline, i.e. with the send queue disabled.
+    client.send_queue().set_enabled(false).await;
+
+    for body in ["1", "2", "3"] {
+        room.send_queue().send(RoomMessageEventContent::text_plain(body).into()).await.unwrap();
+    }
+
+    let (local_echoes, _) = room.send_queue().subscribe().await.unwrap();
+    let txn_ids: Vec<_> = local_echoes.into_iter().map(|echo| echo.transaction_id).collect();
+    assert_eq!(txn_ids.len(), 3);
+
+    {
+        // Kill the client, let it close background tasks.
+        drop(room);
+        drop(client);
+        sleep(Duration::from_secs(1)).await;
+    }
+
+    // Restart with the same store: the local echoes are still there, in the same
+    // order.
+    let new_client = mock
+        .client_builder()
+        .store_config(
+            StoreConfig::new("cross-process-store-locks-holder-name".to_owned()).state_store(store),
+        )
+        .build()
+        .await;
+
+    // Stay offline until the endpoints are mounted.
+    new_client.send_queue().set_enabled(false).await;
+
+    let room = new_client.get_room(room_id).unwrap();
+    let (local_echoes, _) = room.send_queue().subscribe().await.unwrap();
+    let reloaded_txn_ids: Vec<_> =
+        local_echoes.into_iter().map(|echo| echo.transaction_id).collect();
+    assert_eq!(reloaded_txn_ids, txn_ids);
+
+    // Once back online, the events are sent in the order they were queued.
+    mock.mock_room_state_encryption().plain().mount().await;
+    mock.mock_room_send().ok(event_id!("$1")).mock_once().mount().await;
+    mock.mock_room_send().ok(event_id!("$2")).mock_once().mount().await;
+    mock.mock_room_send().ok(event_id!("$3")).mock_once().mount().await;
+
+    new_client.send_queue().set_enabled(true).await;
+
+    // Let the sending queue process events.
+    sleep(Duration::from_secs(1)).await;
+
+    let sent_bodies: Vec<_> = received_send_requests(&mock)
+        .await
+        .into_iter()
+        .map(|request| request.body_json::<serde_json::Value>().unwrap()["body"].clone())
+        .collect();
+    assert_eq!(sent_bodies, vec![json!("1"), json!("2"), json!("3")]);
+}
+
 #[async_test]
 async fn test_reactions() {
     let mock = MatrixMockServer::new().await;
@@ -56,6 +56,9 @@ pub struct SyncResponseBuilder {
     /// Internal counter to enable the `prev_batch` and `next_batch` of each
     /// sync response to vary.
     batch_counter: i64,
+    /// The `next_batch` token to use for the next sync response, instead of a
+    /// generated one.
+    next_batch: Option<String>,
     /// The device lists of the user.
     changed_device_lists: Vec<OwnedUserId>,
     to_device_events: Vec<Raw<AnyToDeviceEvent>>,
@@ -169,6 +172,16 @@ impl SyncResponseBuilder {
         self
     }
 
+    /// Set the `next_batch` token of the next sync response.
+    ///
+    /// By default, a token is generated from an internal counter, so that each
+    /// sync response has a different one. The token set here is only used for
+    /// the next sync response.
+    pub fn set_next_batch(&mut self, next_batch: impl Into<String>) -> &mut Self {
+        self.next_batch = Some(next_batch.into());
+        self
+    }
+
     /// Builds a sync response as a JSON Value containing the events we queued
     /// so far.
     ///
@@ -181,7 +194,7 @@ impl SyncResponseBuilder {
     /// response.
     pub fn build_json_sync_response(&mut self) -> JsonValue {
         self.batch_counter += 1;
-        let next_batch = self.generate_sync_token();
+        let next_batch = self.next_batch.take().unwrap_or_else(|| self.generate_sync_token());
 
         let body = json! {
             {